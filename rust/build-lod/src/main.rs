@@ -1,16 +1,22 @@
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 
-use spark_lib::{chunk_tree, sh_clustering};
+use glam::{Affine3A, Mat3A, Vec3A};
+
+use spark_lib::{chunk_tree, sh_clustering, transform};
 use spark_lib::decoder::{SplatEncoding, SplatGetter, SplatReceiver};
-use spark_lib::rad::RadEncoder;
+use spark_lib::rad::{RadCompression, RadEncoder, RadFilter, RAD_CHUNK_SIZE};
 use spark_lib::{
+    assemble::SplatAssembler,
     decoder::{ChunkReceiver, MultiDecoder},
+    encoder::MultiEncoder,
     gsplat::GsplatArray,
+    outliers::OutlierFilter,
     csplat::CsplatArray,
     tsplat::{Tsplat, TsplatMut, TsplatArray},
     tiny_lod,
     bhatt_lod,
+    sogs::SogsDirectoryResolver,
     spz::SpzEncoder,
 };
 
@@ -62,11 +68,17 @@ struct BuildLodOptions {
     min_box: Option<[f32; 3]>,
     max_box: Option<[f32; 3]>,
     within_dist: Option<([f32; 3], f32)>,
+    transform: Option<Affine3A>,
     skip_validate: bool,
     inflate: bool,
     cluster_sh: Option<usize>,
     cluster_sh_cpu: bool,
     cluster_sh_f16: Option<bool>,
+    rad_compression: Option<RadCompression>,
+    rad_filter: Option<RadFilter>,
+    merge: Option<String>,
+    outliers: OutlierFilter,
+    ply_extras: bool,
 }
 
 fn read_file_chunks(filename: &str, decoder: &mut impl ChunkReceiver) -> anyhow::Result<()> {
@@ -96,21 +108,65 @@ fn process_file_lod(filename: &str, options: &BuildLodOptions) {
     }
 }
 
-fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filename: &str, options: &BuildLodOptions, splats: TS) {
-    let mut decoder = MultiDecoder::new(splats, None, Some(&filename));
-    let mut splats = match read_file_chunks(&filename, &mut decoder) {
+fn read_splats<TS: SplatReceiver + TsplatArray + SplatGetter>(filename: &str, options: &BuildLodOptions, splats: TS) -> Option<TS> {
+    let resolver = Box::new(SogsDirectoryResolver::from_pathname(filename));
+    let mut decoder = MultiDecoder::new_with_resolver(splats, None, Some(filename), Some(resolver))
+        .with_ply_extras(options.ply_extras);
+    match read_file_chunks(filename, &mut decoder) {
         Ok(_) => {
             println!("Detected file type: {:?}", decoder.file_type.unwrap());
-            decoder.into_splats()
+            Some(decoder.into_splats())
         }
         Err(error) => {
             eprintln!("Decoding failed: {:?}", error);
+            None
+        }
+    }
+}
+
+fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filename: &str, options: &BuildLodOptions, splats: TS) {
+    if let Some(splats) = read_splats(filename, options, splats) {
+        process_splats_lod(filename, options, splats, serde_json::Map::new());
+    }
+}
+
+fn merge_files_lod(inputs: &[(String, Option<Affine3A>)], output: &str, options: &BuildLodOptions) {
+    match options.tsplat {
+        BuildLodTsplat::Gsplat => {
+            merge_files_lod_tsplat(inputs, output, options, GsplatArray::new)
+        },
+        BuildLodTsplat::Csplat => {
+            merge_files_lod_tsplat(inputs, output, options, || CsplatArray::new_encoding(options.splat_encoding.clone()))
+        }
+    }
+}
+
+fn merge_files_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(inputs: &[(String, Option<Affine3A>)], output: &str, options: &BuildLodOptions, new_splats: impl Fn() -> TS) {
+    let mut assembler = SplatAssembler::new();
+    for (filename, transform) in inputs {
+        println!("*** Reading: {}", filename);
+        let Some(splats) = read_splats(filename, options, new_splats()) else {
+            return;
+        };
+        assembler.push(splats, *transform);
+    }
+
+    let assembled = match assembler.assemble() {
+        Ok(assembled) => assembled,
+        Err(error) => {
+            eprintln!("Merging failed: {:?}", error);
             return;
         }
     };
+    println!("Merged {} files into {} splats", inputs.len(), assembled.splats.len());
 
     let mut description = serde_json::Map::new();
+    description.insert("merged_inputs".to_string(), serde_json::Value::Array(inputs.iter().map(|(filename, _)| filename.clone().into()).collect()));
+    description.insert("merged_input_counts".to_string(), serde_json::Value::Array(assembled.part_ranges.iter().map(|range| range.len().into()).collect()));
+    process_splats_lod(output, options, assembled.splats, description);
+}
 
+fn process_splats_lod<TS: SplatReceiver + TsplatArray + SplatGetter>(filename: &str, options: &BuildLodOptions, mut splats: TS, mut description: serde_json::Map<String, serde_json::Value>) {
     let input_splat_count = splats.len();
     let input_sh_degree = TsplatArray::max_sh_degree(&splats);
 
@@ -187,8 +243,17 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
         description.insert("within_dist_radius".to_string(), serde_json::Number::from_f64(dist as f64).into());
     }
 
+    if let Some(transform) = options.transform {
+        splats.apply_transform(transform);
+        let rows = (0..3).flat_map(|row| [transform.matrix3.row(row).to_array().to_vec(), vec![transform.translation[row]]].concat());
+        description.insert("transform".to_string(), serde_json::Value::Array(rows.map(|v| serde_json::Number::from_f64(v as f64).into()).collect()));
+    }
+
     let mut output_filename = filename.to_string();
-    if let Some(dot) = filename.rfind('.') {
+    if let Some(dir) = filename.strip_suffix("/meta.json") {
+        // Unzipped SOGS bundle: name output after its directory
+        output_filename = format!("{}-lod", dir);
+    } else if let Some(dot) = filename.rfind('.') {
         output_filename.replace_range(dot.., "-lod");
     } else {
         output_filename.push_str("-lod");
@@ -209,6 +274,20 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
         description.insert("unlod".to_string(), serde_json::Value::Bool(true));
     }
 
+    // After --unlod, so no child indices are left to orphan
+    if options.outliers.is_enabled() {
+        let report = match options.outliers.remove(&mut splats) {
+            Ok(report) => report,
+            Err(error) => {
+                eprintln!("Skipping {}: {}", filename, error);
+                return;
+            }
+        };
+        println!("Removed {} outlier splats (statistical: {}, radius: {}, size: {}), remaining splats.len={}", report.flagged, report.statistical, report.radius, report.size, splats.len());
+        description.insert("outlier_filter".to_string(), serde_json::to_value(&options.outliers).unwrap());
+        description.insert("outlier_counts".to_string(), serde_json::to_value(&report).unwrap());
+    }
+
     let method = match options.method.clone() {
         BuildLodMethod::Quick => BuildLodMethod::TinyLod { lod_base: 1.5 },
         BuildLodMethod::Quality => BuildLodMethod::BhattLod { lod_base: 1.75 },
@@ -320,6 +399,12 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
             if let Some(sh_clusters) = sh_clusters {
                 encoder = encoder.with_sh_clusters(sh_clusters);
             }
+            if let Some(compression) = options.rad_compression {
+                encoder = encoder.with_compression(compression);
+            }
+            if let Some(filter) = options.rad_filter {
+                encoder = encoder.with_filter(filter);
+            }
 
             let input_encoding = serde_json::json!({
                 "center": encoder.center_encoding,
@@ -330,6 +415,8 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
                 "sh": encoder.sh_encoding,
                 "encoding": encoder.encoding,
                 "sh_label": encoder.sh_label_encoding,
+                "compression": encoder.compression,
+                "filter": encoder.filter,
             });
             description.insert("input_encoding".to_string(), input_encoding);
 
@@ -354,33 +441,41 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
             let comment = serde_json::to_string_pretty(&description).unwrap();
             println!("Comment: {}", comment);
             let mut encoder = encoder.with_comment(comment);
-            
+
+            // Stream chunks out of the array so the encoded file is never held in memory
+            let splats = std::mem::replace(&mut encoder.getter, TS::new());
+            let num_splats = splats.len();
             let filename_ext = format!("{}.rad", output_filename);
-            let mut writer = BufWriter::new(File::create(&filename_ext).unwrap());
+            let writer = BufWriter::new(File::create(&filename_ext).unwrap());
+            let mut stream = encoder.into_stream(writer, num_splats).unwrap();
 
-            if options.output == BuildLodOutput::Rad {
-                encoder.encode(&mut writer).unwrap();
-            } else {
+            if options.output == BuildLodOutput::RadChunked {
                 let mut output_path = std::path::PathBuf::from(&output_filename);
                 let filename_only = output_path.file_name().unwrap().to_str().unwrap();
                 let chunk_prefix = format!("{}-", filename_only);
-                let chunks = encoder.encode_with_chunks(&mut writer, &chunk_prefix).unwrap();
-                for (filename, chunk) in chunks {
-                    output_path.set_file_name(&filename);
-                    let mut chunk_writer = BufWriter::new(File::create(&output_path).unwrap());
-                    chunk_writer.write_all(&chunk).unwrap();
+                stream = stream.with_chunk_files(&chunk_prefix, move |filename, chunk| {
+                    output_path.set_file_name(filename);
+                    let mut chunk_writer = BufWriter::new(File::create(&output_path)?);
+                    chunk_writer.write_all(chunk)?;
                     println!("Wrote {} ({} bytes)", filename, chunk.len());
-                }
+                    Ok(())
+                });
             }
+
+            for chunk in 0..stream.num_chunks() {
+                let start = chunk * RAD_CHUNK_SIZE;
+                let count = (num_splats - start).min(RAD_CHUNK_SIZE);
+                stream.push_chunk(splats.clone_subset(start, count)).unwrap();
+            }
+            stream.finish().unwrap();
             println!("Wrote {}", filename_ext);
         },
         BuildLodOutput::Spz => {
-            let encoder = SpzEncoder::new(splats);
-            let bytes = encoder.encode().unwrap();
             let filename_ext = format!("{}.spz", output_filename);
+            let encoder = MultiEncoder::from_pathname(&filename_ext).unwrap();
             let mut writer = BufWriter::new(File::create(&filename_ext).unwrap());
-            writer.write_all(&bytes).unwrap();
-            println!("Wrote {} ({} bytes)", filename_ext, bytes.len());
+            encoder.encode(splats, &mut writer).unwrap();
+            println!("Wrote {}", filename_ext);
         },
         BuildLodOutput::SpzChunked => {
             let num_splats = splats.len();
@@ -401,6 +496,24 @@ fn process_file_lod_tsplat<TS: SplatReceiver + TsplatArray + SplatGetter>(filena
     }
 }
 
+// Row-major 3x4 matrix of comma-separated values
+fn parse_affine(values: &str) -> anyhow::Result<Affine3A> {
+    let values = values.split(",").map(|v| v.parse::<f32>()).collect::<Result<Vec<f32>, _>>()?;
+    if values.len() != 12 {
+        return Err(anyhow::anyhow!("expected 12 values, got {}", values.len()));
+    }
+    let linear = Mat3A::from_cols_array(&[
+        values[0], values[4], values[8],
+        values[1], values[5], values[9],
+        values[2], values[6], values[10],
+    ]);
+    let affine = Affine3A::from_mat3_translation(linear.into(), Vec3A::new(values[3], values[7], values[11]).into());
+    if !transform::is_invertible(&affine) {
+        return Err(anyhow::anyhow!("matrix is singular"));
+    }
+    Ok(affine)
+}
+
 fn show_usage_exit() {
     eprintln!("Usage: build-lod");
     eprintln!("  [--unlod]                                       // Remove LoD nodes with children from file");
@@ -409,15 +522,26 @@ fn show_usage_exit() {
     eprintln!("  [--tiny-lod[=<base>]] [--bhatt-lod[=<base>]]    // Use tiny-lod (default base 1.5) or bhatt-lod (default base 1.75) LoD method");
     eprintln!("  [--max-sh=<max-sh>]                             // Set maximum SH degree (default 3)");
     eprintln!("  [--rad] [--rad-chunked] [--spz] [--spz-chunked] // Output RAD (+chunked) or SPZ (+chunked) output files");
+    eprintln!("  [--rad-compression=<gz|lz4|arith|none|auto>]    // Compression codec for RAD properties (default gz)");
+    eprintln!("  [--rad-filter=<none|shuffle|bitshuffle|auto>]   // Byte transform applied before RAD compression (default none)");
     eprintln!("  [--min-box=<x>,<y>,<z>]                         // Crop input file to minimum bounding coord");
     eprintln!("  [--max-box=<x>,<y>,<z>]                         // Crop input file to maximum bounding coord");
     eprintln!("  [--within-dist=<x>,<y>,<z>,<radius>]            // Crop input file to within radius of a point");
+    eprintln!("  [--transform=<m00>,<m01>,<m02>,<tx>,...,<tz>]   // Apply row-major 3x4 affine transform after cropping");
+    eprintln!("  [--outlier-std=<ratio>]                         // Remove splats whose mean kNN distance is <ratio> std devs above average");
+    eprintln!("  [--outlier-radius=<radius>,<min-neighbors>]     // Remove splats with fewer than <min-neighbors> within <radius>");
+    eprintln!("  [--outlier-size=<ratio>]                        // Remove splats larger than <ratio> times the median of their kNN");
+    eprintln!("  [--outlier-k=<k>]                               // Neighbours for --outlier-std and --outlier-size (default 8)");
+    eprintln!("  [--outlier-max-opacity=<opacity>]               // Only remove outliers below this opacity");
+    eprintln!("  [--merge=<output>]                              // Merge all input files into one <output>-lod file");
+    eprintln!("  [--place=<m00>,<m01>,<m02>,<tx>,...,<tz>]       // Transform the next input file when merging");
+    eprintln!("  [--ply-extras]                                  // Keep non-standard PLY vertex properties as extras");
     eprintln!("  [--skip-validate]                               // Skip validation of input file");
     eprintln!("  [--inflate]                                     // Inflate scales to output normal splat opacity 0..1");
     eprintln!("  [--cluster-sh[=<iterations>]]                   // Cluster SH coefficients into <=64K codebook (default 10 iterations)");
     eprintln!("  [--cluster-sh-cpu[=<iterations>]]               // Cluster SH coefficients using CPU");
     eprintln!("  [--cluster-sh-f16[=auto,true,false]]            // Force GPU SH coefficients to use float16 (default if available)");
-    eprintln!("  <file.ply|file.spz|file.compressed.ply|file.splat|file.ksplat|file.sog|file.rad> [...] // Multiple input files and wildcards allowed");
+    eprintln!("  <file.ply|file.spz|file.compressed.ply|file.splat|file.ksplat|file.sog|dir/meta.json|file.rad> [...] // Multiple input files and wildcards allowed");
     std::process::exit(1);
 }
 
@@ -426,6 +550,8 @@ fn main() {
 
     let mut options = BuildLodOptions::default();
     let mut filenames = Vec::new();
+    let mut placements = Vec::new();
+    let mut place = None;
 
     for arg in args {
         if arg == "--unlod" {
@@ -524,6 +650,37 @@ fn main() {
             println!("Using --spz-chunked: Chunk SPZ file output");
             continue;
         }
+        if let Some(rest) = arg.strip_prefix("--rad-compression=") {
+            options.rad_compression = Some(match rest {
+                "gz" => RadCompression::Gz,
+                "lz4" => RadCompression::Lz4,
+                "arith" => RadCompression::Arith,
+                "none" => RadCompression::None,
+                "auto" => RadCompression::Auto,
+                _ => {
+                    eprintln!("Invalid --rad-compression value: {}", rest);
+                    show_usage_exit();
+                    unreachable!();
+                }
+            });
+            println!("Using --rad-compression={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--rad-filter=") {
+            options.rad_filter = Some(match rest {
+                "none" => RadFilter::None,
+                "shuffle" => RadFilter::Shuffle,
+                "bitshuffle" => RadFilter::BitShuffle,
+                "auto" => RadFilter::Auto,
+                _ => {
+                    eprintln!("Invalid --rad-filter value: {}", rest);
+                    show_usage_exit();
+                    unreachable!();
+                }
+            });
+            println!("Using --rad-filter={}", rest);
+            continue;
+        }
         if let Some(rest) = arg.strip_prefix("--min-box=") {
             let values = rest.split(",").map(|v| v.parse::<f32>().unwrap()).collect::<Vec<f32>>();
             if values.len() != 3 {
@@ -554,6 +711,92 @@ fn main() {
             println!("Using --within-dist={:?}", options.within_dist);
             continue;
         }
+        if let Some(rest) = arg.strip_prefix("--transform=") {
+            match parse_affine(rest) {
+                Ok(transform) => options.transform = Some(transform),
+                Err(err) => {
+                    eprintln!("Invalid --transform value {}: {}", rest, err);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --transform={:?}", options.transform);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--outlier-std=") {
+            match rest.parse::<f32>() {
+                Ok(v) => options.outliers.std_ratio = Some(v),
+                Err(_) => {
+                    eprintln!("Invalid --outlier-std value: {}", rest);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --outlier-std={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--outlier-radius=") {
+            match rest.split_once(",").and_then(|(radius, min)| Some((radius.parse::<f32>().ok()?, min.parse::<usize>().ok()?))) {
+                Some(radius) => options.outliers.radius = Some(radius),
+                None => {
+                    eprintln!("Invalid --outlier-radius value: {}", rest);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --outlier-radius={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--outlier-size=") {
+            match rest.parse::<f32>() {
+                Ok(v) => options.outliers.size_ratio = Some(v),
+                Err(_) => {
+                    eprintln!("Invalid --outlier-size value: {}", rest);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --outlier-size={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--outlier-k=") {
+            match rest.parse::<usize>() {
+                Ok(v) => options.outliers.k = v.max(1),
+                Err(_) => {
+                    eprintln!("Invalid --outlier-k value: {}", rest);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --outlier-k={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--outlier-max-opacity=") {
+            match rest.parse::<f32>() {
+                Ok(v) => options.outliers.max_opacity = v,
+                Err(_) => {
+                    eprintln!("Invalid --outlier-max-opacity value: {}", rest);
+                    show_usage_exit();
+                }
+            }
+            println!("Using --outlier-max-opacity={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--merge=") {
+            options.merge = Some(rest.to_string());
+            println!("Using --merge={}", rest);
+            continue;
+        }
+        if let Some(rest) = arg.strip_prefix("--place=") {
+            match parse_affine(rest) {
+                Ok(transform) => place = Some(transform),
+                Err(err) => {
+                    eprintln!("Invalid --place value {}: {}", rest, err);
+                    show_usage_exit();
+                }
+            }
+            continue;
+        }
+        if arg == "--ply-extras" {
+            options.ply_extras = true;
+            println!("Using --ply-extras: Keep non-standard PLY vertex properties as extras");
+            continue;
+        }
         if arg == "--skip-validate" {
             options.skip_validate = true;
             println!("Using --skip-validate: Skip validation of input file");
@@ -623,9 +866,15 @@ fn main() {
             eprintln!("Unknown option: {}", arg);
             show_usage_exit();
         }
+        placements.push(place.take());
         filenames.push(arg);
     }
 
+    if options.merge.is_none() && placements.iter().any(|place| place.is_some()) {
+        eprintln!("--place is only supported with --merge");
+        show_usage_exit();
+    }
+
     if options.cluster_sh.is_some() && !options.output.is_rad() {
         eprintln!("--cluster-sh is only supported for RAD output");
         show_usage_exit();
@@ -635,6 +884,16 @@ fn main() {
         show_usage_exit();
     }
 
+    if let Some(output) = options.merge.as_ref() {
+        if options.unlod {
+            eprintln!("--unlod is not supported with --merge");
+            show_usage_exit();
+        }
+        let inputs: Vec<(String, Option<Affine3A>)> = filenames.into_iter().zip(placements).collect();
+        merge_files_lod(&inputs, output, &options);
+        return;
+    }
+
     for filename in filenames {
         println!("*** Processing: {}", filename);
 
@@ -1,6 +1,7 @@
 use anyhow::anyhow;
 
-use crate::decoder::{ChunkReceiver, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::decoder::{ChunkReceiver, SplatFileType, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::probe::SplatFileInfo;
 
 pub const ANTISPLAT_BYTES_PER_SPLAT: usize = 32;
 const MAX_SPLAT_CHUNK: usize = 65536;
@@ -128,16 +129,77 @@ impl<T: SplatReceiver> ChunkReceiver for AntiSplatDecoder<T> {
     }
 }
 
+// Number of leading records checked when sniffing .splat contents
+const SNIFF_RECORDS: usize = 1024;
+
+// Checks whether bytes look like a .splat file, which has no header. Sampled
+// records must have plausible centers, scales and unit quaternions, and a
+// complete file must be a whole number of records. Returns None until enough
+// records are available to decide.
+pub fn sniff_antisplat(bytes: &[u8], complete: bool) -> Option<bool> {
+    let num_records = if complete {
+        if bytes.is_empty() || bytes.len() % ANTISPLAT_BYTES_PER_SPLAT != 0 {
+            return Some(false);
+        }
+        (bytes.len() / ANTISPLAT_BYTES_PER_SPLAT).min(SNIFF_RECORDS)
+    } else {
+        if bytes.len() < SNIFF_RECORDS * ANTISPLAT_BYTES_PER_SPLAT {
+            return None;
+        }
+        SNIFF_RECORDS
+    };
+
+    for index in 0..num_records {
+        let byte_base = index * ANTISPLAT_BYTES_PER_SPLAT;
+        let float_base = index * 8;
+        let center_ok = (0..3).all(|d| read_f32(bytes, float_base + d).abs() < 1.0e7);
+        let scale_ok = (3..6).all(|d| (0.0..1.0e5).contains(&read_f32(bytes, float_base + d)));
+        let quat_norm2: f32 = bytes[byte_base + 28..byte_base + 32].iter()
+            .map(|&b| (b as f32 - 128.0) / 128.0)
+            .map(|q| q * q)
+            .sum();
+        if !center_ok || !scale_ok || !(0.6..1.4).contains(&quat_norm2) {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+// Probes a prefix that may be a .splat file, which has no header to give the
+// splat count. Returns a result without file type until sniffing can decide.
+pub fn probe_antisplat(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let whole = bytes.len() - bytes.len() % ANTISPLAT_BYTES_PER_SPLAT;
+    match sniff_antisplat(bytes, false) {
+        // Records already available can rule the format out early
+        None if whole > 0 && sniff_antisplat(&bytes[..whole], true) == Some(false) => None,
+        None => Some(SplatFileInfo::needs(None, SNIFF_RECORDS * ANTISPLAT_BYTES_PER_SPLAT - bytes.len())),
+        Some(false) => None,
+        Some(true) => Some(SplatFileInfo {
+            max_sh_degree: Some(0),
+            lod_tree: Some(false),
+            ..SplatFileInfo::new(SplatFileType::ANTISPLAT)
+        }),
+    }
+}
+
 pub struct AntiSplatEncoder<T: SplatGetter> {
     getter: T,
+    max_sh_out: Option<u8>,
 }
 
 impl<T: SplatGetter> AntiSplatEncoder<T> {
-    pub fn new(getter: T) -> Self { Self { getter } }
+    pub fn new(getter: T) -> Self { Self { getter, max_sh_out: None } }
+
+    // The format has no SH, so only max_sh 0 lets SH inputs be written
+    pub fn with_max_sh(mut self, max_sh: u8) -> Self {
+        self.max_sh_out = Some(max_sh.min(3));
+        self
+    }
 
     pub fn encode(mut self) -> anyhow::Result<Vec<u8>> {
         let num_splats = self.getter.num_splats();
-        if self.getter.max_sh_degree() > 0 {
+        let sh_src = self.getter.max_sh_degree() as u8;
+        if self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) > 0 {
             return Err(anyhow!("AntiSplat format does not store SH data"));
         }
 
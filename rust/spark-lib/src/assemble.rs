@@ -0,0 +1,253 @@
+use std::ops::Range;
+
+use anyhow::anyhow;
+use glam::Affine3A;
+
+use crate::chunk_tree;
+use crate::decoder::{SetSplatEncoding, SplatEncoding, SplatGetter, SplatInit, SplatProps, SplatReceiver, SplatType};
+use crate::transform;
+use crate::tsplat::TsplatArray;
+
+const MAX_SPLAT_CHUNK: usize = 65536;
+
+// What to do with LoD trees already present in the parts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssembleLod {
+    // Keep only leaf splats so a new tree can be built over the whole scene
+    #[default]
+    Drop,
+    // Keep each part's tree with its child indices offset into the output.
+    // Multiple trees are joined under a new merged root.
+    Rebase,
+}
+
+pub struct AssembledSplats<T> {
+    pub splats: T,
+    // Output index range of each part, in the order they were pushed. With
+    // AssembleLod::Rebase the splats are reordered afterwards, so only the
+    // lengths still apply.
+    pub part_ranges: Vec<Range<usize>>,
+    // Root of the joined LoD tree with AssembleLod::Rebase, always 0
+    pub root: Option<usize>,
+}
+
+// Concatenates separately loaded splat arrays into one scene, each placed
+// with its own transform. SH degrees are raised to the highest among the
+// parts with zero padding, and Csplat encoding ranges are widened to cover
+// every part. Normals and distortions are kept only if all parts are surfels.
+pub struct SplatAssembler<T> {
+    pub lod: AssembleLod,
+    parts: Vec<(T, Option<Affine3A>)>,
+}
+
+impl<T: TsplatArray + SplatGetter + SplatReceiver> SplatAssembler<T> {
+    pub fn new() -> Self {
+        Self {
+            lod: AssembleLod::default(),
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn with_lod(mut self, lod: AssembleLod) -> Self {
+        self.lod = lod;
+        self
+    }
+
+    pub fn push(&mut self, splats: T, transform: Option<Affine3A>) {
+        self.parts.push((splats, transform));
+    }
+
+    pub fn with_part(mut self, splats: T, transform: Option<Affine3A>) -> Self {
+        self.push(splats, transform);
+        self
+    }
+
+    pub fn assemble(self) -> anyhow::Result<AssembledSplats<T>> {
+        let lod = self.lod;
+        let mut parts = Vec::with_capacity(self.parts.len());
+        for (mut splats, transform) in self.parts {
+            match lod {
+                AssembleLod::Drop => {
+                    if splats.has_children() {
+                        splats.retain_children(|_, children| children.is_empty());
+                        splats.clear_children();
+                    }
+                },
+                AssembleLod::Rebase => {
+                    if !splats.has_children() && splats.len() > 0 {
+                        return Err(anyhow!("Cannot rebase LoD tree of part without one"));
+                    }
+                },
+            }
+            if let Some(transform) = transform {
+                if !transform::is_invertible(&transform) {
+                    return Err(anyhow!("Part transform is singular"));
+                }
+                splats.apply_transform(transform);
+            }
+            parts.push(splats);
+        }
+
+        let num_splats = parts.iter().map(|splats| splats.len()).sum();
+        let max_sh_degree = parts.iter().map(|splats| TsplatArray::max_sh_degree(splats)).max().unwrap_or(0);
+        let surfel = !parts.is_empty() && parts.iter().all(|splats| SplatGetter::splat_type(splats) == SplatType::Surfel);
+        let normals = surfel && parts.iter().all(|splats| splats.has_normals());
+        let distortion = surfel && parts.iter().all(|splats| splats.has_distortion());
+
+        let encodings: Vec<Option<SplatEncoding>> = parts.iter_mut().map(|splats| splats.get_encoding()).collect();
+        let encoding = union_encoding(encodings.iter().flatten().cloned());
+
+        let mut output = T::new();
+        output.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
+        if let Some(encoding) = encoding.clone() {
+            output.set_encoding(&SetSplatEncoding::from(encoding))?;
+        }
+        if surfel {
+            output.set_splat_type(SplatType::Surfel)?;
+        }
+
+        let mut part_ranges = Vec::with_capacity(parts.len());
+        let mut base = 0;
+        for (splats, part_encoding) in parts.iter_mut().zip(encodings.iter()) {
+            // Encoded SH come out relative to the part's shMax
+            let sh_rescale = match part_encoding {
+                Some(part) => [part.sh1_max, part.sh2_max, part.sh3_max],
+                None => [1.0; 3],
+            };
+            copy_part(splats, &mut output, base, max_sh_degree, sh_rescale, normals, distortion);
+            part_ranges.push(base..base + splats.len());
+            base += splats.len();
+        }
+        output.finish()?;
+
+        let mut root = None;
+        if lod == AssembleLod::Rebase && num_splats > 0 {
+            output.prepare_children();
+            let mut roots = Vec::new();
+            for (splats, range) in parts.iter().zip(part_ranges.iter()) {
+                if range.is_empty() {
+                    continue;
+                }
+                let mut is_child = vec![false; splats.len()];
+                for index in 0..splats.len() {
+                    let children = splats.get_children(index);
+                    for &child in children.iter() {
+                        is_child[child] = true;
+                    }
+                    let rebased: Vec<usize> = children.iter().map(|&child| child + range.start).collect();
+                    output.set_children(range.start + index, &rebased);
+                }
+                let part_roots: Vec<usize> = (0..splats.len()).filter(|&index| !is_child[index]).collect();
+                if part_roots.len() != 1 {
+                    return Err(anyhow!("LoD tree has {} roots, expected 1", part_roots.len()));
+                }
+                roots.push(range.start + part_roots[0]);
+            }
+            let joined = if roots.len() == 1 { roots[0] } else { output.new_merged(&roots, 0.0) };
+            // Encoders need the root first and each node's children contiguous
+            chunk_tree::chunk_tree(&mut output, joined, |_| {});
+            root = Some(0);
+        }
+
+        Ok(AssembledSplats { splats: output, part_ranges, root })
+    }
+}
+
+impl<T: TsplatArray + SplatGetter + SplatReceiver> Default for SplatAssembler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Smallest encoding whose ranges cover all given ones, None if none are set
+fn union_encoding(encodings: impl Iterator<Item = SplatEncoding>) -> Option<SplatEncoding> {
+    encodings.reduce(|a, b| SplatEncoding {
+        rgb_min: a.rgb_min.min(b.rgb_min),
+        rgb_max: a.rgb_max.max(b.rgb_max),
+        ln_scale_min: a.ln_scale_min.min(b.ln_scale_min),
+        ln_scale_max: a.ln_scale_max.max(b.ln_scale_max),
+        sh1_max: a.sh1_max.max(b.sh1_max),
+        sh2_max: a.sh2_max.max(b.sh2_max),
+        sh3_max: a.sh3_max.max(b.sh3_max),
+        lod_opacity: a.lod_opacity || b.lod_opacity,
+    })
+}
+
+fn copy_part<G: SplatGetter, R: SplatReceiver>(getter: &mut G, receiver: &mut R, offset: usize, max_sh_degree: usize, sh_rescale: [f32; 3], normals: bool, distortion: bool) {
+    let num_splats = getter.num_splats();
+    let sh_degree = getter.max_sh_degree();
+
+    // SH bands the part doesn't have stay zero
+    let mut center = Vec::new();
+    let mut opacity = Vec::new();
+    let mut rgb = Vec::new();
+    let mut scale = Vec::new();
+    let mut quat = Vec::new();
+    let mut sh1 = Vec::new();
+    let mut sh2 = Vec::new();
+    let mut sh3 = Vec::new();
+    let mut normal = Vec::new();
+    let mut distortions = Vec::new();
+
+    let mut base = 0;
+    while base < num_splats {
+        let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+        center.resize(count * 3, 0.0);
+        opacity.resize(count, 0.0);
+        rgb.resize(count * 3, 0.0);
+        scale.resize(count * 3, 0.0);
+        quat.resize(count * 4, 0.0);
+        getter.get_center(base, count, &mut center);
+        getter.get_opacity(base, count, &mut opacity);
+        getter.get_rgb(base, count, &mut rgb);
+        getter.get_scale(base, count, &mut scale);
+        getter.get_quat(base, count, &mut quat);
+
+        for (degree, size, buffer) in [(1, 9, &mut sh1), (2, 15, &mut sh2), (3, 21, &mut sh3)] {
+            buffer.clear();
+            if max_sh_degree >= degree {
+                buffer.resize(count * size, 0.0);
+            }
+        }
+        if sh_degree >= 1 {
+            getter.get_sh1(base, count, &mut sh1);
+        }
+        if sh_degree >= 2 {
+            getter.get_sh2(base, count, &mut sh2);
+        }
+        if sh_degree >= 3 {
+            getter.get_sh3(base, count, &mut sh3);
+        }
+        for (rescale, buffer) in sh_rescale.iter().zip([&mut sh1, &mut sh2, &mut sh3]) {
+            if *rescale != 1.0 {
+                buffer.iter_mut().for_each(|v| *v *= rescale);
+            }
+        }
+
+        receiver.set_batch(offset + base, count, &SplatProps {
+            center: &center,
+            opacity: &opacity,
+            rgb: &rgb,
+            scale: &scale,
+            quat: &quat,
+            sh1: &sh1,
+            sh2: &sh2,
+            sh3: &sh3,
+            child_count: &[],
+            child_start: &[],
+        });
+
+        if normals {
+            normal.resize(count * 3, 0.0);
+            getter.get_normal(base, count, &mut normal);
+            receiver.set_normal(offset + base, count, &normal);
+        }
+        if distortion {
+            distortions.resize(count, 0.0);
+            getter.get_distortion(base, count, &mut distortions);
+            receiver.set_distortion(offset + base, count, &distortions);
+        }
+
+        base += count;
+    }
+}
@@ -1,11 +1,12 @@
 use std::collections::BinaryHeap;
 
 use ahash::AHashMap;
-use glam::I64Vec3;
+use glam::{I64Vec3, Mat3A, Quat, Vec3A};
 use ordered_float::OrderedFloat;
 use smallvec::{SmallVec, smallvec};
 
-use crate::tsplat::{Tsplat, TsplatArray};
+use crate::symmat3::SymMat3;
+use crate::tsplat::{Tsplat, TsplatArray, TsplatMut, ellipsoid_area, planar_normal};
 
 const MERGE_BASE: f32 = 2.0;
 
@@ -96,6 +97,9 @@ pub fn compute_lod_tree<TA: TsplatArray>(splats: &mut TA, lod_base: f32, logger:
             if best.0 != usize::MAX {
                 let best_neighbor = best.0;
                 let merged = splats.new_merged(&[index, best_neighbor], 0.0);
+                if splats.is_planar() {
+                    flatten_merged(splats, merged, &[index, best_neighbor], 0.0);
+                }
                 // if (merged % 10000) == 0 {
                 //     logger(&format!("merged: {}", merged));
                 // }
@@ -265,3 +269,53 @@ pub fn compute_lod_tree<TA: TsplatArray>(splats: &mut TA, lod_base: f32, logger:
 
     logger(&format!("Root #children: {}", splats.get_children(0).len()));
 }
+
+// Refits a merged surfel as a disc in the plane of its children's mean normal,
+// dropping their out-of-plane spread so every level of the tree stays planar.
+pub fn flatten_merged<TA: TsplatArray>(splats: &mut TA, merged: usize, indices: &[usize], step: f32) {
+    let children: SmallVec<[(Vec3A, f32, SymMat3, Vec3A); 8]> = indices.iter().map(|&index| {
+        let splat = splats.get(index);
+        let covariance = SymMat3::new_scale_quaternion(splat.scales(), splat.quaternion());
+        (splat.center(), splat.area() * splat.opacity(), covariance, planar_normal(&splat))
+    }).collect();
+    let total_weight = children.iter().map(|child| child.1).sum::<f32>().max(1.0e-30);
+    let center = splats.get(merged).center();
+
+    // Surfels are double-sided, so align normals with the first child before averaging
+    let first = children[0].3;
+    let normal = children.iter().fold(Vec3A::ZERO, |total, &(_, weight, _, normal)| {
+        let sign = if normal.dot(first) < 0.0 { -weight } else { weight };
+        normal.mul_add(Vec3A::splat(sign), total)
+    }).try_normalize().unwrap_or(first);
+    let (tangent, bitangent) = normal.any_orthonormal_pair();
+
+    // Covariance projected into the (tangent, bitangent) plane
+    let filter2 = (0.5 * step).powi(2);
+    let (mut uu, mut vv, mut uv) = (filter2, filter2, 0.0);
+    for &(child_center, weight, covariance, _) in children.iter() {
+        let weight = weight / total_weight;
+        let delta = child_center - center;
+        let (du, dv) = (delta.dot(tangent), delta.dot(bitangent));
+        let (cov_u, cov_v) = (covariance.mul_vec3(tangent), covariance.mul_vec3(bitangent));
+        uu += weight * (du * du + tangent.dot(cov_u));
+        vv += weight * (dv * dv + bitangent.dot(cov_v));
+        uv += weight * (du * dv + tangent.dot(cov_v));
+    }
+
+    let mean = 0.5 * (uu + vv);
+    let half_diff = 0.5 * (uu - vv);
+    let radius = (half_diff * half_diff + uv * uv).sqrt();
+    let angle = 0.5 * uv.atan2(half_diff);
+    let axis_u = tangent * angle.cos() + bitangent * angle.sin();
+    let axis_v = normal.cross(axis_u);
+
+    let scales = Vec3A::new((mean + radius).max(0.0).sqrt(), (mean - radius).max(0.0).sqrt(), 0.0)
+        .max(Vec3A::new(1.0e-30, 1.0e-30, 0.0));
+    let quaternion = Quat::from_mat3a(&Mat3A::from_cols(axis_u, axis_v, normal));
+    let opacity = (total_weight / ellipsoid_area(scales)).clamp(0.000001, 1000.0);
+
+    let mut splat = splats.get_mut(merged);
+    splat.set_scales(scales);
+    splat.set_quaternion(quaternion);
+    splat.set_opacity(opacity);
+}
@@ -0,0 +1,288 @@
+use std::collections::BinaryHeap;
+
+use glam::{Mat3A, Mat4, Vec3A, Vec4};
+use ordered_float::OrderedFloat;
+
+use crate::chunk_tree::Aabb;
+use crate::tsplat::{Tsplat, TsplatArray};
+
+const DEFAULT_STD_DEVS: f32 = 3.0;
+const DEFAULT_LEAF_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug)]
+struct BvhNode {
+    aabb: Aabb,
+    // Leaf: range into BVH order. Interior: first child is the next node,
+    // second child at `second`, and count is 0.
+    start: u32,
+    count: u32,
+    second: u32,
+}
+
+// Six inward-facing planes (a, b, c, d) with a*x + b*y + c*z + d >= 0 inside
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // Planes of a clip-space view-projection matrix with z in 0..1 (glam's
+    // perspective_rh and friends)
+    pub fn from_view_projection(m: Mat4) -> Self {
+        let [r0, r1, r2, r3] = [m.row(0), m.row(1), m.row(2), m.row(3)];
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2]
+            .map(|plane| plane / plane.truncate().length().max(1.0e-30));
+        Self { planes }
+    }
+
+    pub fn overlaps(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = Vec3A::from(plane.truncate());
+            // Corner furthest along the plane normal
+            let corner = Vec3A::select(normal.cmpge(Vec3A::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub index: usize,
+    pub distance: f32,
+}
+
+// Bounding volume hierarchy over splat AABBs at a given number of standard
+// deviations, for region queries, nearest neighbours and raycasts. Indices
+// refer to the array the BVH was built from and are invalidated by any
+// change to it.
+pub struct SplatBvh {
+    pub std_devs: f32,
+    nodes: Vec<BvhNode>,
+    // Splat indices in leaf order, with their AABBs and centers
+    order: Vec<usize>,
+    aabbs: Vec<Aabb>,
+    centers: Vec<Vec3A>,
+}
+
+impl SplatBvh {
+    pub fn new<T: TsplatArray>(splats: &T) -> Self {
+        Self::new_with(splats, DEFAULT_STD_DEVS, DEFAULT_LEAF_SIZE)
+    }
+
+    pub fn new_with<T: TsplatArray>(splats: &T, std_devs: f32, leaf_size: usize) -> Self {
+        let aabbs: Vec<Aabb> = (0..splats.len()).map(|index| Aabb::from_splat(&splats.get(index), std_devs)).collect();
+        let centers: Vec<Vec3A> = (0..splats.len()).map(|index| splats.get(index).center()).collect();
+        let mut order: Vec<usize> = (0..splats.len()).collect();
+        let mut nodes = Vec::with_capacity(2 * splats.len().div_ceil(leaf_size.max(1)));
+        if !order.is_empty() {
+            build_node(&mut nodes, &mut order, 0, &aabbs, &centers, leaf_size.max(1));
+        }
+
+        Self {
+            std_devs,
+            nodes,
+            aabbs: order.iter().map(|&index| aabbs[index]).collect(),
+            centers: order.iter().map(|&index| centers[index]).collect(),
+            order,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::empty(), |node| node.aabb)
+    }
+
+    // Splats whose AABB overlaps the box
+    pub fn query_box(&self, aabb: &Aabb) -> Vec<usize> {
+        self.query(|node_aabb| node_aabb.overlaps(aabb))
+    }
+
+    // Splats whose AABB comes within radius of the point
+    pub fn query_sphere(&self, center: Vec3A, radius: f32) -> Vec<usize> {
+        let radius2 = radius * radius;
+        self.query(|node_aabb| distance2_to_aabb(center, node_aabb) <= radius2)
+    }
+
+    // Splats whose AABB isn't entirely outside one of the frustum planes
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<usize> {
+        self.query(|node_aabb| frustum.overlaps(node_aabb))
+    }
+
+    fn query(&self, overlaps: impl Fn(&Aabb) -> bool) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !overlaps(&node.aabb) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.second as usize);
+                stack.push(node_index + 1);
+                continue;
+            }
+            let range = node.start as usize..(node.start + node.count) as usize;
+            result.extend(range.filter(|&i| overlaps(&self.aabbs[i])).map(|i| self.order[i]));
+        }
+        result
+    }
+
+    // Up to k splats with centers nearest the point, closest first, with
+    // their distances
+    pub fn nearest_k(&self, point: Vec3A, k: usize) -> Vec<(usize, f32)> {
+        if k == 0 || self.nodes.is_empty() {
+            return Vec::new();
+        }
+
+        // Node AABBs contain their splats' centers, so their distance is a
+        // lower bound for best-first search
+        let mut best: BinaryHeap<(OrderedFloat<f32>, usize)> = BinaryHeap::with_capacity(k + 1);
+        let mut frontier = BinaryHeap::new();
+        frontier.push((OrderedFloat(-distance2_to_aabb(point, &self.nodes[0].aabb)), 0));
+        while let Some((OrderedFloat(neg_distance2), node_index)) = frontier.pop() {
+            if best.len() == k && -neg_distance2 > best.peek().unwrap().0.0 {
+                break;
+            }
+            let node = &self.nodes[node_index];
+            if node.count == 0 {
+                for child in [node_index + 1, node.second as usize] {
+                    frontier.push((OrderedFloat(-distance2_to_aabb(point, &self.nodes[child].aabb)), child));
+                }
+                continue;
+            }
+            for i in node.start as usize..(node.start + node.count) as usize {
+                let distance2 = self.centers[i].distance_squared(point);
+                if best.len() < k {
+                    best.push((OrderedFloat(distance2), self.order[i]));
+                } else if distance2 < best.peek().unwrap().0.0 {
+                    best.pop();
+                    best.push((OrderedFloat(distance2), self.order[i]));
+                }
+            }
+        }
+
+        best.into_sorted_vec().into_iter().map(|(distance2, index)| (index, distance2.0.sqrt())).collect()
+    }
+
+    // Nearest intersection of a ray with the splats' ellipsoids at std_devs,
+    // within max_distance along the normalized direction
+    pub fn raycast<T: TsplatArray>(&self, splats: &T, origin: Vec3A, direction: Vec3A, max_distance: f32) -> Option<RayHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3A::ZERO || self.nodes.is_empty() {
+            return None;
+        }
+        let inv_direction = direction.recip();
+
+        let mut best: Option<RayHit> = None;
+        let mut stack = vec![(0usize, 0.0f32)];
+        while let Some((node_index, entry)) = stack.pop() {
+            let limit = best.map_or(max_distance, |hit| hit.distance);
+            if entry > limit {
+                continue;
+            }
+            let node = &self.nodes[node_index];
+            if node.count == 0 {
+                // Visit the nearer child first
+                let mut children = [node_index + 1, node.second as usize]
+                    .map(|child| (child, ray_aabb(origin, inv_direction, &self.nodes[child].aabb, limit)));
+                if children[0].1.unwrap_or(f32::INFINITY) < children[1].1.unwrap_or(f32::INFINITY) {
+                    children.swap(0, 1);
+                }
+                stack.extend(children.into_iter().filter_map(|(child, entry)| Some((child, entry?))));
+                continue;
+            }
+            for i in node.start as usize..(node.start + node.count) as usize {
+                let limit = best.map_or(max_distance, |hit| hit.distance);
+                if ray_aabb(origin, inv_direction, &self.aabbs[i], limit).is_none() {
+                    continue;
+                }
+                let index = self.order[i];
+                if let Some(distance) = ray_ellipsoid(&splats.get(index), self.std_devs, origin, direction) {
+                    if distance <= limit {
+                        best = Some(RayHit { index, distance });
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+fn build_node(nodes: &mut Vec<BvhNode>, order: &mut [usize], start: usize, aabbs: &[Aabb], centers: &[Vec3A], leaf_size: usize) -> usize {
+    let aabb = order.iter().fold(Aabb::empty(), |aabb, &index| aabb.extend(&aabbs[index]));
+    let node_index = nodes.len();
+    nodes.push(BvhNode { aabb, start: start as u32, count: order.len() as u32, second: 0 });
+    if order.len() <= leaf_size {
+        return node_index;
+    }
+
+    // Median split along the longest axis of the centers
+    let center_bounds = order.iter().fold(Aabb::empty(), |aabb, &index| aabb.add_point(centers[index]));
+    let (axis, extent) = center_bounds.longest_axis();
+    if extent <= 0.0 {
+        return node_index;
+    }
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |&a, &b| axis.get_vec3(centers[a]).total_cmp(&axis.get_vec3(centers[b])));
+
+    let (left, right) = order.split_at_mut(mid);
+    build_node(nodes, left, start, aabbs, centers, leaf_size);
+    let second = build_node(nodes, right, start + mid, aabbs, centers, leaf_size);
+    nodes[node_index].count = 0;
+    nodes[node_index].second = second as u32;
+    node_index
+}
+
+fn distance2_to_aabb(point: Vec3A, aabb: &Aabb) -> f32 {
+    let nearest = point.clamp(aabb.min, aabb.max);
+    nearest.distance_squared(point)
+}
+
+// Slab test, returning the entry distance if the ray hits within limit
+fn ray_aabb(origin: Vec3A, inv_direction: Vec3A, aabb: &Aabb, limit: f32) -> Option<f32> {
+    let (mut entry, mut exit) = (0.0f32, limit);
+    for d in 0..3 {
+        let t0 = (aabb.min[d] - origin[d]) * inv_direction[d];
+        let t1 = (aabb.max[d] - origin[d]) * inv_direction[d];
+        // f32::min/max skip the NaN of 0 * inf, from an axis-parallel ray
+        // starting on a slab face
+        entry = entry.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+    }
+    (entry <= exit).then_some(entry)
+}
+
+fn ray_ellipsoid<TS: Tsplat>(splat: &TS, std_devs: f32, origin: Vec3A, direction: Vec3A) -> Option<f32> {
+    // Same scale clamp as Aabb::from_splat, so flat splats can still be hit
+    let radii = splat.scales().max(Vec3A::splat(1.0e-3)) * std_devs;
+    let inverse = Mat3A::from_quat(splat.quaternion()).transpose();
+    let local_origin = inverse * (origin - splat.center()) / radii;
+    let local_direction = inverse * direction / radii;
+
+    // Unit sphere |o + t d| = 1
+    let a = local_direction.length_squared();
+    let b = local_origin.dot(local_direction);
+    let c = local_origin.length_squared() - 1.0;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (near, far) = ((-b - root) / a, (-b + root) / a);
+    if far < 0.0 {
+        None
+    } else {
+        // Origin inside the ellipsoid hits at 0
+        Some(near.max(0.0))
+    }
+}
@@ -1,8 +1,8 @@
-use glam::{Mat3A, Quat, Vec3, Vec3A};
+use glam::{Affine3A, Mat3A, Quat, Vec3, Vec3A};
 use half::f16;
 use smallvec::SmallVec;
 
-use crate::{decoder::{SetSplatEncoding, SplatEncoding, SplatGetter, SplatInit, SplatProps, SplatReceiver}, splat_encode::{decode_quat_oct888, decode_scale8, encode_quat_oct888, encode_scale8}, symmat3::SymMat3, tsplat::{Tsplat, TsplatArray, TsplatMut, apply_swaps, compute_swaps, ellipsoid_area, similarity_metric}};
+use crate::{decoder::{SetSplatEncoding, SplatEncoding, SplatGetter, SplatInit, SplatProps, SplatReceiver, SplatType}, splat_encode::{decode_quat_oct888, decode_scale8, encode_quat_oct888, encode_scale8}, symmat3::SymMat3, transform::SplatTransform, tsplat::{Tsplat, TsplatArray, TsplatMut, apply_swaps, compute_swaps, ellipsoid_area, similarity_metric}};
 
 #[derive(Clone, Default)]
 pub struct Csplat {
@@ -436,6 +436,44 @@ impl TsplatArray for CsplatArray {
             sh3: if self.sh3.is_empty() { Vec::new() } else { self.sh3[start..start + count].to_vec() },
         }
     }
+
+    fn apply_transform(&mut self, transform: Affine3A) {
+        let transform = SplatTransform::new(transform);
+        // Scaling can take scales outside the ln-scale range, so it's widened
+        // to cover them before they're requantized
+        let transformed: Vec<(Vec3A, Quat)> = (0..self.splats.len()).map(|index| {
+            let splat = self.get(index);
+            transform.transform_scales_quaternion(splat.scales(), splat.quaternion())
+        }).collect();
+        let ln_scales = transformed.iter().flat_map(|(scales, _)| scales.to_array()).filter(|&scale| scale > 0.0).map(f32::ln);
+        if let Some((ln_min, ln_max)) = ln_scales.fold(None, |range: Option<(f32, f32)>, ln| Some(range.map_or((ln, ln), |(min, max)| (min.min(ln), max.max(ln))))) {
+            let encoding = self.encoding.get_or_insert_with(SplatEncoding::default);
+            encoding.ln_scale_min = encoding.ln_scale_min.min(ln_min);
+            encoding.ln_scale_max = encoding.ln_scale_max.max(ln_max);
+        }
+        for (index, (scales, quaternion)) in transformed.into_iter().enumerate() {
+            let mut splat = self.get_mut(index);
+            splat.set_center(transform.transform_center(splat.center()));
+            splat.set_scales(scales);
+            splat.set_quaternion(quaternion);
+        }
+        // Rotation keeps each band's norm, but single coefficients can
+        // exceed the quantized range and are clamped
+        fn transform_i8<const N: usize>(transform: &SplatTransform, degree: usize, sh: &mut [i8; N]) {
+            let mut values = sh.map(|v| v as f32);
+            transform.transform_sh(degree, &mut values);
+            *sh = values.map(|v| v.clamp(-127.0, 127.0).round() as i8);
+        }
+        for sh1 in self.sh1.iter_mut() {
+            transform_i8(&transform, 1, sh1);
+        }
+        for sh2 in self.sh2.iter_mut() {
+            transform_i8(&transform, 2, sh2);
+        }
+        for sh3 in self.sh3.iter_mut() {
+            transform_i8(&transform, 3, sh3);
+        }
+    }
 }
 
 impl SplatReceiver for CsplatArray {
@@ -479,6 +517,13 @@ impl SplatReceiver for CsplatArray {
         Ok(())
     }
 
+    fn set_splat_type(&mut self, splat_type: SplatType) -> anyhow::Result<()> {
+        if splat_type != SplatType::Gsplat {
+            return Err(anyhow::anyhow!("CsplatArray doesn't support {:?} splats", splat_type));
+        }
+        Ok(())
+    }
+
     fn set_encoding(&mut self, encoding: &SetSplatEncoding) -> anyhow::Result<()> {
         let mut current = self.encoding.clone().unwrap_or_default();
         if let Some(rgb_min) = encoding.rgb_min {
@@ -4,11 +4,13 @@ use miniz_oxide::inflate::{core::{decompress, inflate_flags::{TINFL_FLAG_HAS_MOR
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    antisplat::AntiSplatDecoder,
-    ksplat::KsplatDecoder,
+    antisplat::{AntiSplatDecoder, sniff_antisplat},
+    gltf::{GLB_MAGIC, GltfDecoder},
+    ksplat::{KsplatDecoder, sniff_ksplat},
+    npz::NpzDecoder,
     ply::{PLY_MAGIC, PlyDecoder},
     rad::{RAD_CHUNK_MAGIC, RAD_MAGIC, RadDecoder},
-    sogs::SogsDecoder,
+    sogs::{SogsDecoder, SogsFileResolver},
     spz::{SPZ_MAGIC, SpzDecoder}
 };
 
@@ -38,6 +40,68 @@ impl Default for SplatInit {
     }
 }
 
+// Primitive represented by each splat. Surfels are planar 2D Gaussians (2DGS)
+// with the third scale fixed at zero and the normal along the local z axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplatType {
+    #[default]
+    Gsplat,
+    Surfel,
+}
+
+// Storage encoding for a user-defined per-splat channel. Values are passed
+// around as f64, which holds every U32 exactly, with integer encodings
+// rounded and clamped on write.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplatExtraEncoding {
+    #[default]
+    F32,
+    F16,
+    U8,
+    U16,
+    U32,
+}
+
+// How LoD builders combine the extra values of merged splats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplatExtraMerge {
+    // Mean weighted by splat area * opacity, for continuous values like confidence
+    #[default]
+    Mean,
+    // Value with the largest total weight, for categorical IDs
+    Vote,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplatExtraInfo {
+    pub name: String,
+    pub dims: usize,
+    #[serde(default)]
+    pub encoding: SplatExtraEncoding,
+    #[serde(default)]
+    pub merge: SplatExtraMerge,
+}
+
+impl SplatExtraInfo {
+    pub fn new(name: &str, dims: usize) -> Self {
+        Self { name: name.to_string(), dims, encoding: SplatExtraEncoding::default(), merge: SplatExtraMerge::default() }
+    }
+
+    pub fn with_encoding(mut self, encoding: SplatExtraEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_merge(mut self, merge: SplatExtraMerge) -> Self {
+        self.merge = merge;
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplatEncoding {
     #[serde(rename = "rgbMin")]
@@ -167,6 +231,17 @@ pub trait SplatReceiver: 'static {
 
     fn set_child_count(&mut self, base: usize, count: usize, child_count: &[u16]) {}
     fn set_child_start(&mut self, base: usize, count: usize, child_start: &[usize]) {}
+
+    // Declares extra channels after init_splats, before any set_extra calls
+    fn init_extras(&mut self, extras: &[SplatExtraInfo]) -> anyhow::Result<()> { Ok(()) }
+    fn set_extra(&mut self, base: usize, count: usize, name: &str, values: &[f64]) {}
+
+    // Called after init_splats for sources that aren't Gsplats. Surfel scales
+    // are still passed as 3 values with the last one zero.
+    fn set_splat_type(&mut self, splat_type: SplatType) -> anyhow::Result<()> { Ok(()) }
+    // Optional surfel terms: unit normal (3 values) and depth distortion (1 value)
+    fn set_normal(&mut self, base: usize, count: usize, normal: &[f32]) {}
+    fn set_distortion(&mut self, base: usize, count: usize, distortion: &[f32]) {}
 }
 
 #[derive(Default)]
@@ -277,6 +352,9 @@ pub trait SplatGetter: 'static {
     fn max_sh_degree(&self) -> usize;
     fn flag_antialias(&self) -> bool { false }
     fn has_lod_tree(&self) -> bool { false }
+    fn splat_type(&self) -> SplatType { SplatType::Gsplat }
+    fn has_normals(&self) -> bool { false }
+    fn has_distortion(&self) -> bool { false }
     fn get_encoding(&mut self) -> Option<SplatEncoding> { None }
 
     fn get_batch(&mut self, base: usize, count: usize, out: &mut SplatPropsMut) {
@@ -325,16 +403,32 @@ pub trait SplatGetter: 'static {
 
     fn get_child_count(&mut self, _base: usize, _count: usize, _out: &mut [u16]) {}
     fn get_child_start(&mut self, _base: usize, _count: usize, _out: &mut [usize]) {}
+
+    fn get_extras(&self) -> Vec<SplatExtraInfo> { Vec::new() }
+    fn get_extra(&mut self, _base: usize, _count: usize, _name: &str, _out: &mut [f64]) {}
+
+    fn get_normal(&mut self, _base: usize, _count: usize, _out: &mut [f32]) {}
+    fn get_distortion(&mut self, _base: usize, _count: usize, _out: &mut [f32]) {}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum SplatFileType {
+    #[serde(rename = "ply")]
     PLY,
+    #[serde(rename = "spz")]
     SPZ,
+    #[serde(rename = "splat")]
     ANTISPLAT,
+    #[serde(rename = "ksplat")]
     KSPLAT,
+    #[serde(rename = "pcsogszip")]
     SOGS,
+    #[serde(rename = "rad")]
     RAD,
+    #[serde(rename = "gltf")]
+    GLTF,
+    #[serde(rename = "npz")]
+    NPZ,
 }
 
 impl SplatFileType {
@@ -346,6 +440,8 @@ impl SplatFileType {
             Self::KSPLAT => "ksplat",
             Self::SOGS => "pcsogszip",
             Self::RAD => "rad",
+            Self::GLTF => "gltf",
+            Self::NPZ => "npz",
         }
     }
 
@@ -357,6 +453,8 @@ impl SplatFileType {
             "ksplat" => Ok(Self::KSPLAT),
             "pcsogszip" => Ok(Self::SOGS),
             "rad" => Ok(Self::RAD),
+            "gltf" => Ok(Self::GLTF),
+            "npz" => Ok(Self::NPZ),
             _ => Err(anyhow::anyhow!("Invalid file type: {}", enum_str)),
         }
     }
@@ -371,6 +469,9 @@ impl SplatFileType {
             "sogs" => Some(Self::SOGS),
             "zip" => Some(Self::SOGS),
             "rad" => Some(Self::RAD),
+            "gltf" => Some(Self::GLTF),
+            "glb" => Some(Self::GLTF),
+            "npz" => Some(Self::NPZ),
             _ => None,
         }
     }
@@ -382,10 +483,18 @@ impl SplatFileType {
         let clean_path = clean_path
             .split_once('#')
             .map_or(clean_path, |(path, _)| path);
+        if is_sogs_meta(clean_path) {
+            return Some(Self::SOGS);
+        }
         clean_path.split('.').last().and_then(Self::from_extension)
     }
 }
 
+// True if the last component of the path is a SOGS meta.json
+pub fn is_sogs_meta(pathname: &str) -> bool {
+    pathname.rsplit(['/', '\\']).next() == Some("meta.json")
+}
+
 pub struct MultiDecoder<T: SplatReceiver> {
     pub file_type: Option<SplatFileType>,
     pub pathname: Option<String>,
@@ -393,6 +502,9 @@ pub struct MultiDecoder<T: SplatReceiver> {
     buffer: Vec<u8>,
     buffer_gz: Option<Vec<u8>>,
     inner: Option<Box<dyn ChunkReceiver>>,
+    inflater: Option<Inflater>,
+    sogs_resolver: Option<Box<dyn SogsFileResolver>>,
+    ply_extras: bool,
 }
 
 impl<T: SplatReceiver> MultiDecoder<T> {
@@ -401,23 +513,42 @@ impl<T: SplatReceiver> MultiDecoder<T> {
         file_type: Option<SplatFileType>,
         pathname: Option<&str>,
     ) -> Self {
-        let (splats, inner) = if let Some(file_type) = file_type {
-            (None, Some(new_decoder(file_type, splats)))
-        } else {
-            (Some(splats), None)
-        };
+        Self::new_with_resolver(splats, file_type, pathname, None)
+    }
+
+    // Resolver used to fetch sibling files when decoding a bare SOGS meta.json
+    pub fn new_with_resolver(
+        splats: T,
+        file_type: Option<SplatFileType>,
+        pathname: Option<&str>,
+        sogs_resolver: Option<Box<dyn SogsFileResolver>>,
+    ) -> Self {
+        // With a known file type the decoder is made on the first push, so
+        // options set after construction still apply
         Self {
             file_type,
             pathname: pathname.map(|s| s.to_string()),
-            splats,
+            splats: Some(splats),
             buffer: Vec::new(),
             buffer_gz: None,
-            inner,
+            inner: None,
+            inflater: None,
+            sogs_resolver,
+            ply_extras: false,
         }
     }
 
+    // See PlyDecoder::with_extras
+    pub fn with_ply_extras(mut self, ply_extras: bool) -> Self {
+        self.ply_extras = ply_extras;
+        self
+    }
+
     pub fn into_splats(self) -> T {
-        let inner_any = self.inner.unwrap().into_any();
+        let Some(inner) = self.inner else {
+            return self.splats.unwrap();
+        };
+        let inner_any = inner.into_any();
         let inner_any = match inner_any.downcast::<PlyDecoder<T>>() {
             Ok(ply) => { return ply.into_splats(); },
             Err(inner_any) => inner_any,
@@ -442,6 +573,14 @@ impl<T: SplatReceiver> MultiDecoder<T> {
             Ok(rad) => { return rad.into_splats(); },
             Err(inner_any) => inner_any,
         };
+        let inner_any = match inner_any.downcast::<GltfDecoder<T>>() {
+            Ok(gltf) => { return gltf.into_splats(); },
+            Err(inner_any) => inner_any,
+        };
+        let inner_any = match inner_any.downcast::<NpzDecoder<T>>() {
+            Ok(npz) => { return npz.into_splats(); },
+            Err(inner_any) => inner_any,
+        };
         let _ = inner_any;
         panic!("Invalid decoder type");
     }
@@ -449,19 +588,24 @@ impl<T: SplatReceiver> MultiDecoder<T> {
     fn init_file_type(&mut self, file_type: SplatFileType) -> anyhow::Result<()> {
         self.file_type = Some(file_type);
         let splats = self.splats.take().unwrap();
-        let mut inner = new_decoder(file_type, splats);
+        let mut inner = new_decoder(file_type, splats, self.sogs_resolver.take(), self.ply_extras);
         inner.push(&self.buffer)?;
         self.buffer.clear();
         self.buffer_gz = None;
         self.inner = Some(inner);
         Ok(())
     }
-}
 
-const GZIP_MAGIC: u32 = 0x00088b1f; // Gzip deflate
+    // Continues detection on the contents of a gzip or zip wrapper
+    fn init_inflater(&mut self, inflater: Inflater, pathname: Option<String>) -> anyhow::Result<()> {
+        let buffer = std::mem::take(&mut self.buffer);
+        self.buffer_gz = None;
+        self.inflater = Some(inflater);
+        self.pathname = pathname;
+        self.push(&buffer)
+    }
 
-impl<T: SplatReceiver> ChunkReceiver for MultiDecoder<T> {
-    fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+    fn push_contents(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
         if self.file_type.is_none() {
             self.buffer.extend_from_slice(bytes);
             if self.buffer.len() < 4 {
@@ -487,8 +631,24 @@ impl<T: SplatReceiver> ChunkReceiver for MultiDecoder<T> {
                             return self.init_file_type(SplatFileType::SPZ);
                         }
                     }
+                    if self.inflater.is_none() {
+                        let pathname = self.pathname.as_deref().map(strip_gz_extension);
+                        return self.init_inflater(Inflater::gzip(), pathname);
+                    }
                 }
             } else if magic == 0x04034b50 {
+                // A zip whose first entry is a splat file is unwrapped, leaving
+                // multi-file formats like SOGS and NPZ to their own decoders
+                if self.inflater.is_none() {
+                    let Some(entry) = parse_zip_entry_header(&self.buffer)? else {
+                        return Ok(());
+                    };
+                    if !matches!(SplatFileType::from_pathname(&entry.name), None | Some(SplatFileType::SOGS) | Some(SplatFileType::NPZ)) {
+                        let inflater = Inflater::zip_entry(entry.method, entry.compressed_size)?;
+                        self.buffer.drain(..entry.data_start);
+                        return self.init_inflater(inflater, Some(entry.name));
+                    }
+                }
                 detection_complete = true;
                 if let Some(pathname) = &self.pathname {
                     if let Some(SplatFileType::SOGS) = SplatFileType::from_pathname(pathname) {
@@ -497,8 +657,19 @@ impl<T: SplatReceiver> ChunkReceiver for MultiDecoder<T> {
                 }
             } else if magic == RAD_MAGIC || magic == RAD_CHUNK_MAGIC {
                 return self.init_file_type(SplatFileType::RAD);
+            } else if magic == GLB_MAGIC {
+                return self.init_file_type(SplatFileType::GLTF);
             } else {
-                detection_complete = true;
+                // No magic number, so check for formats recognised by content
+                let ksplat = sniff_ksplat(&self.buffer);
+                if ksplat == Some(true) {
+                    return self.init_file_type(SplatFileType::KSPLAT);
+                }
+                let antisplat = sniff_antisplat(&self.buffer, false);
+                if ksplat == Some(false) && antisplat == Some(true) {
+                    return self.init_file_type(SplatFileType::ANTISPLAT);
+                }
+                detection_complete = ksplat.is_some() && antisplat.is_some();
             }
 
             if detection_complete {
@@ -514,30 +685,76 @@ impl<T: SplatReceiver> ChunkReceiver for MultiDecoder<T> {
                 Ok(())
             }
         } else {
+            if self.inner.is_none() {
+                self.init_file_type(self.file_type.unwrap())?;
+            }
             self.inner.as_mut().unwrap().push(bytes)
         }
     }
+}
+
+pub(crate) const GZIP_MAGIC: u32 = 0x00088b1f; // Gzip deflate
+
+impl<T: SplatReceiver> ChunkReceiver for MultiDecoder<T> {
+    fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        if let Some(inflater) = self.inflater.as_mut() {
+            let mut inflated = Vec::new();
+            inflater.push(bytes, &mut inflated)?;
+            return self.push_contents(&inflated);
+        }
+        self.push_contents(bytes)
+    }
 
     fn finish(&mut self) -> anyhow::Result<()> {
+        if self.inflater.as_ref().is_some_and(|inflater| !inflater.done) {
+            return Err(anyhow::anyhow!("Truncated compressed stream"));
+        }
         if self.file_type.is_none() {
-            return Err(anyhow::anyhow!("Unknown file type"));
+            // Input ended before detection could decide, so use the whole file
+            let file_type = if sniff_ksplat(&self.buffer) == Some(true) {
+                Some(SplatFileType::KSPLAT)
+            } else if sniff_antisplat(&self.buffer, true) == Some(true) {
+                Some(SplatFileType::ANTISPLAT)
+            } else {
+                self.pathname.as_deref().and_then(SplatFileType::from_pathname)
+            };
+            let Some(file_type) = file_type else {
+                return Err(anyhow::anyhow!("Unknown file type"));
+            };
+            self.init_file_type(file_type)?;
+        } else if self.inner.is_none() {
+            self.init_file_type(self.file_type.unwrap())?;
         }
         self.inner.as_mut().unwrap().finish()
     }
 }
 
-fn new_decoder<T: SplatReceiver>(file_type: SplatFileType, splats: T) -> Box<dyn ChunkReceiver> {
+fn new_decoder<T: SplatReceiver>(
+    file_type: SplatFileType,
+    splats: T,
+    sogs_resolver: Option<Box<dyn SogsFileResolver>>,
+    ply_extras: bool,
+) -> Box<dyn ChunkReceiver> {
     match file_type {
-        SplatFileType::PLY => Box::new(PlyDecoder::new(splats)),
+        SplatFileType::PLY => Box::new(PlyDecoder::new(splats).with_extras(ply_extras)),
         SplatFileType::SPZ => Box::new(SpzDecoder::new(splats)),
         SplatFileType::ANTISPLAT => Box::new(AntiSplatDecoder::new(splats)),
         SplatFileType::KSPLAT => Box::new(KsplatDecoder::new(splats)),
-        SplatFileType::SOGS => Box::new(SogsDecoder::new(splats, None)),
+        SplatFileType::SOGS => {
+            let decoder = SogsDecoder::new(splats);
+            match sogs_resolver {
+                Some(resolver) => Box::new(decoder.with_resolver(resolver)),
+                None => Box::new(decoder),
+            }
+        },
         SplatFileType::RAD => Box::new(RadDecoder::new(splats)),
+        SplatFileType::GLTF => Box::new(GltfDecoder::new(splats)),
+        SplatFileType::NPZ => Box::new(NpzDecoder::new(splats)),
     }
 }
 
-fn try_gunzip(buffer: &[u8], max_bytes: usize) -> anyhow::Result<Option<Vec<u8>>> {
+// Size of the gzip member header, or None if more bytes are needed
+fn gzip_header_size(buffer: &[u8]) -> anyhow::Result<Option<usize>> {
     if buffer.len() < 10 {
         return Ok(None);
     }
@@ -601,6 +818,16 @@ fn try_gunzip(buffer: &[u8], max_bytes: usize) -> anyhow::Result<Option<Vec<u8>>
         end += 2;
     }
     
+    if buffer.len() < end {
+        return Ok(None);
+    }
+    Ok(Some(end))
+}
+
+fn try_gunzip(buffer: &[u8], max_bytes: usize) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(end) = gzip_header_size(buffer)? else {
+        return Ok(None);
+    };
     if buffer.len() <= end {
         return Ok(None);
     }
@@ -630,6 +857,155 @@ fn try_gunzip(buffer: &[u8], max_bytes: usize) -> anyhow::Result<Option<Vec<u8>>
     }
 }
 
+// Incremental unwrapping of a gzip member or a single zip entry, whose
+// contents are then run through format detection like a plain file
+pub(crate) struct Inflater {
+    decompressor: DecompressorOxide,
+    compressed: Vec<u8>,
+    window: Vec<u8>,
+    out_pos: usize,
+    // Gzip header still to be skipped before the deflate stream
+    gzip_header: bool,
+    // Remaining bytes of a stored zip entry, None when deflated
+    stored: Option<usize>,
+    done: bool,
+}
+
+impl Inflater {
+    fn new(gzip_header: bool, stored: Option<usize>) -> Self {
+        Self {
+            decompressor: DecompressorOxide::new(),
+            compressed: Vec::new(),
+            window: if stored.is_some() { Vec::new() } else { vec![0u8; 128 * 1024] },
+            out_pos: 0,
+            gzip_header,
+            stored,
+            done: stored == Some(0),
+        }
+    }
+
+    pub(crate) fn gzip() -> Self {
+        Self::new(true, None)
+    }
+
+    pub(crate) fn zip_entry(method: u16, compressed_size: Option<usize>) -> anyhow::Result<Self> {
+        match (method, compressed_size) {
+            (0, Some(size)) => Ok(Self::new(false, Some(size))),
+            (0, None) => Err(anyhow::anyhow!("Stored zip entry without size")),
+            (8, _) => Ok(Self::new(false, None)),
+            _ => Err(anyhow::anyhow!("Unsupported zip compression method {}", method)),
+        }
+    }
+
+    // Appends everything that can be inflated so far to out. Input after the
+    // end of the stream (gzip trailer, further zip records) is ignored.
+    pub(crate) fn push(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> anyhow::Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        if let Some(remaining) = self.stored.as_mut() {
+            let count = bytes.len().min(*remaining);
+            out.extend_from_slice(&bytes[..count]);
+            *remaining -= count;
+            self.done = *remaining == 0;
+            return Ok(());
+        }
+
+        self.compressed.extend_from_slice(bytes);
+        if self.gzip_header {
+            let Some(header_size) = gzip_header_size(&self.compressed)? else {
+                return Ok(());
+            };
+            self.compressed.drain(..header_size);
+            self.gzip_header = false;
+        }
+
+        let mut in_offset = 0;
+        while in_offset < self.compressed.len() {
+            // Keep the last 32 KiB as history and at least 64 KiB free
+            const WINDOW: usize = 32 * 1024;
+            if self.window.len() - self.out_pos < 64 * 1024 {
+                let keep_start = self.out_pos.saturating_sub(WINDOW);
+                self.window.copy_within(keep_start..self.out_pos, 0);
+                self.out_pos -= keep_start;
+            }
+
+            let (status, in_consumed, out_written) = decompress(
+                &mut self.decompressor,
+                &self.compressed[in_offset..],
+                &mut self.window,
+                self.out_pos,
+                TINFL_FLAG_HAS_MORE_INPUT | TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+            );
+            out.extend_from_slice(&self.window[self.out_pos..self.out_pos + out_written]);
+            self.out_pos += out_written;
+            in_offset += in_consumed;
+
+            match status {
+                TINFLStatus::Done => {
+                    self.done = true;
+                    self.compressed = Vec::new();
+                    return Ok(());
+                },
+                TINFLStatus::NeedsMoreInput => {
+                    if in_consumed == 0 && out_written == 0 {
+                        break;
+                    }
+                },
+                TINFLStatus::HasMoreOutput => {},
+                _ => return Err(anyhow::anyhow!("Decompression failed: {:?}", status)),
+            }
+        }
+        self.compressed.drain(..in_offset);
+        Ok(())
+    }
+}
+
+pub(crate) struct ZipEntryHeader {
+    pub name: String,
+    pub method: u16,
+    pub compressed_size: Option<usize>,
+    pub data_start: usize,
+}
+
+// Parses a zip local file header, or returns None if more bytes are needed
+pub(crate) fn parse_zip_entry_header(buffer: &[u8]) -> anyhow::Result<Option<ZipEntryHeader>> {
+    if buffer.len() < 30 {
+        return Ok(None);
+    }
+    let read_u16 = |offset: usize| u16::from_le_bytes([buffer[offset], buffer[offset + 1]]);
+    let read_u32 = |offset: usize| u32::from_le_bytes([buffer[offset], buffer[offset + 1], buffer[offset + 2], buffer[offset + 3]]);
+    let flags = read_u16(6);
+    let method = read_u16(8);
+    let compressed_size = read_u32(18);
+    let name_len = read_u16(26) as usize;
+    let extra_len = read_u16(28) as usize;
+    let data_start = 30 + name_len + extra_len;
+    if buffer.len() < data_start {
+        return Ok(None);
+    }
+    if (flags & 0x1) != 0 {
+        return Err(anyhow::anyhow!("Encrypted zip entries are not supported"));
+    }
+
+    // Sizes may instead follow the data in a descriptor or a ZIP64 extra field
+    let size_known = (flags & 0x8) == 0 && compressed_size != 0xffffffff;
+    Ok(Some(ZipEntryHeader {
+        name: String::from_utf8_lossy(&buffer[30..30 + name_len]).into_owned(),
+        method,
+        compressed_size: size_known.then_some(compressed_size as usize),
+        data_start,
+    }))
+}
+
+// Drops a trailing .gz so the pathname describes the inflated contents
+fn strip_gz_extension(pathname: &str) -> String {
+    let end = pathname.find(['?', '#']).unwrap_or(pathname.len());
+    let (path, rest) = pathname.split_at(end);
+    let path = if path.to_lowercase().ends_with(".gz") { &path[..path.len() - 3] } else { path };
+    format!("{}{}", path, rest)
+}
+
 pub fn copy_getter_to_receiver<G: SplatGetter, R: SplatReceiver>(getter: &mut G, receiver: &mut R) -> anyhow::Result<()> {
     const MAX_SPLAT_CHUNK: usize = 65536;
 
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::bhatt_lod::flatten_merged;
+use crate::bvh::SplatBvh;
+use crate::chunk_tree::Aabb;
+use crate::tsplat::{Tsplat, TsplatMut, TsplatArray};
+
+// Neighbour search reach, wide enough that any pair above a useful
+// threshold has overlapping AABBs
+const DEDUP_STD_DEVS: f32 = 3.0;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DedupReport {
+    // Merged splats written, and the original splats they replaced
+    pub groups: usize,
+    pub merged: usize,
+}
+
+impl DedupReport {
+    pub fn removed(&self) -> usize {
+        self.merged - self.groups
+    }
+}
+
+// Merges near-identical splats, such as coincident copies from overlapping
+// captures, into single splats. Each splat is grouped with the unmerged
+// neighbours whose similarity to it (Bhattacharyya coefficient weighted by
+// color, or the planar variant for surfels) is at least threshold, and the
+// group is replaced by its moment-matched merge, as LoD parents are built
+// but without keeping the originals as children, with the opacity of the
+// copies composited over each other.
+#[derive(Clone, Debug, Serialize)]
+pub struct Dedup {
+    pub threshold: f32,
+    pub max_group: usize,
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self {
+            threshold: 0.9,
+            max_group: 8,
+        }
+    }
+}
+
+impl Dedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_max_group(mut self, max_group: usize) -> Self {
+        self.max_group = max_group.max(2);
+        self
+    }
+
+    // Merged splats are appended after the remaining originals. Arrays with
+    // an LoD tree are left unchanged, since merging would orphan children.
+    pub fn merge<T: TsplatArray>(&self, splats: &mut T) -> DedupReport {
+        let mut report = DedupReport::default();
+        if splats.has_children() || splats.len() < 2 {
+            return report;
+        }
+
+        let num_splats = splats.len();
+        let bvh = SplatBvh::new_with(splats, DEDUP_STD_DEVS, 8);
+        let aabbs: Vec<Aabb> = (0..num_splats).map(|index| Aabb::from_splat(&splats.get(index), DEDUP_STD_DEVS)).collect();
+
+        // new_merged records children, which are cleared at the end
+        splats.prepare_children();
+        let mut merged = vec![false; num_splats];
+        for index in 0..num_splats {
+            if merged[index] {
+                continue;
+            }
+            let mut candidates: Vec<(f32, usize)> = bvh.query_box(&aabbs[index]).into_iter()
+                .filter(|&other| other != index && !merged[other])
+                .map(|other| (splats.similarity(index, other), other))
+                .filter(|&(similarity, _)| similarity >= self.threshold)
+                .collect();
+            if candidates.is_empty() {
+                continue;
+            }
+            candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let group: Vec<usize> = std::iter::once(index)
+                .chain(candidates.iter().map(|&(_, other)| other))
+                .take(self.max_group)
+                .collect();
+            let parent = splats.new_merged(&group, 0.0);
+            if splats.is_planar() {
+                flatten_merged(splats, parent, &group, 0.0);
+            }
+            // Moment matching sums the weights as an LoD parent covering
+            // separate children would, but coincident copies composite
+            let transmittance: f32 = group.iter().map(|&member| 1.0 - splats.get(member).opacity().clamp(0.0, 1.0)).product();
+            splats.get_mut(parent).set_opacity(1.0 - transmittance);
+            for &member in group.iter() {
+                merged[member] = true;
+            }
+            report.groups += 1;
+            report.merged += group.len();
+        }
+
+        // Merged splats past num_splats are kept
+        let mut index = 0;
+        splats.retain(|_| {
+            let keep = index >= num_splats || !merged[index];
+            index += 1;
+            keep
+        });
+        splats.clear_children();
+        report
+    }
+}
@@ -0,0 +1,310 @@
+use std::io::Write;
+
+use crate::{
+    antisplat::AntiSplatEncoder,
+    decoder::{SplatEncoding, SplatExtraInfo, SplatFileType, SplatGetter, SplatType},
+    gltf::GltfEncoder,
+    ksplat::KsplatEncoder,
+    npz::{NpyDtype, NpzEncoder},
+    ply::PlyEncoder,
+    rad::{RadAlphaEncoding, RadCenterEncoding, RadEncoder, RadOrientationEncoding, RadRgbEncoding, RadScalesEncoding, RadShEncoding},
+    sogs::SogsEncoder,
+    spz::SpzEncoder,
+};
+
+// Size vs. fidelity trade-off, mapped onto each format's own settings.
+// Formats with a fixed layout (KSPLAT, .splat, glTF) ignore it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplatPrecision {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+#[derive(Clone, Debug)]
+pub struct SplatEncodeOptions {
+    // Highest SH degree to write, None for as much as the source and format hold
+    pub max_sh: Option<usize>,
+    pub precision: SplatPrecision,
+    // Write the getter's LoD tree in formats that can store one. Otherwise,
+    // or when false, only the tree's leaf splats are written.
+    pub lod: bool,
+}
+
+impl Default for SplatEncodeOptions {
+    fn default() -> Self {
+        Self {
+            max_sh: None,
+            precision: SplatPrecision::default(),
+            lod: true,
+        }
+    }
+}
+
+impl SplatEncodeOptions {
+    pub fn with_max_sh(mut self, max_sh: usize) -> Self {
+        self.max_sh = Some(max_sh.min(3));
+        self
+    }
+
+    pub fn with_precision(mut self, precision: SplatPrecision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_lod(mut self, lod: bool) -> Self {
+        self.lod = lod;
+        self
+    }
+}
+
+pub struct MultiEncoder {
+    pub file_type: SplatFileType,
+    pub options: SplatEncodeOptions,
+    // Write .gltf JSON instead of a .glb container
+    gltf_json: bool,
+}
+
+impl MultiEncoder {
+    pub fn new(file_type: SplatFileType) -> Self {
+        Self {
+            file_type,
+            options: SplatEncodeOptions::default(),
+            gltf_json: false,
+        }
+    }
+
+    pub fn from_pathname(pathname: &str) -> Option<Self> {
+        let file_type = SplatFileType::from_pathname(pathname)?;
+        let clean_path = pathname.split(['?', '#']).next().unwrap_or(pathname);
+        let mut encoder = Self::new(file_type);
+        encoder.gltf_json = clean_path.to_lowercase().ends_with(".gltf");
+        Some(encoder)
+    }
+
+    pub fn with_options(mut self, options: SplatEncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn encode<G: SplatGetter, W: Write>(&self, getter: G, writer: &mut W) -> anyhow::Result<()> {
+        // Interior splats would render on top of their descendants without
+        // the tree, so dropping it keeps only the leaves
+        if getter.has_lod_tree() && !(self.options.lod && self.file_type.supports_lod()) {
+            return self.encode_getter(LeafSplats::new(getter), writer);
+        }
+        self.encode_getter(getter, writer)
+    }
+
+    fn encode_getter<G: SplatGetter, W: Write>(&self, getter: G, writer: &mut W) -> anyhow::Result<()> {
+        let SplatEncodeOptions { max_sh, precision, .. } = self.options;
+        let max_sh = max_sh.map(|max_sh| max_sh.min(3));
+
+        let bytes = match self.file_type {
+            SplatFileType::PLY => {
+                let mut encoder = PlyEncoder::new(getter).with_compressed(precision == SplatPrecision::Low);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh as u8);
+                }
+                return encoder.encode_to_writer(writer);
+            },
+            SplatFileType::SPZ => {
+                let version = if precision == SplatPrecision::High { 3 } else { 2 };
+                let mut encoder = SpzEncoder::new(getter).with_version(version);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh);
+                }
+                encoder.encode()?
+            },
+            SplatFileType::ANTISPLAT => AntiSplatEncoder::new(getter).with_max_sh(0).encode()?,
+            SplatFileType::KSPLAT => {
+                let mut encoder = KsplatEncoder::new(getter);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh as u8);
+                }
+                encoder.encode()?
+            },
+            SplatFileType::SOGS => {
+                // More k-means iterations give better fitting codebooks
+                let iterations = match precision {
+                    SplatPrecision::Low => 5,
+                    SplatPrecision::Medium => 10,
+                    SplatPrecision::High => 20,
+                };
+                let mut encoder = SogsEncoder::new(getter).with_iterations(iterations);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh as u8);
+                }
+                encoder.encode()?
+            },
+            SplatFileType::RAD => {
+                let mut encoder = RadEncoder::new(getter);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh);
+                }
+                match precision {
+                    // Auto already picks 8-bit RGB, scales and SH
+                    SplatPrecision::Low => {
+                        encoder = encoder
+                            .with_center_encoding(RadCenterEncoding::F16LeBytes)
+                            .with_alpha_encoding(RadAlphaEncoding::R8);
+                    },
+                    SplatPrecision::Medium => {},
+                    SplatPrecision::High => {
+                        encoder = encoder
+                            .with_center_encoding(RadCenterEncoding::F32)
+                            .with_alpha_encoding(RadAlphaEncoding::F16)
+                            .with_rgb_encoding(RadRgbEncoding::F16)
+                            .with_scales_encoding(RadScalesEncoding::LnF16)
+                            .with_orientation_encoding(RadOrientationEncoding::F16)
+                            .with_sh_encoding(RadShEncoding::F16);
+                    },
+                }
+                // Fitting encoding ranges needs at least one splat
+                if encoder.getter.num_splats() > 0 {
+                    encoder.resolve_encoding();
+                }
+                return encoder.encode(writer);
+            },
+            SplatFileType::GLTF => {
+                let mut encoder = GltfEncoder::new(getter).with_binary(!self.gltf_json);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh as u8);
+                }
+                encoder.encode()?
+            },
+            SplatFileType::NPZ => {
+                let dtype = if precision == SplatPrecision::Low { NpyDtype::F16 } else { NpyDtype::F32 };
+                let mut encoder = NpzEncoder::new(getter).with_dtype(dtype);
+                if let Some(max_sh) = max_sh {
+                    encoder = encoder.with_max_sh(max_sh as u8);
+                }
+                encoder.encode()?
+            },
+        };
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+impl SplatFileType {
+    pub fn supports_lod(self) -> bool {
+        matches!(self, Self::RAD | Self::SPZ)
+    }
+
+    pub fn encode<G: SplatGetter, W: Write>(self, getter: G, options: &SplatEncodeOptions, writer: &mut W) -> anyhow::Result<()> {
+        MultiEncoder::new(self).with_options(options.clone()).encode(getter, writer)
+    }
+}
+
+const LEAF_BATCH: usize = 65536;
+
+// Getter over only the leaf splats of an LoD tree, in their original order
+struct LeafSplats<G: SplatGetter> {
+    getter: G,
+    // Runs of consecutive leaves as (source start, output start, length)
+    runs: Vec<(usize, usize, usize)>,
+    num_splats: usize,
+}
+
+impl<G: SplatGetter> LeafSplats<G> {
+    fn new(mut getter: G) -> Self {
+        let total = getter.num_splats();
+        let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+        let mut num_splats = 0;
+        let mut child_count = vec![0u16; LEAF_BATCH.min(total)];
+        let mut base = 0;
+        while base < total {
+            let count = (total - base).min(LEAF_BATCH);
+            getter.get_child_count(base, count, &mut child_count[..count]);
+            for (i, &children) in child_count[..count].iter().enumerate() {
+                if children != 0 {
+                    continue;
+                }
+                match runs.last_mut() {
+                    Some((start, _, len)) if *start + *len == base + i => *len += 1,
+                    _ => runs.push((base + i, num_splats, 1)),
+                }
+                num_splats += 1;
+            }
+            base += count;
+        }
+        Self { getter, runs, num_splats }
+    }
+
+    // Calls get for each source run covering output splats base..base+count
+    fn forward<T>(&mut self, base: usize, count: usize, stride: usize, out: &mut [T], get: impl Fn(&mut G, usize, usize, &mut [T])) {
+        let mut run = self.runs.partition_point(|&(_, start, len)| start + len <= base);
+        let mut done = 0;
+        while done < count {
+            let (src, dst, len) = self.runs[run];
+            let offset = base + done - dst;
+            let n = (len - offset).min(count - done);
+            get(&mut self.getter, src + offset, n, &mut out[done * stride..(done + n) * stride]);
+            done += n;
+            run += 1;
+        }
+    }
+}
+
+impl<G: SplatGetter> SplatGetter for LeafSplats<G> {
+    fn num_splats(&self) -> usize { self.num_splats }
+    fn max_sh_degree(&self) -> usize { self.getter.max_sh_degree() }
+    fn flag_antialias(&self) -> bool { self.getter.flag_antialias() }
+    fn splat_type(&self) -> SplatType { self.getter.splat_type() }
+    fn has_normals(&self) -> bool { self.getter.has_normals() }
+    fn has_distortion(&self) -> bool { self.getter.has_distortion() }
+    fn get_encoding(&mut self) -> Option<SplatEncoding> {
+        self.getter.get_encoding().map(|encoding| SplatEncoding { lod_opacity: false, ..encoding })
+    }
+
+    fn get_center(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 3, out, |getter, base, count, out| getter.get_center(base, count, out));
+    }
+
+    fn get_opacity(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 1, out, |getter, base, count, out| getter.get_opacity(base, count, out));
+    }
+
+    fn get_rgb(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 3, out, |getter, base, count, out| getter.get_rgb(base, count, out));
+    }
+
+    fn get_scale(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 3, out, |getter, base, count, out| getter.get_scale(base, count, out));
+    }
+
+    fn get_quat(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 4, out, |getter, base, count, out| getter.get_quat(base, count, out));
+    }
+
+    fn get_sh1(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 9, out, |getter, base, count, out| getter.get_sh1(base, count, out));
+    }
+
+    fn get_sh2(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 15, out, |getter, base, count, out| getter.get_sh2(base, count, out));
+    }
+
+    fn get_sh3(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 21, out, |getter, base, count, out| getter.get_sh3(base, count, out));
+    }
+
+    fn get_extras(&self) -> Vec<SplatExtraInfo> { self.getter.get_extras() }
+
+    fn get_extra(&mut self, base: usize, count: usize, name: &str, out: &mut [f64]) {
+        let Some(dims) = self.getter.get_extras().iter().find(|extra| extra.name == name).map(|extra| extra.dims) else {
+            return;
+        };
+        self.forward(base, count, dims, out, |getter, base, count, out| getter.get_extra(base, count, name, out));
+    }
+
+    fn get_normal(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 3, out, |getter, base, count, out| getter.get_normal(base, count, out));
+    }
+
+    fn get_distortion(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        self.forward(base, count, 1, out, |getter, base, count, out| getter.get_distortion(base, count, out));
+    }
+}
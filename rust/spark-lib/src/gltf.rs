@@ -0,0 +1,612 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::decoder::{ChunkReceiver, SplatFileType, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::probe::{union_bounds, SplatFileInfo};
+
+pub const GLB_MAGIC: u32 = 0x46546c67; // "glTF"
+const GLB_CHUNK_JSON: u32 = 0x4e4f534a; // "JSON"
+const GLB_CHUNK_BIN: u32 = 0x004e4942; // "BIN\0"
+const MAX_SPLAT_CHUNK: usize = 65536;
+const SH_C0: f32 = 0.28209479177387814;
+
+const EXTENSION: &str = "KHR_gaussian_splatting";
+const ATTR_ROTATION: &str = "KHR_gaussian_splatting:ROTATION";
+const ATTR_SCALE: &str = "KHR_gaussian_splatting:SCALE";
+const ATTR_OPACITY: &str = "KHR_gaussian_splatting:OPACITY";
+
+const COMPONENT_BYTE: u32 = 5120;
+const COMPONENT_UNSIGNED_BYTE: u32 = 5121;
+const COMPONENT_SHORT: u32 = 5122;
+const COMPONENT_UNSIGNED_SHORT: u32 = 5123;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const COMPONENT_FLOAT: u32 = 5126;
+
+fn sh_attribute(degree: usize, coef: usize) -> String {
+    format!("KHR_gaussian_splatting:SH_DEGREE_{}_COEF_{}", degree, coef)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Gltf {
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    meshes: Vec<Mesh>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    ty: String,
+    min: Option<Vec<f32>>,
+    max: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    byte_length: usize,
+    uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Mesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    #[serde(default)]
+    extensions: HashMap<String, serde_json::Value>,
+}
+
+pub struct GltfDecoder<T: SplatReceiver> {
+    splats: T,
+    buffer: Vec<u8>,
+}
+
+impl<T: SplatReceiver> GltfDecoder<T> {
+    pub fn new(splats: T) -> Self {
+        Self {
+            splats,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn into_splats(self) -> T {
+        self.splats
+    }
+}
+
+impl<T: SplatReceiver> ChunkReceiver for GltfDecoder<T> {
+    fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        let (json, bin) = split_glb(&self.buffer)?;
+        let gltf: Gltf = serde_json::from_slice(json).context("Failed to parse glTF JSON")?;
+        let buffers = load_buffers(&gltf, bin)?;
+        decode_gltf(&gltf, &buffers, &mut self.splats)
+    }
+}
+
+fn split_glb(bytes: &[u8]) -> anyhow::Result<(&[u8], Option<&[u8]>)> {
+    if bytes.len() < 12 || read_u32_le(bytes, 0) != GLB_MAGIC {
+        // Plain .gltf JSON
+        return Ok((bytes, None));
+    }
+    let version = read_u32_le(bytes, 4);
+    if version != 2 {
+        return Err(anyhow!("Unsupported GLB version: {}", version));
+    }
+    let length = (read_u32_le(bytes, 8) as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32_le(bytes, offset) as usize;
+        let chunk_type = read_u32_le(bytes, offset + 4);
+        let start = offset + 8;
+        let end = start.checked_add(chunk_length).ok_or_else(|| anyhow!("Invalid GLB chunk length"))?;
+        if end > length {
+            return Err(anyhow!("Truncated GLB chunk"));
+        }
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(&bytes[start..end]),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(&bytes[start..end]),
+            _ => {},
+        }
+        offset = end;
+    }
+    let json = json.ok_or_else(|| anyhow!("Missing JSON chunk in GLB"))?;
+    Ok((json, bin))
+}
+
+fn load_buffers<'a>(gltf: &Gltf, bin: Option<&'a [u8]>) -> anyhow::Result<Vec<std::borrow::Cow<'a, [u8]>>> {
+    gltf.buffers.iter().enumerate().map(|(index, buffer)| {
+        let data: std::borrow::Cow<'a, [u8]> = match buffer.uri.as_deref() {
+            None if index == 0 => bin.ok_or_else(|| anyhow!("Missing GLB binary chunk"))?.into(),
+            None => return Err(anyhow!("glTF buffer {} has no data", index)),
+            Some(uri) => {
+                let Some(rest) = uri.strip_prefix("data:") else {
+                    return Err(anyhow!("External glTF buffer URIs are not supported: {}", uri));
+                };
+                let (_, encoded) = rest.split_once(";base64,").ok_or_else(|| anyhow!("Unsupported glTF data URI"))?;
+                base64_decode(encoded)?.into()
+            },
+        };
+        if data.len() < buffer.byte_length {
+            return Err(anyhow!("glTF buffer {} is shorter than byteLength", index));
+        }
+        Ok(data)
+    }).collect()
+}
+
+fn splat_primitives(gltf: &Gltf) -> Vec<&Primitive> {
+    gltf.meshes.iter()
+        .flat_map(|mesh| mesh.primitives.iter())
+        .filter(|p| p.extensions.contains_key(EXTENSION) || p.attributes.contains_key(ATTR_ROTATION))
+        .collect()
+}
+
+fn sh_degree_of(p: &Primitive) -> usize {
+    (1..=3).take_while(|&degree| {
+        (0..(2 * degree + 1)).all(|coef| p.attributes.contains_key(&sh_attribute(degree, coef)))
+    }).count()
+}
+
+// Reads splat count, SH degree and POSITION bounds from .gltf JSON or the
+// JSON chunk at the start of a .glb
+pub fn probe_gltf(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let json = if bytes.len() >= 4 && read_u32_le(bytes, 0) == GLB_MAGIC {
+        if bytes.len() < 20 {
+            return Some(SplatFileInfo::needs(Some(SplatFileType::GLTF), 20 - bytes.len()));
+        }
+        if read_u32_le(bytes, 16) != GLB_CHUNK_JSON {
+            return None;
+        }
+        let json_end = (read_u32_le(bytes, 12) as usize).checked_add(20)?;
+        if bytes.len() < json_end {
+            return Some(SplatFileInfo::needs(Some(SplatFileType::GLTF), json_end - bytes.len()));
+        }
+        &bytes[20..json_end]
+    } else {
+        bytes
+    };
+
+    let gltf: Gltf = serde_json::from_slice(json).ok()?;
+    let primitives = splat_primitives(&gltf);
+    if primitives.is_empty() {
+        return None;
+    }
+    let positions: Vec<&Accessor> = primitives.iter()
+        .map(|p| p.attributes.get("POSITION").and_then(|&index| gltf.accessors.get(index)))
+        .collect::<Option<_>>()?;
+    let bounds = union_bounds(positions.iter().map(|accessor| {
+        let (min, max) = (accessor.min.as_deref()?, accessor.max.as_deref()?);
+        Some(([*min.first()?, *min.get(1)?, *min.get(2)?], [*max.first()?, *max.get(1)?, *max.get(2)?]))
+    }));
+    Some(SplatFileInfo {
+        num_splats: Some(positions.iter().map(|accessor| accessor.count).sum()),
+        max_sh_degree: primitives.iter().map(|p| sh_degree_of(p)).max(),
+        lod_tree: Some(false),
+        ..SplatFileInfo::new(SplatFileType::GLTF)
+    }.with_bounds(bounds))
+}
+
+fn decode_gltf<T: SplatReceiver, B: AsRef<[u8]>>(gltf: &Gltf, buffers: &[B], splats: &mut T) -> anyhow::Result<()> {
+    let primitives = splat_primitives(gltf);
+    if primitives.is_empty() {
+        return Err(anyhow!("No {} primitives found in glTF", EXTENSION));
+    }
+
+    let max_sh_degree = primitives.iter().map(|p| sh_degree_of(p)).max().unwrap_or(0);
+
+    let mut counts = Vec::with_capacity(primitives.len());
+    for p in primitives.iter() {
+        let position = p.attributes.get("POSITION").ok_or_else(|| anyhow!("Missing POSITION attribute"))?;
+        let accessor = gltf.accessors.get(*position).ok_or_else(|| anyhow!("Invalid accessor index {}", position))?;
+        counts.push(accessor.count);
+    }
+    let num_splats = counts.iter().sum();
+    splats.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
+
+    let mut base = 0;
+    for (p, &count) in primitives.iter().zip(counts.iter()) {
+        let read = |name: &str, components: usize| -> anyhow::Result<Option<Vec<f32>>> {
+            match p.attributes.get(name) {
+                Some(&index) => read_accessor(gltf, buffers, index, components, count).map(Some),
+                None => Ok(None),
+            }
+        };
+
+        let center = read("POSITION", 3)?.unwrap();
+        let mut quat = read(ATTR_ROTATION, 4)?.ok_or_else(|| anyhow!("Missing {} attribute", ATTR_ROTATION))?;
+        let scale = read(ATTR_SCALE, 3)?.ok_or_else(|| anyhow!("Missing {} attribute", ATTR_SCALE))?;
+        let color = read("COLOR_0", 4)?;
+
+        let rgb: Vec<f32> = match read(&sh_attribute(0, 0), 3)? {
+            Some(dc) => dc.iter().map(|v| 0.5 + SH_C0 * v).collect(),
+            None => match color.as_ref() {
+                Some(color) => color.chunks(4).flat_map(|c| [c[0], c[1], c[2]]).collect(),
+                None => vec![1.0; count * 3],
+            },
+        };
+        let opacity: Vec<f32> = match read(ATTR_OPACITY, 1)? {
+            Some(opacity) => opacity,
+            None => match color.as_ref() {
+                Some(color) => color.chunks(4).map(|c| c[3]).collect(),
+                None => vec![1.0; count],
+            },
+        };
+
+        for q in quat.chunks_mut(4) {
+            let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                q.iter_mut().for_each(|v| *v /= norm);
+            }
+        }
+
+        // SH coefficients interleaved per splat as [coef][rgb]
+        let degree = sh_degree_of(p);
+        let mut sh = [Vec::new(), Vec::new(), Vec::new()];
+        for d in 1..=max_sh_degree {
+            let coefs = 2 * d + 1;
+            let mut values = vec![0.0f32; count * coefs * 3];
+            if d <= degree {
+                for coef in 0..coefs {
+                    let data = read(&sh_attribute(d, coef), 3)?.unwrap();
+                    for i in 0..count {
+                        let o = (i * coefs + coef) * 3;
+                        values[o..o + 3].copy_from_slice(&data[i * 3..i * 3 + 3]);
+                    }
+                }
+            }
+            sh[d - 1] = values;
+        }
+
+        let mut offset = 0;
+        while offset < count {
+            let chunk = (count - offset).min(MAX_SPLAT_CHUNK);
+            let [i3, i4] = [offset * 3, offset * 4];
+            splats.set_batch(base + offset, chunk, &SplatProps {
+                center: &center[i3..i3 + chunk * 3],
+                opacity: &opacity[offset..offset + chunk],
+                rgb: &rgb[i3..i3 + chunk * 3],
+                scale: &scale[i3..i3 + chunk * 3],
+                quat: &quat[i4..i4 + chunk * 4],
+                sh1: if max_sh_degree >= 1 { &sh[0][offset * 9..(offset + chunk) * 9] } else { &[] },
+                sh2: if max_sh_degree >= 2 { &sh[1][offset * 15..(offset + chunk) * 15] } else { &[] },
+                sh3: if max_sh_degree >= 3 { &sh[2][offset * 21..(offset + chunk) * 21] } else { &[] },
+                ..Default::default()
+            });
+            offset += chunk;
+        }
+        base += count;
+    }
+
+    splats.finish()
+}
+
+fn read_accessor<B: AsRef<[u8]>>(gltf: &Gltf, buffers: &[B], index: usize, components: usize, count: usize) -> anyhow::Result<Vec<f32>> {
+    let accessor = gltf.accessors.get(index).ok_or_else(|| anyhow!("Invalid accessor index {}", index))?;
+    let expected = match accessor.ty.as_str() {
+        "SCALAR" => 1,
+        "VEC2" => 2,
+        "VEC3" => 3,
+        "VEC4" => 4,
+        _ => return Err(anyhow!("Unsupported accessor type: {}", accessor.ty)),
+    };
+    if expected != components {
+        return Err(anyhow!("Accessor {} has type {}, expected {} components", index, accessor.ty, components));
+    }
+    if accessor.count != count {
+        return Err(anyhow!("Accessor {} count {} does not match POSITION count {}", index, accessor.count, count));
+    }
+
+    let mut out = vec![0.0f32; count * components];
+    let Some(view_index) = accessor.buffer_view else {
+        // Accessors without a bufferView are all zeros
+        return Ok(out);
+    };
+    let view = gltf.buffer_views.get(view_index).ok_or_else(|| anyhow!("Invalid bufferView index {}", view_index))?;
+    let data = buffers.get(view.buffer).ok_or_else(|| anyhow!("Invalid buffer index {}", view.buffer))?.as_ref();
+    let view_end = view.byte_offset.checked_add(view.byte_length)
+        .ok_or_else(|| anyhow!("bufferView {} out of range", view_index))?;
+    let data = data.get(view.byte_offset..view_end)
+        .ok_or_else(|| anyhow!("bufferView {} out of range", view_index))?;
+
+    let component_size = match accessor.component_type {
+        COMPONENT_BYTE | COMPONENT_UNSIGNED_BYTE => 1,
+        COMPONENT_SHORT | COMPONENT_UNSIGNED_SHORT => 2,
+        COMPONENT_UNSIGNED_INT | COMPONENT_FLOAT => 4,
+        other => return Err(anyhow!("Unsupported accessor componentType: {}", other)),
+    };
+    let stride = view.byte_stride.unwrap_or(component_size * components);
+    if count > 0 {
+        let end = (count - 1).checked_mul(stride)
+            .and_then(|last| last.checked_add(accessor.byte_offset))
+            .and_then(|last| last.checked_add(component_size * components));
+        if end.is_none_or(|end| end > data.len()) {
+            return Err(anyhow!("Accessor {} out of range", index));
+        }
+    }
+
+    for i in 0..count {
+        for c in 0..components {
+            let o = accessor.byte_offset + i * stride + c * component_size;
+            let b = &data[o..o + component_size];
+            out[i * components + c] = match (accessor.component_type, accessor.normalized) {
+                (COMPONENT_FLOAT, _) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                (COMPONENT_BYTE, true) => (b[0] as i8 as f32 / 127.0).max(-1.0),
+                (COMPONENT_BYTE, false) => b[0] as i8 as f32,
+                (COMPONENT_UNSIGNED_BYTE, true) => b[0] as f32 / 255.0,
+                (COMPONENT_UNSIGNED_BYTE, false) => b[0] as f32,
+                (COMPONENT_SHORT, true) => (i16::from_le_bytes([b[0], b[1]]) as f32 / 32767.0).max(-1.0),
+                (COMPONENT_SHORT, false) => i16::from_le_bytes([b[0], b[1]]) as f32,
+                (COMPONENT_UNSIGNED_SHORT, true) => u16::from_le_bytes([b[0], b[1]]) as f32 / 65535.0,
+                (COMPONENT_UNSIGNED_SHORT, false) => u16::from_le_bytes([b[0], b[1]]) as f32,
+                (_, _) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+            };
+        }
+    }
+    Ok(out)
+}
+
+pub struct GltfEncoder<T: SplatGetter> {
+    getter: T,
+    max_sh_out: Option<u8>,
+    binary: bool,
+}
+
+impl<T: SplatGetter> GltfEncoder<T> {
+    pub fn new(getter: T) -> Self {
+        Self {
+            getter,
+            max_sh_out: None,
+            binary: true,
+        }
+    }
+
+    pub fn with_max_sh(mut self, max_sh: u8) -> Self {
+        self.max_sh_out = Some(max_sh.min(3));
+        self
+    }
+
+    // Write a .glb container (default) or a .gltf JSON with an embedded data URI
+    pub fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    pub fn encode(mut self) -> anyhow::Result<Vec<u8>> {
+        let num_splats = self.getter.num_splats();
+        let sh_src = self.getter.max_sh_degree() as u8;
+        let sh_degree = self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) as usize;
+
+        let mut center = vec![0.0f32; num_splats * 3];
+        let mut opacity = vec![0.0f32; num_splats];
+        let mut rgb = vec![0.0f32; num_splats * 3];
+        let mut scale = vec![0.0f32; num_splats * 3];
+        let mut quat = vec![0.0f32; num_splats * 4];
+        let mut sh1: Vec<f32> = if sh_degree >= 1 { vec![0.0; num_splats * 9] } else { Vec::new() };
+        let mut sh2: Vec<f32> = if sh_degree >= 2 { vec![0.0; num_splats * 15] } else { Vec::new() };
+        let mut sh3: Vec<f32> = if sh_degree >= 3 { vec![0.0; num_splats * 21] } else { Vec::new() };
+
+        let mut base = 0usize;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            let [i3, i4] = [base * 3, base * 4];
+            self.getter.get_center(base, count, &mut center[i3..i3 + count * 3]);
+            self.getter.get_opacity(base, count, &mut opacity[base..base + count]);
+            self.getter.get_rgb(base, count, &mut rgb[i3..i3 + count * 3]);
+            self.getter.get_scale(base, count, &mut scale[i3..i3 + count * 3]);
+            self.getter.get_quat(base, count, &mut quat[i4..i4 + count * 4]);
+            if sh_degree >= 1 {
+                self.getter.get_sh1(base, count, &mut sh1[base * 9..(base + count) * 9]);
+            }
+            if sh_degree >= 2 {
+                self.getter.get_sh2(base, count, &mut sh2[base * 15..(base + count) * 15]);
+            }
+            if sh_degree >= 3 {
+                self.getter.get_sh3(base, count, &mut sh3[base * 21..(base + count) * 21]);
+            }
+            base += count;
+        }
+
+        for q in quat.chunks_mut(4) {
+            let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                q.iter_mut().for_each(|v| *v /= norm);
+            } else {
+                q.copy_from_slice(&[0.0, 0.0, 0.0, 1.0]);
+            }
+        }
+        let color: Vec<f32> = (0..num_splats)
+            .flat_map(|i| [rgb[i * 3], rgb[i * 3 + 1], rgb[i * 3 + 2], opacity[i]].map(|v| v.clamp(0.0, 1.0)))
+            .collect();
+        let dc: Vec<f32> = rgb.iter().map(|v| (v - 0.5) / SH_C0).collect();
+
+        let mut bin: Vec<u8> = Vec::new();
+        let mut attributes = serde_json::Map::new();
+        let mut accessors = Vec::new();
+        let mut buffer_views = Vec::new();
+
+        let mut add_attribute = |name: &str, ty: &str, components: usize, values: &mut dyn Iterator<Item = f32>, bounds: bool| {
+            let byte_offset = bin.len();
+            let mut min = vec![f32::INFINITY; components];
+            let mut max = vec![f32::NEG_INFINITY; components];
+            for (i, v) in values.enumerate() {
+                bin.extend_from_slice(&v.to_le_bytes());
+                min[i % components] = min[i % components].min(v);
+                max[i % components] = max[i % components].max(v);
+            }
+            let mut accessor = json!({
+                "bufferView": buffer_views.len(),
+                "componentType": COMPONENT_FLOAT,
+                "count": num_splats,
+                "type": ty,
+            });
+            if bounds && num_splats > 0 {
+                accessor["min"] = json!(min);
+                accessor["max"] = json!(max);
+            }
+            attributes.insert(name.to_string(), json!(accessors.len()));
+            accessors.push(accessor);
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": byte_offset,
+                "byteLength": bin.len() - byte_offset,
+            }));
+        };
+
+        add_attribute("POSITION", "VEC3", 3, &mut center.iter().copied(), true);
+        add_attribute("COLOR_0", "VEC4", 4, &mut color.iter().copied(), false);
+        add_attribute(ATTR_ROTATION, "VEC4", 4, &mut quat.iter().copied(), false);
+        add_attribute(ATTR_SCALE, "VEC3", 3, &mut scale.iter().copied(), false);
+        add_attribute(ATTR_OPACITY, "SCALAR", 1, &mut opacity.iter().copied(), false);
+        add_attribute(&sh_attribute(0, 0), "VEC3", 3, &mut dc.iter().copied(), false);
+        for (degree, values) in [(1, &sh1), (2, &sh2), (3, &sh3)] {
+            if degree > sh_degree {
+                break;
+            }
+            let coefs = 2 * degree + 1;
+            for coef in 0..coefs {
+                let mut coef_values = (0..num_splats).flat_map(|i| {
+                    let o = (i * coefs + coef) * 3;
+                    [values[o], values[o + 1], values[o + 2]]
+                });
+                add_attribute(&sh_attribute(degree, coef), "VEC3", 3, &mut coef_values, false);
+            }
+        }
+
+        let mut buffer = json!({ "byteLength": bin.len() });
+        if !self.binary {
+            buffer["uri"] = json!(format!("data:application/octet-stream;base64,{}", base64_encode(&bin)));
+        }
+
+        let root = json!({
+            "asset": { "version": "2.0", "generator": "spark" },
+            "extensionsUsed": [EXTENSION],
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{
+                "primitives": [{
+                    "mode": 0,
+                    "attributes": attributes,
+                    "extensions": {
+                        EXTENSION: {
+                            "kernel": "ellipse",
+                            "colorSpace": "srgb_rec709_display",
+                        }
+                    },
+                }],
+            }],
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [buffer],
+        });
+        let mut json_bytes = serde_json::to_vec(&root)?;
+
+        if !self.binary {
+            return Ok(json_bytes);
+        }
+
+        while json_bytes.len() % 4 != 0 {
+            json_bytes.push(b' ');
+        }
+        while bin.len() % 4 != 0 {
+            bin.push(0);
+        }
+        let total = 12 + 8 + json_bytes.len() + 8 + bin.len();
+        let mut out = Vec::with_capacity(total);
+        out.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        out.extend_from_slice(&2u32.to_le_bytes());
+        out.extend_from_slice(&(total as u32).to_le_bytes());
+        out.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        out.extend_from_slice(&json_bytes);
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        out.extend_from_slice(&bin);
+        Ok(out)
+    }
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        for k in 0..4 {
+            if k <= chunk.len() {
+                out.push(BASE64_ALPHABET[((n >> (18 - 6 * k)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return Err(anyhow!("Invalid base64 in glTF data URI")),
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
@@ -1,14 +1,15 @@
 
 use std::array;
 
-use glam::{Mat3A, Quat, Vec3, Vec3A};
+use glam::{Affine3A, Mat3A, Quat, Vec3, Vec3A};
 use half::f16;
 use smallvec::SmallVec;
 
-use crate::decoder::{SetSplatEncoding, SplatEncoding, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::decoder::{SetSplatEncoding, SplatEncoding, SplatExtraInfo, SplatGetter, SplatInit, SplatProps, SplatReceiver, SplatType};
 use crate::splat_encode::{encode_packed_splat, encode_sh1, encode_sh2, encode_sh3, get_splat_tex_size};
 use crate::symmat3::SymMat3;
-use crate::tsplat::{Tsplat, TsplatArray, TsplatMut, apply_swaps, compute_swaps, similarity_metric};
+use crate::transform::{transform_splats, SplatTransform};
+use crate::tsplat::{SplatExtra, Tsplat, TsplatArray, TsplatMut, apply_swaps, compute_swaps, planar_normal, planar_similarity_metric, similarity_metric};
 
 const INFLATE_SCALE: bool = false;
 
@@ -231,6 +232,11 @@ pub struct GsplatArray {
     pub sh1: Vec<GsplatSH1>,
     pub sh2: Vec<GsplatSH2>,
     pub sh3: Vec<GsplatSH3>,
+    pub extras: Vec<SplatExtra>,
+    pub splat_type: SplatType,
+    // Optional surfel terms, empty when absent
+    pub normals: Vec<Vec3>,
+    pub distortions: Vec<f32>,
 }
 
 impl TsplatArray for GsplatArray {
@@ -246,6 +252,10 @@ impl TsplatArray for GsplatArray {
             sh1: Vec::with_capacity(if max_sh_degree >= 1 { capacity } else { 0 }),
             sh2: Vec::with_capacity(if max_sh_degree >= 2 { capacity } else { 0 }),
             sh3: Vec::with_capacity(if max_sh_degree >= 3 { capacity } else { 0 }),
+            extras: Vec::new(),
+            splat_type: SplatType::Gsplat,
+            normals: Vec::new(),
+            distortions: Vec::new(),
         }
     }
 
@@ -447,6 +457,26 @@ impl TsplatArray for GsplatArray {
             self.sh3.push(GsplatSH3::new(total));
         }
 
+        if !self.normals.is_empty() {
+            // Normals of double-sided surfels are aligned to the first before averaging
+            let first = self.normals[indices[0]];
+            let total = indices.iter().zip(weights.iter()).fold(Vec3::ZERO, |total, (&index, &weight)| {
+                let normal = self.normals[index];
+                let sign = if normal.dot(first) < 0.0 { -weight } else { weight };
+                normal.mul_add(Vec3::splat(sign), total)
+            });
+            self.normals.push(total.try_normalize().unwrap_or(first));
+        }
+
+        if !self.distortions.is_empty() {
+            let total = indices.iter().zip(weights.iter()).map(|(&index, &weight)| weight * self.distortions[index]).sum();
+            self.distortions.push(total);
+        }
+
+        for extra in self.extras.iter_mut() {
+            extra.push_merged(indices, &weights);
+        }
+
         new_index
     }
 
@@ -480,7 +510,14 @@ impl TsplatArray for GsplatArray {
     }
 
     fn similarity(&self, a: usize, b: usize) -> f32 {
-        similarity_metric(&self.get(a), &self.get(b))
+        match self.splat_type {
+            SplatType::Gsplat => similarity_metric(&self.get(a), &self.get(b)),
+            SplatType::Surfel => planar_similarity_metric(&self.get(a), &self.get(b)),
+        }
+    }
+
+    fn is_planar(&self) -> bool {
+        self.splat_type == SplatType::Surfel
     }
 
     fn retain<F: (FnMut(&mut Gsplat) -> bool)>(&mut self, mut f: F) {
@@ -503,6 +540,17 @@ impl TsplatArray for GsplatArray {
             let mut bits = keep.iter();
             self.sh3.retain(|_sh3| *bits.next().unwrap());
         }
+        if !self.normals.is_empty() {
+            let mut bits = keep.iter();
+            self.normals.retain(|_normal| *bits.next().unwrap());
+        }
+        if !self.distortions.is_empty() {
+            let mut bits = keep.iter();
+            self.distortions.retain(|_distortion| *bits.next().unwrap());
+        }
+        for extra in self.extras.iter_mut() {
+            extra.retain(&keep);
+        }
     }
 
     fn retain_children<F: (FnMut(&mut Gsplat, &[usize]) -> bool)>(&mut self, mut f: F) {
@@ -533,6 +581,17 @@ impl TsplatArray for GsplatArray {
             let mut bits = keep.iter();
             self.sh3.retain(|_sh3| *bits.next().unwrap());
         }
+        if !self.normals.is_empty() {
+            let mut bits = keep.iter();
+            self.normals.retain(|_normal| *bits.next().unwrap());
+        }
+        if !self.distortions.is_empty() {
+            let mut bits = keep.iter();
+            self.distortions.retain(|_distortion| *bits.next().unwrap());
+        }
+        for extra in self.extras.iter_mut() {
+            extra.retain(&keep);
+        }
     }
 
     fn permute(&mut self, index_map: &[usize]) {
@@ -551,6 +610,15 @@ impl TsplatArray for GsplatArray {
         if !self.sh3.is_empty() {
             apply_swaps(&mut self.sh3, &swaps);
         }
+        if !self.normals.is_empty() {
+            apply_swaps(&mut self.normals, &swaps);
+        }
+        if !self.distortions.is_empty() {
+            apply_swaps(&mut self.distortions, &swaps);
+        }
+        for extra in self.extras.iter_mut() {
+            extra.apply_swaps(&swaps);
+        }
     }
 
     fn truncate(&mut self, count: usize) {
@@ -567,6 +635,15 @@ impl TsplatArray for GsplatArray {
         if !self.sh3.is_empty() {
             self.sh3.truncate(count);
         }
+        if !self.normals.is_empty() {
+            self.normals.truncate(count);
+        }
+        if !self.distortions.is_empty() {
+            self.distortions.truncate(count);
+        }
+        for extra in self.extras.iter_mut() {
+            extra.truncate(count);
+        }
     }
 
     fn new_from_index_map(&mut self, index_map: &[usize]) -> Self {
@@ -593,6 +670,18 @@ impl TsplatArray for GsplatArray {
             } else {
                 Vec::new()
             },
+            extras: self.extras.iter().map(|extra| extra.new_from_index_map(index_map)).collect(),
+            splat_type: self.splat_type,
+            normals: if !self.normals.is_empty() {
+                index_map.iter().map(|&i| self.normals[i]).collect()
+            } else {
+                Vec::new()
+            },
+            distortions: if !self.distortions.is_empty() {
+                index_map.iter().map(|&i| self.distortions[i]).collect()
+            } else {
+                Vec::new()
+            },
         }
     }
 
@@ -604,6 +693,33 @@ impl TsplatArray for GsplatArray {
             sh1: if self.sh1.is_empty() { Vec::new() } else { self.sh1[start..start + count].to_vec() },
             sh2: if self.sh2.is_empty() { Vec::new() } else { self.sh2[start..start + count].to_vec() },
             sh3: if self.sh3.is_empty() { Vec::new() } else { self.sh3[start..start + count].to_vec() },
+            extras: self.extras.iter().map(|extra| extra.clone_subset(start, count)).collect(),
+            splat_type: self.splat_type,
+            normals: if self.normals.is_empty() { Vec::new() } else { self.normals[start..start + count].to_vec() },
+            distortions: if self.distortions.is_empty() { Vec::new() } else { self.distortions[start..start + count].to_vec() },
+        }
+    }
+
+    fn apply_transform(&mut self, transform: Affine3A) {
+        let transform = SplatTransform::new(transform);
+        transform_splats(self, &transform);
+        for sh1 in self.sh1.iter_mut() {
+            let mut values = sh1.to_array();
+            transform.transform_sh(1, &mut values);
+            sh1.set_from_array(&values);
+        }
+        for sh2 in self.sh2.iter_mut() {
+            let mut values = sh2.to_array();
+            transform.transform_sh(2, &mut values);
+            sh2.set_from_array(&values);
+        }
+        for sh3 in self.sh3.iter_mut() {
+            let mut values = sh3.to_array();
+            transform.transform_sh(3, &mut values);
+            sh3.set_from_array(&values);
+        }
+        for normal in self.normals.iter_mut() {
+            *normal = transform.transform_normal(normal.to_vec3a()).to_vec3();
         }
     }
 }
@@ -639,9 +755,31 @@ impl GsplatArray {
             self.sh3.push(sh3.unwrap());
         }
 
+        if !self.normals.is_empty() {
+            self.normals.push(planar_normal(&&self.splats[index]).to_vec3());
+        }
+        if !self.distortions.is_empty() {
+            self.distortions.push(0.0);
+        }
+
+        for extra in self.extras.iter_mut() {
+            extra.push_default();
+        }
+
         index
     }
 
+    // Adds a zero-initialized extra channel, replacing any existing one with the same name
+    pub fn add_extra(&mut self, info: SplatExtraInfo) -> &mut SplatExtra {
+        self.extras.retain(|extra| extra.info.name != info.name);
+        self.extras.push(SplatExtra::new(info, self.splats.len()));
+        self.extras.last_mut().unwrap()
+    }
+
+    pub fn extra(&self, name: &str) -> Option<&SplatExtra> {
+        self.extras.iter().find(|extra| extra.info.name == name)
+    }
+
     pub fn to_packed_array(&self, encoding: &SplatEncoding) -> (usize, Vec<u32>) {
         let (_, _, _, max_splats) = get_splat_tex_size(self.splats.len());
         let mut packed = Vec::new();
@@ -753,6 +891,10 @@ impl SplatReceiver for GsplatArray {
             self.children.resize_with(init.num_splats, Default::default);
         }
 
+        self.extras.clear();
+        self.splat_type = SplatType::Gsplat;
+        self.normals.clear();
+        self.distortions.clear();
         Ok(())
     }
 
@@ -901,6 +1043,42 @@ impl SplatReceiver for GsplatArray {
             }
         }
     }
+
+    fn init_extras(&mut self, extras: &[SplatExtraInfo]) -> anyhow::Result<()> {
+        for info in extras {
+            self.add_extra(info.clone());
+        }
+        Ok(())
+    }
+
+    fn set_extra(&mut self, base: usize, count: usize, name: &str, values: &[f64]) {
+        if let Some(extra) = self.extras.iter_mut().find(|extra| extra.info.name == name) {
+            let dims = extra.info.dims;
+            extra.values[base * dims..(base + count) * dims].copy_from_slice(&values[..count * dims]);
+        }
+    }
+
+    fn set_splat_type(&mut self, splat_type: SplatType) -> anyhow::Result<()> {
+        self.splat_type = splat_type;
+        Ok(())
+    }
+
+    fn set_normal(&mut self, base: usize, count: usize, normal: &[f32]) {
+        if self.normals.is_empty() {
+            self.normals.resize(self.splats.len(), Vec3::Z);
+        }
+        for i in 0..count {
+            let i3 = i * 3;
+            self.normals[base + i] = Vec3::from_slice(&normal[i3..i3 + 3]);
+        }
+    }
+
+    fn set_distortion(&mut self, base: usize, count: usize, distortion: &[f32]) {
+        if self.distortions.is_empty() {
+            self.distortions.resize(self.splats.len(), 0.0);
+        }
+        self.distortions[base..base + count].copy_from_slice(&distortion[..count]);
+    }
 }
 
 impl SplatGetter for GsplatArray {
@@ -908,6 +1086,9 @@ impl SplatGetter for GsplatArray {
     fn max_sh_degree(&self) -> usize { self.max_sh_degree }
     fn flag_antialias(&self) -> bool { true }
     fn has_lod_tree(&self) -> bool { !self.children.is_empty() }
+    fn splat_type(&self) -> SplatType { self.splat_type }
+    fn has_normals(&self) -> bool { !self.normals.is_empty() }
+    fn has_distortion(&self) -> bool { !self.distortions.is_empty() }
     fn get_encoding(&mut self) -> Option<SplatEncoding> { None }
 
     fn get_center(&mut self, base: usize, count: usize, out: &mut [f32]) {
@@ -983,4 +1164,25 @@ impl SplatGetter for GsplatArray {
             out[i] = children.first().copied().unwrap_or(0) as usize;
         }
     }
+
+    fn get_extras(&self) -> Vec<SplatExtraInfo> {
+        self.extras.iter().map(|extra| extra.info.clone()).collect()
+    }
+
+    fn get_extra(&mut self, base: usize, count: usize, name: &str, out: &mut [f64]) {
+        if let Some(extra) = self.extra(name) {
+            let dims = extra.info.dims;
+            out[..count * dims].copy_from_slice(&extra.values[base * dims..(base + count) * dims]);
+        }
+    }
+
+    fn get_normal(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        for i in 0..count {
+            out[i * 3..i * 3 + 3].copy_from_slice(&self.normals[base + i].to_array());
+        }
+    }
+
+    fn get_distortion(&mut self, base: usize, count: usize, out: &mut [f32]) {
+        out[..count].copy_from_slice(&self.distortions[base..base + count]);
+    }
 }
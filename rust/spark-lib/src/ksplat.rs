@@ -1,7 +1,8 @@
 use anyhow::anyhow;
 use half::f16;
 
-use crate::decoder::{ChunkReceiver, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::decoder::{ChunkReceiver, SplatFileType, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::probe::SplatFileInfo;
 
 const HEADER_BYTES: usize = 4096;
 const SECTION_BYTES: usize = 1024;
@@ -376,11 +377,78 @@ impl<T: SplatReceiver> ChunkReceiver for KsplatDecoder<T> {
     }
 }
 
+// Section headers beyond this are unlikely in real files and would make
+// detection buffer an unreasonable amount
+const SNIFF_MAX_SECTIONS: usize = 4096;
+
+// Checks whether bytes look like the start of a .ksplat file, which has no
+// magic number. Returns None until enough of the header and section headers
+// are available to decide.
+pub fn sniff_ksplat(bytes: &[u8]) -> Option<bool> {
+    if bytes.len() >= 2 && (bytes[0] != 0 || bytes[1] < 1) {
+        return Some(false);
+    }
+    if bytes.len() < HEADER_BYTES {
+        return None;
+    }
+
+    let max_section_count = read_u32(bytes, 4).ok()? as usize;
+    let section_count = read_u32(bytes, 8).ok()? as usize;
+    let num_splats = read_u32(bytes, 16).ok()? as usize;
+    let compression_level = read_u16(bytes, 20).ok()?;
+    if max_section_count == 0 || max_section_count > SNIFF_MAX_SECTIONS || section_count > max_section_count || compression_level > 2 {
+        return Some(false);
+    }
+    if bytes.len() < HEADER_BYTES + max_section_count * SECTION_BYTES {
+        return None;
+    }
+
+    // Section splat counts must fit their capacity and add up to the total
+    let mut total_splats = 0usize;
+    for section in 0..max_section_count {
+        let header_offset = HEADER_BYTES + section * SECTION_BYTES;
+        let section_splat_count = read_u32(bytes, header_offset).ok()? as usize;
+        let section_max_splat_count = read_u32(bytes, header_offset + 4).ok()? as usize;
+        let sh_degree = read_u16(bytes, header_offset + 40).ok()? as usize;
+        if section_splat_count > section_max_splat_count || sh_degree >= SH_COMPONENTS.len() {
+            return Some(false);
+        }
+        total_splats += section_splat_count;
+    }
+    Some(total_splats == num_splats)
+}
+
+// Probes a prefix that may be a .ksplat file. Returns None if it isn't one,
+// and a result without file type while sniff_ksplat can't decide yet.
+pub fn probe_ksplat(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let header_bytes = match read_u32(bytes, 4) {
+        Ok(max_section_count) => HEADER_BYTES + (max_section_count as usize).min(SNIFF_MAX_SECTIONS) * SECTION_BYTES,
+        Err(_) => HEADER_BYTES,
+    };
+    match sniff_ksplat(bytes) {
+        None => return Some(SplatFileInfo::needs(None, header_bytes.saturating_sub(bytes.len()))),
+        Some(false) => return None,
+        Some(true) => {},
+    }
+    let max_section_count = read_u32(bytes, 4).ok()? as usize;
+    let max_sh_degree = (0..max_section_count)
+        .map(|section| read_u16(bytes, HEADER_BYTES + section * SECTION_BYTES + 40).unwrap_or(0) as usize)
+        .max()
+        .unwrap_or(0);
+    Some(SplatFileInfo {
+        num_splats: Some(read_u32(bytes, 16).ok()? as usize),
+        max_sh_degree: Some(max_sh_degree),
+        lod_tree: Some(false),
+        ..SplatFileInfo::new(SplatFileType::KSPLAT)
+    })
+}
+
 pub struct KsplatEncoder<T: SplatGetter> {
     getter: T,
     compression_level: u16,
     min_sh: f32,
     max_sh: f32,
+    max_sh_out: Option<u8>,
 }
 
 impl<T: SplatGetter> KsplatEncoder<T> {
@@ -390,9 +458,15 @@ impl<T: SplatGetter> KsplatEncoder<T> {
             compression_level: 0,
             min_sh: -1.5,
             max_sh: 1.5,
+            max_sh_out: None,
         }
     }
 
+    pub fn with_max_sh(mut self, max_sh: u8) -> Self {
+        self.max_sh_out = Some(max_sh.min(3));
+        self
+    }
+
     #[allow(dead_code)]
     pub fn with_compression_level(mut self, level: u16) -> Self {
         self.compression_level = level.min(2);
@@ -412,7 +486,8 @@ impl<T: SplatGetter> KsplatEncoder<T> {
         }
 
         let num_splats = self.getter.num_splats();
-        let sh_degree = self.getter.max_sh_degree().min(3);
+        let sh_src = self.getter.max_sh_degree().min(3) as u8;
+        let sh_degree = self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) as usize;
         let sh_components = SH_COMPONENTS[sh_degree];
         let comp = &KSPLAT_COMPRESSION[self.compression_level as usize];
         let bytes_per_splat = comp.bytes_per_center
@@ -12,18 +12,33 @@ pub mod antisplat;
 pub mod ksplat;
 pub mod sogs;
 pub mod rad;
+pub mod range_coder;
+pub mod gltf;
+pub mod npz;
 pub mod decoder;
+pub mod encoder;
+pub mod probe;
+pub mod transform;
+pub mod assemble;
 pub mod splat_encode;
 pub mod ordering;
 pub mod chunk_tree;
+pub mod bvh;
+pub mod outliers;
+pub mod dedup;
 pub mod sh_clustering;
 
 #[cfg(test)]
 mod tests {
     use super::{
         antisplat::{AntiSplatDecoder, AntiSplatEncoder},
+        decoder::{MultiDecoder, SplatFileType},
+        gltf::GltfEncoder,
         gsplat::*,
         ksplat::{KsplatDecoder, KsplatEncoder},
+        npz::{read_npy, write_npy, NpyDtype, NpzDecoder, NpzEncoder, NpzMapping},
+        rad::{RadCompression, RadDecoder, RadEncoder, RadFilter, RadReader},
+        sogs::{SogsDecoder, SogsEncoder},
         spz::{SpzDecoder, SpzEncoder},
     };
     use super::decoder::ChunkReceiver;
@@ -95,6 +110,1275 @@ mod tests {
         }
     }
 
+    #[test]
+    fn multi_decoder_sniffs_splat_and_ksplat() {
+        let make_array = |num_splats: usize| {
+            let mut arr = GsplatArray::new_capacity(num_splats, 0);
+            for i in 0..num_splats {
+                let t = i as f32 * 0.01;
+                arr.push_splat(make_splat([t, -t, 2.0 * t], 0.5, [0.2, 0.4, 0.6], [0.05, 0.1, 0.02], [0.1, 0.2, 0.3, 0.9]), None, None, None);
+            }
+            arr
+        };
+        let decode = |bytes: &[u8], pathname: Option<&str>| {
+            let mut dec = MultiDecoder::new(GsplatArray::new(), None, pathname);
+            for piece in bytes.chunks(1000) {
+                dec.push(piece)?;
+            }
+            dec.finish()?;
+            anyhow::Ok((dec.file_type, dec.into_splats()))
+        };
+
+        for num_splats in [2, 2000] {
+            let encoded = AntiSplatEncoder::new(make_array(num_splats)).encode().expect("encode ok");
+            let (file_type, out) = decode(&encoded, None).expect("decode ok");
+            assert_eq!(file_type, Some(SplatFileType::ANTISPLAT));
+            assert_eq!(out.len(), num_splats);
+            assert!(approx(out.splats[num_splats - 1].center.z, 0.02 * (num_splats - 1) as f32, 1e-5));
+
+            let encoded = KsplatEncoder::new(make_array(num_splats)).encode().expect("encode ok");
+            let (file_type, out) = decode(&encoded, Some("blob:abc")).expect("decode ok");
+            assert_eq!(file_type, Some(SplatFileType::KSPLAT));
+            assert_eq!(out.len(), num_splats);
+        }
+
+        // Content wins over a misleading extension
+        let encoded = AntiSplatEncoder::new(make_array(40)).encode().expect("encode ok");
+        let (file_type, _) = decode(&encoded, Some("scene.ksplat")).expect("decode ok");
+        assert_eq!(file_type, Some(SplatFileType::ANTISPLAT));
+
+        // A record count mismatch rules out .ksplat, leaving only the pathname
+        let mut encoded = KsplatEncoder::new(make_array(40)).encode().expect("encode ok");
+        encoded[16] += 1;
+        assert!(decode(&encoded, None).is_err());
+
+        let text = b"{ \"not\": \"a splat file\", \"padding\": \"0123456789abcdef\" }\n".repeat(8);
+        assert!(decode(&text, None).is_err());
+    }
+
+    #[test]
+    fn multi_decoder_unwraps_gzip_and_zip() {
+        use std::io::{Cursor, Write};
+        use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+        use crate::ply::PlyEncoder;
+
+        let num_splats = 3000;
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(num_splats, 0);
+            for i in 0..num_splats {
+                let t = i as f32 * 0.01;
+                arr.push_splat(make_splat([t, t.cos(), -t], 0.5, [0.2, 0.4, 0.6], [0.05, 0.1, 0.02], [0.1, 0.2, 0.3, 0.9]), None, None, None);
+            }
+            arr
+        };
+        let gzip = |data: &[u8]| {
+            let mut gz = vec![0x1f, 0x8b, 8, 0x08, 0, 0, 0, 0, 0, 0xff];
+            gz.extend_from_slice(b"inner-name\0");
+            gz.extend(miniz_oxide::deflate::compress_to_vec(data, 6));
+            gz.extend(crate::rad::crc32(data).to_le_bytes());
+            gz.extend((data.len() as u32).to_le_bytes());
+            gz
+        };
+        let decode = |bytes: &[u8], pathname: Option<&str>| {
+            let mut dec = MultiDecoder::new(GsplatArray::new(), None, pathname);
+            for piece in bytes.chunks(777) {
+                dec.push(piece)?;
+            }
+            dec.finish()?;
+            anyhow::Ok((dec.file_type, dec.into_splats()))
+        };
+        let check = |(file_type, out): (Option<SplatFileType>, GsplatArray), expected: SplatFileType| {
+            assert_eq!(file_type, Some(expected));
+            assert_eq!(out.len(), num_splats);
+            assert!(approx(out.splats[num_splats - 1].center.z, -0.01 * (num_splats - 1) as f32, 1e-4));
+        };
+
+        let ply = PlyEncoder::new(make_array()).encode().expect("encode ok");
+        check(decode(&gzip(&ply), Some("scene.ply.gz?v=2")).expect("decode ok"), SplatFileType::PLY);
+        let splat = AntiSplatEncoder::new(make_array()).encode().expect("encode ok");
+        check(decode(&gzip(&splat), None).expect("decode ok"), SplatFileType::ANTISPLAT);
+        let mut rad = Vec::new();
+        RadEncoder::new(make_array()).encode(&mut rad).expect("encode ok");
+        check(decode(&gzip(&rad), Some("scene.rad.gz")).expect("decode ok"), SplatFileType::RAD);
+
+        let truncated = gzip(&ply);
+        assert!(decode(&truncated[..truncated.len() / 2], None).is_err());
+
+        for method in [CompressionMethod::Deflated, CompressionMethod::Stored] {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            writer.start_file("scene.ply", SimpleFileOptions::default().compression_method(method)).unwrap();
+            writer.write_all(&ply).unwrap();
+            let zipped = writer.finish().unwrap().into_inner();
+            check(decode(&zipped, Some("download.zip")).expect("decode ok"), SplatFileType::PLY);
+        }
+    }
+
+    #[test]
+    fn multi_encoder_roundtrip_all_formats() {
+        use crate::encoder::{MultiEncoder, SplatEncodeOptions, SplatPrecision};
+
+        let num_splats = 50;
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(num_splats, 1);
+            for i in 0..num_splats {
+                let t = i as f32 * 0.1;
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&[0.1; 9]);
+                arr.push_splat(make_splat([t, 1.0 - t, 0.5], 0.6, [0.2, 0.4, 0.6], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]), Some(sh1), None, None);
+            }
+            arr
+        };
+
+        let pathnames = ["a.ply", "a.spz", "a.splat", "a.ksplat", "a.sog", "a.rad", "a.glb", "a.gltf", "a.npz"];
+        for pathname in pathnames {
+            for precision in [SplatPrecision::Low, SplatPrecision::High] {
+                let encoder = MultiEncoder::from_pathname(pathname).expect("known type")
+                    .with_options(SplatEncodeOptions::default().with_precision(precision));
+                let mut bytes = Vec::new();
+                encoder.encode(make_array(), &mut bytes).unwrap_or_else(|e| panic!("{pathname}: {e}"));
+
+                let mut dec = MultiDecoder::new(GsplatArray::new(), None, Some(pathname));
+                dec.push(&bytes).expect("push ok");
+                dec.finish().unwrap_or_else(|e| panic!("{pathname}: {e}"));
+                assert_eq!(dec.file_type, Some(encoder.file_type));
+                let out = dec.into_splats();
+                assert_eq!(out.len(), num_splats, "{pathname}");
+                let expected_sh = if encoder.file_type == SplatFileType::ANTISPLAT { 0 } else { 1 };
+                assert_eq!(out.max_sh_degree, expected_sh, "{pathname}");
+                let center = out.splats[num_splats - 1].center;
+                assert!(approx(center.x, 4.9, 0.05) && approx(center.y, -3.9, 0.05), "{pathname}: {center:?}");
+            }
+        }
+
+        // Formats without LoD support, or a dropped tree, get only the leaves
+        let make_tree = || {
+            let mut arr = make_array();
+            // Leaves 1 and 3.. are split by interior splat 2
+            arr.children = (0..num_splats).map(|i| match i {
+                0 => [1, 2].into_iter().collect(),
+                2 => (3..num_splats).collect(),
+                _ => Default::default(),
+            }).collect();
+            arr
+        };
+        for (file_type, lod, expected) in [
+            (SplatFileType::PLY, true, num_splats - 2),
+            (SplatFileType::RAD, false, num_splats - 2),
+            (SplatFileType::RAD, true, num_splats),
+        ] {
+            let mut bytes = Vec::new();
+            file_type.encode(make_tree(), &SplatEncodeOptions::default().with_lod(lod).with_max_sh(0), &mut bytes).expect("encode ok");
+            let mut dec = MultiDecoder::new(GsplatArray::new(), None, None);
+            dec.push(&bytes).expect("push ok");
+            dec.finish().expect("finish ok");
+            let out = dec.into_splats();
+            assert_eq!((out.len(), out.max_sh_degree, out.has_children()), (expected, 0, expected == num_splats), "{file_type:?} {lod}");
+            let second = if expected == num_splats { 0.1 } else { 0.3 };
+            assert!(approx(out.splats[1].center.x, second, 0.01), "{file_type:?} {lod}");
+        }
+    }
+
+    #[test]
+    fn probe_reads_headers_of_all_formats() {
+        use crate::encoder::{MultiEncoder, SplatEncodeOptions, SplatPrecision};
+        use crate::probe::probe;
+
+        let num_splats = 50;
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(num_splats, 1);
+            for i in 0..num_splats {
+                let t = i as f32 * 0.1;
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&[0.1; 9]);
+                arr.push_splat(make_splat([t, 1.0 - t, 0.5], 0.6, [0.2, 0.4, 0.6], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]), Some(sh1), None, None);
+            }
+            arr
+        };
+
+        let cases = [
+            ("a.ply", SplatPrecision::Medium, false),
+            ("a.ply", SplatPrecision::Low, true),
+            ("a.spz", SplatPrecision::Medium, false),
+            ("a.ksplat", SplatPrecision::Medium, false),
+            ("a.sog", SplatPrecision::Medium, true),
+            ("a.rad", SplatPrecision::Medium, true),
+            ("a.glb", SplatPrecision::Medium, true),
+            ("a.gltf", SplatPrecision::Medium, true),
+            ("a.npz", SplatPrecision::Medium, false),
+        ];
+        for (pathname, precision, has_bounds) in cases {
+            let encoder = MultiEncoder::from_pathname(pathname).expect("known type")
+                .with_options(SplatEncodeOptions::default().with_precision(precision));
+            let mut bytes = Vec::new();
+            encoder.encode(make_array(), &mut bytes).expect("encode ok");
+
+            let info = probe(&bytes).unwrap_or_else(|| panic!("{pathname}: not recognized"));
+            assert!(info.is_complete(), "{pathname}: {info:?}");
+            assert_eq!(info.file_type, Some(encoder.file_type), "{pathname}");
+            match encoder.file_type {
+                SplatFileType::NPZ => assert_eq!(info.num_splats, None),
+                _ => assert_eq!((info.num_splats, info.max_sh_degree), (Some(num_splats), Some(1)), "{pathname}"),
+            }
+            assert_eq!(info.bounds().is_some(), has_bounds, "{pathname}");
+            if let Some((min, max)) = info.bounds() {
+                // RAD bounds also cover the splats' extent, up to 3 * 0.2
+                let pad = if encoder.file_type == SplatFileType::RAD { 0.6 } else { 0.0 };
+                let within = |actual: f32, center: f32, outward: f32| (actual - center) * outward > -0.05 && (actual - center) * outward < pad + 0.05;
+                assert!(within(min[0], 0.0, -1.0) && within(max[0], 4.9, 1.0), "{pathname}: {min:?} {max:?}");
+                assert!(within(min[1], -3.9, -1.0) && within(max[1], 1.0, 1.0), "{pathname}: {min:?} {max:?}");
+            }
+        }
+
+        // Short prefixes report how much more of the header is needed
+        assert_eq!(probe(&[0x52]).and_then(|info| info.need_bytes), Some(3));
+        let mut rad = Vec::new();
+        SplatFileType::RAD.encode(make_array(), &SplatEncodeOptions::default(), &mut rad).expect("encode ok");
+        let short = probe(&rad[..6]).expect("rad prefix");
+        assert_eq!((short.file_type, short.need_bytes), (Some(SplatFileType::RAD), Some(2)));
+        let partial = probe(&rad[..20]).expect("rad prefix");
+        let header_len = 20 + partial.need_bytes.expect("incomplete header");
+        let info = probe(&rad[..header_len]).expect("rad header");
+        assert_eq!((info.num_splats, info.need_bytes), (Some(num_splats), None));
+
+        let mut ksplat = Vec::new();
+        SplatFileType::KSPLAT.encode(make_array(), &SplatEncodeOptions::default(), &mut ksplat).expect("encode ok");
+        assert_eq!(probe(&ksplat[..1000]).and_then(|info| info.file_type), None);
+        assert!(probe(&[0xffu8; 64]).is_none());
+
+        // .splat is recognized once enough records are sampled, with no count
+        let mut splat = Vec::new();
+        let mut arr = GsplatArray::new_capacity(2000, 0);
+        for i in 0..2000 {
+            arr.push_splat(make_splat([i as f32 * 0.01, 0.0, 1.0], 0.5, [0.5; 3], [0.1; 3], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        SplatFileType::ANTISPLAT.encode(arr, &SplatEncodeOptions::default(), &mut splat).expect("encode ok");
+        let partial = probe(&splat[..1600]).expect("splat prefix");
+        assert_eq!(partial.file_type, None);
+        let need_bytes = partial.need_bytes.expect("undecided");
+        let info = probe(&splat[..1600 + need_bytes]).expect("splat prefix");
+        assert_eq!((info.file_type, info.num_splats, info.max_sh_degree), (Some(SplatFileType::ANTISPLAT), None, Some(0)));
+    }
+
+    #[test]
+    fn apply_transform_rotates_sh_and_mirrors() {
+        use glam::{Affine3A, Mat3A};
+        use crate::csplat::CsplatArray;
+        use crate::decoder::{SetSplatEncoding, SplatInit, SplatReceiver, SplatType};
+        use crate::tsplat::Tsplat;
+
+        let covariance = |scales: Vec3A, quat: Quat| {
+            let m = Mat3A::from_quat(quat) * Mat3A::from_diagonal((scales * scales).into());
+            m * Mat3A::from_quat(quat).transpose()
+        };
+        let assert_mat = |a: Mat3A, b: Mat3A| {
+            for (x, y) in a.to_cols_array().iter().zip(b.to_cols_array().iter()) {
+                assert!(approx(*x, *y, 1e-3), "{a:?} != {b:?}");
+            }
+        };
+
+        // SH1 channel 0 holds f(d) = x, channel 1 f(d) = y, channel 2 f(d) = z
+        let sh1_xyz = [0.0, -0.5, 0.0, 0.0, 0.0, 0.5, -0.5, 0.0, 0.0];
+        let sh2_values: [f32; 15] = std::array::from_fn(|i| (i as f32 * 0.37).sin() * 0.5);
+        let sh3_values: [f32; 21] = std::array::from_fn(|i| (i as f32 * 0.61).cos() * 0.5);
+        let quat = Quat::from_xyzw(0.1, 0.2, 0.3, 0.9).normalize();
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(1, 3);
+            let mut sh1 = GsplatSH1::default();
+            sh1.set_from_array(&sh1_xyz);
+            let mut sh2 = GsplatSH2::default();
+            sh2.set_from_array(&sh2_values);
+            let mut sh3 = GsplatSH3::default();
+            sh3.set_from_array(&sh3_values);
+            arr.push_splat(make_splat([1.0, 2.0, 3.0], 0.5, [0.5; 3], [0.1, 0.2, 0.3], quat.to_array()), Some(sh1), Some(sh2), Some(sh3));
+            arr
+        };
+
+        // 90 degrees about z maps x to y and y to -x
+        let rotate = Affine3A::from_rotation_translation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), glam::Vec3::new(0.0, 0.0, 1.0));
+        let mut arr = make_array();
+        arr.apply_transform(rotate);
+        let splat = arr.get(0);
+        assert!((splat.center() - Vec3A::new(-2.0, 1.0, 4.0)).length() < 1e-5);
+        assert_mat(covariance(splat.scales(), splat.quaternion()), Mat3A::from(rotate.matrix3) * covariance(Vec3A::new(0.1, 0.2, 0.3), quat) * rotate.matrix3.transpose());
+        let sh1 = arr.get_sh1(0);
+        let expected = [-0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.0];
+        for (a, b) in sh1.iter().zip(expected.iter()) {
+            assert!(approx(*a, *b, 2e-3), "{sh1:?}");
+        }
+
+        // Four quarter turns bring SH2 and SH3 back
+        for _ in 0..3 {
+            arr.apply_transform(rotate);
+        }
+        for (a, b) in arr.get_sh2(0).iter().zip(sh2_values.iter()).chain(arr.get_sh3(0).iter().zip(sh3_values.iter())) {
+            assert!(approx(*a, *b, 5e-3), "{a} != {b}");
+        }
+
+        // Mirroring x negates f(d) = x and keeps the covariance reflected
+        let mirror = Affine3A::from_scale(glam::Vec3::new(-1.0, 1.0, 1.0));
+        let mut arr = make_array();
+        arr.apply_transform(mirror);
+        let splat = arr.get(0);
+        assert!((splat.center() - Vec3A::new(-1.0, 2.0, 3.0)).length() < 1e-5);
+        assert_mat(covariance(splat.scales(), splat.quaternion()), Mat3A::from(mirror.matrix3) * covariance(Vec3A::new(0.1, 0.2, 0.3), quat) * mirror.matrix3);
+        let sh1 = arr.get_sh1(0);
+        let expected = [0.0, -0.5, 0.0, 0.0, 0.0, 0.5, 0.5, 0.0, 0.0];
+        for (a, b) in sh1.iter().zip(expected.iter()) {
+            assert!(approx(*a, *b, 2e-3), "{sh1:?}");
+        }
+
+        // Non-uniform scale goes through the covariance
+        let stretch = Affine3A::from_scale(glam::Vec3::new(2.0, 1.0, 0.5));
+        let mut arr = make_array();
+        arr.apply_transform(stretch);
+        let splat = arr.get(0);
+        assert!((splat.center() - Vec3A::new(2.0, 2.0, 1.5)).length() < 1e-5);
+        assert_mat(covariance(splat.scales(), splat.quaternion()), Mat3A::from(stretch.matrix3) * covariance(Vec3A::new(0.1, 0.2, 0.3), quat) * stretch.matrix3);
+
+        // Csplat SH go through the same rotation at i8 precision
+        let mut csplats = CsplatArray::new();
+        csplats.init_splats(&SplatInit { num_splats: 1, max_sh_degree: 1, lod_tree: false }).unwrap();
+        csplats.set_center(0, 1, &[1.0, 2.0, 3.0]);
+        csplats.set_opacity(0, 1, &[0.5]);
+        csplats.set_rgb(0, 1, &[0.5; 3]);
+        csplats.set_scale(0, 1, &[0.1, 0.2, 0.3]);
+        csplats.set_quat(0, 1, &quat.to_array());
+        SplatReceiver::set_sh1(&mut csplats, 0, 1, &sh1_xyz);
+        csplats.apply_transform(rotate);
+        assert!((csplats.get(0).center() - Vec3A::new(-2.0, 1.0, 4.0)).length() < 1e-2);
+        let sh1 = csplats.get_sh1(0);
+        let expected = [-0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.0];
+        for (a, b) in sh1.iter().zip(expected.iter()) {
+            assert!(approx(*a, *b, 1e-2), "{sh1:?}");
+        }
+
+        // Csplat scales past the ln-scale range widen it instead of clamping
+        csplats.set_encoding(&SetSplatEncoding { ln_scale_min: Some(-3.0), ln_scale_max: Some(-1.0), ..Default::default() }).unwrap();
+        csplats.set_scale(0, 1, &[0.1, 0.2, 0.3]);
+        csplats.apply_transform(Affine3A::from_scale(glam::Vec3::splat(100.0)));
+        let scales = csplats.get(0).scales();
+        assert!((scales - Vec3A::new(10.0, 20.0, 30.0)).abs().max_element() < 0.5, "{scales:?}");
+
+        // Surfels stay flat under shear
+        let shear = Affine3A::from_mat3(glam::Mat3::from_cols_array(&[1.0, 0.3, 0.1, 0.2, 1.5, 0.4, 0.0, 0.7, 0.8]));
+        let mut arr = GsplatArray::new_capacity(1, 0);
+        arr.push_splat(make_splat([0.0; 3], 0.5, [0.5; 3], [0.1, 0.2, 0.0], quat.to_array()), None, None, None);
+        arr.set_splat_type(SplatType::Surfel).unwrap();
+        arr.apply_transform(shear);
+        assert_eq!(arr.get(0).scales().z, 0.0);
+    }
+
+    #[test]
+    fn assembler_merges_parts_with_transforms() {
+        use glam::Affine3A;
+        use crate::assemble::{AssembleLod, SplatAssembler};
+        use crate::csplat::CsplatArray;
+        use crate::decoder::{SetSplatEncoding, SplatGetter, SplatInit, SplatReceiver};
+        use crate::tsplat::Tsplat;
+
+        // Two leaves under a merged root at index 2
+        let make_tree = |x: f32, sh_degree: usize| {
+            let mut arr = GsplatArray::new_capacity(3, sh_degree);
+            for i in 0..2 {
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&[0.25; 9]);
+                let sh1 = (sh_degree >= 1).then_some(sh1);
+                arr.push_splat(make_splat([x + i as f32, 0.0, 0.0], 0.5, [0.5; 3], [0.1; 3], [0.0, 0.0, 0.0, 1.0]), sh1, None, None);
+            }
+            arr.prepare_children();
+            arr.new_merged(&[0, 1], 0.0);
+            arr
+        };
+
+        let assembled = SplatAssembler::new()
+            .with_part(make_tree(0.0, 0), None)
+            .with_part(make_tree(0.0, 1), Some(Affine3A::from_translation(glam::Vec3::new(10.0, 0.0, 0.0))))
+            .assemble()
+            .expect("assemble ok");
+        let merged = assembled.splats;
+        assert_eq!(assembled.part_ranges, vec![0..2, 2..4]);
+        assert_eq!((merged.len(), TsplatArray::max_sh_degree(&merged), merged.has_children()), (4, 1, false));
+        assert!(approx(merged.get(3).center().x, 11.0, 1e-6));
+        assert_eq!(merged.get_sh1(0), [0.0; 9]);
+        assert_eq!(merged.get_sh1(2), [0.25; 9]);
+
+        let assembled = SplatAssembler::new()
+            .with_lod(AssembleLod::Rebase)
+            .with_part(make_tree(0.0, 1), None)
+            .with_part(make_tree(5.0, 1), None)
+            .assemble()
+            .expect("assemble ok");
+        let merged = assembled.splats;
+        assert_eq!((assembled.root, merged.len()), (Some(0), 7));
+        assert_eq!(merged.get_children(0).to_vec(), vec![1, 2]);
+        let mut leaves = 0;
+        for index in 0..merged.len() {
+            let children = merged.get_children(index);
+            if children.is_empty() {
+                leaves += 1;
+            } else {
+                assert_eq!(children.to_vec(), (children[0]..children[0] + children.len()).collect::<Vec<_>>());
+                assert!(children[0] > index);
+            }
+        }
+        assert_eq!(leaves, 4);
+        assert!(SplatAssembler::new().with_lod(AssembleLod::Rebase).with_part(GsplatArray::new_capacity(0, 0), None)
+            .with_part({ let mut a = make_tree(0.0, 0); a.clear_children(); a }, None).assemble().is_err());
+        let flatten = Affine3A::from_scale(glam::Vec3::new(1.0, 1.0, 0.0));
+        assert!(SplatAssembler::new().with_part(make_tree(0.0, 0), Some(flatten)).assemble().is_err());
+
+        // Csplat SH stay the same across parts with different shMax
+        let make_csplat = |sh1_max: f32, sh1: f32| {
+            let mut arr = CsplatArray::new();
+            arr.set_encoding(&SetSplatEncoding { sh1_max: Some(sh1_max), ..Default::default() }).unwrap();
+            arr.init_splats(&SplatInit { num_splats: 1, max_sh_degree: 1, lod_tree: false }).unwrap();
+            arr.set_center(0, 1, &[0.0; 3]);
+            arr.set_opacity(0, 1, &[0.5]);
+            arr.set_rgb(0, 1, &[0.5; 3]);
+            arr.set_scale(0, 1, &[0.1; 3]);
+            arr.set_quat(0, 1, &[0.0, 0.0, 0.0, 1.0]);
+            SplatReceiver::set_sh1(&mut arr, 0, 1, &[sh1; 9]);
+            arr
+        };
+        let assembled = SplatAssembler::new()
+            .with_part(make_csplat(0.5, 0.4), None)
+            .with_part(make_csplat(2.0, 1.5), None)
+            .assemble()
+            .expect("assemble ok");
+        let mut merged = assembled.splats;
+        assert!(approx(SplatGetter::get_encoding(&mut merged).unwrap().sh1_max, 2.0, 1e-6));
+        let sh1 = [merged.get_sh1(0)[0] * 2.0, merged.get_sh1(1)[0] * 2.0];
+        assert!(approx(sh1[0], 0.4, 0.02) && approx(sh1[1], 1.5, 0.02), "{sh1:?}");
+    }
+
+    #[test]
+    fn bvh_queries_match_brute_force() {
+        use glam::Mat4;
+        use crate::bvh::{Frustum, SplatBvh};
+        use crate::chunk_tree::Aabb;
+        use crate::tsplat::Tsplat;
+
+        let mut seed = 12345u32;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+        let mut arr = GsplatArray::new_capacity(500, 0);
+        for _ in 0..500 {
+            let center = [random() * 10.0, random() * 10.0, random() * 10.0];
+            let scales = [0.02 + random() * 0.1, 0.02 + random() * 0.1, 0.02 + random() * 0.1];
+            let quat = Quat::from_xyzw(random() - 0.5, random() - 0.5, random() - 0.5, random() - 0.5).normalize();
+            arr.push_splat(make_splat(center, 0.5, [0.5; 3], scales, quat.to_array()), None, None, None);
+        }
+        let bvh = SplatBvh::new_with(&arr, 3.0, 4);
+        let aabbs: Vec<Aabb> = (0..arr.len()).map(|i| Aabb::from_splat(&arr.get(i), 3.0)).collect();
+        let sorted = |mut v: Vec<usize>| { v.sort(); v };
+
+        let query = Aabb { min: Vec3A::new(2.0, 3.0, 1.0), max: Vec3A::new(5.0, 6.0, 4.0) };
+        let expected: Vec<usize> = (0..arr.len()).filter(|&i| aabbs[i].overlaps(&query)).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(sorted(bvh.query_box(&query)), expected);
+
+        let (center, radius) = (Vec3A::new(5.0, 5.0, 5.0), 2.0);
+        let expected: Vec<usize> = (0..arr.len())
+            .filter(|&i| center.clamp(aabbs[i].min, aabbs[i].max).distance(center) <= radius).collect();
+        assert_eq!(sorted(bvh.query_sphere(center, radius)), expected);
+
+        // Camera at the origin looking into the cube, against the frustum's own AABB test
+        let view = Mat4::look_at_rh(glam::Vec3::ZERO, glam::Vec3::new(5.0, 5.0, 5.0), glam::Vec3::Y);
+        let frustum = Frustum::from_view_projection(Mat4::perspective_rh(0.5, 1.0, 0.1, 8.0) * view);
+        let expected: Vec<usize> = (0..arr.len()).filter(|&i| frustum.overlaps(&aabbs[i])).collect();
+        assert!(!expected.is_empty() && expected.len() < arr.len());
+        assert_eq!(sorted(bvh.query_frustum(&frustum)), expected);
+
+        let point = Vec3A::new(3.0, 7.0, 2.0);
+        let mut expected: Vec<(usize, f32)> = (0..arr.len()).map(|i| (i, arr.get(i).center().distance(point))).collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+        expected.truncate(10);
+        assert_eq!(bvh.nearest_k(point, 10), expected);
+
+        // Ray aimed at a splat center hits it or something in front of it
+        let target = arr.get(123).center();
+        let origin = Vec3A::new(-1.0, -1.0, -1.0);
+        let hit = bvh.raycast(&arr, origin, target - origin, 100.0).expect("hit");
+        assert!(hit.distance <= target.distance(origin));
+        let flat = SplatBvh::new_with(&arr, 3.0, arr.len());
+        for i in 0..20 {
+            let direction = arr.get(i * 20).center() - origin;
+            assert_eq!(bvh.raycast(&arr, origin, direction, 100.0), flat.raycast(&arr, origin, direction, 100.0));
+        }
+        assert!(bvh.raycast(&arr, origin, Vec3A::new(-1.0, 0.0, 0.0), 100.0).is_none());
+    }
+
+    #[test]
+    fn outlier_filter_removes_floaters() {
+        use crate::outliers::OutlierFilter;
+        use crate::tsplat::Tsplat;
+
+        // 10x10x10 grid with 0.1 spacing, plus floaters appended at the end
+        let mut arr = GsplatArray::new_capacity(1003, 0);
+        for i in 0..1000 {
+            let center = [(i % 10) as f32 * 0.1, (i / 10 % 10) as f32 * 0.1, (i / 100) as f32 * 0.1];
+            arr.push_splat(make_splat(center, 0.9, [0.5; 3], [0.05; 3], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        // Far away and faint, far away but opaque, and inside but oversized
+        arr.push_splat(make_splat([5.0, 5.0, 5.0], 0.1, [0.5; 3], [0.05; 3], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        arr.push_splat(make_splat([-5.0, 5.0, 5.0], 0.9, [0.5; 3], [0.05; 3], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        arr.push_splat(make_splat([0.45, 0.45, 0.45], 0.1, [0.5; 3], [2.0; 3], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+
+        let (flags, report) = OutlierFilter::new().with_std_ratio(2.0).find(&arr);
+        assert_eq!((report.statistical, report.flagged), (2, 2));
+        assert!(flags[1000] && flags[1001]);
+
+        let (flags, report) = OutlierFilter::new().with_radius(0.5, 3).find(&arr);
+        assert_eq!((report.radius, report.flagged), (2, 2));
+        assert!(flags[1000] && flags[1001]);
+
+        let (flags, report) = OutlierFilter::new().with_size_ratio(10.0).find(&arr);
+        assert_eq!(report.size, 1);
+        assert!(flags[1002]);
+
+        // Opacity limit spares the opaque floater
+        let mut filtered = arr.clone_subset(0, arr.len());
+        let report = OutlierFilter::new().with_std_ratio(2.0).with_size_ratio(10.0).with_max_opacity(0.5).remove(&mut filtered).expect("remove ok");
+        assert_eq!((report.statistical, report.size, report.flagged), (1, 1, 2));
+        assert_eq!(filtered.len(), 1001);
+        assert!(approx(filtered.get(1000).center().x, -5.0, 1e-6));
+
+        // Removing splats from an LoD tree would orphan children
+        filtered.prepare_children();
+        filtered.new_merged(&[0, 1], 0.0);
+        assert!(OutlierFilter::new().with_std_ratio(2.0).remove(&mut filtered).is_err());
+        assert_eq!(filtered.len(), 1002);
+    }
+
+    #[test]
+    fn dedup_merges_near_identical_splats() {
+        use crate::csplat::CsplatArray;
+        use crate::decoder::copy_getter_to_receiver;
+        use crate::dedup::Dedup;
+        use crate::tsplat::Tsplat;
+
+        // Ten distinct splats, the first three with a near copy and the first
+        // also with a differently colored one on top of it
+        let mut arr = GsplatArray::new_capacity(14, 1);
+        let mut sh1 = GsplatSH1::default();
+        sh1.set_from_array(&[0.2; 9]);
+        for i in 0..10 {
+            arr.push_splat(make_splat([i as f32, 0.0, 0.0], 0.5, [0.5; 3], [0.1, 0.2, 0.3], [0.0, 0.0, 0.0, 1.0]), Some(sh1.clone()), None, None);
+        }
+        for i in 0..3 {
+            arr.push_splat(make_splat([i as f32 + 0.001, 0.0, 0.0], 0.5, [0.51; 3], [0.1, 0.2, 0.3], [0.0, 0.0, 0.0, 1.0]), Some(sh1.clone()), None, None);
+        }
+        arr.push_splat(make_splat([0.0, 0.0, 0.0], 0.5, [0.0, 1.0, 0.0], [0.1, 0.2, 0.3], [0.0, 0.0, 0.0, 1.0]), Some(sh1.clone()), None, None);
+
+        let mut csplats = CsplatArray::new();
+        copy_getter_to_receiver(&mut arr.clone_subset(0, arr.len()), &mut csplats).unwrap();
+
+        let report = Dedup::new().merge(&mut arr);
+        assert_eq!((report.groups, report.merged, report.removed()), (3, 6, 3));
+        assert_eq!((arr.len(), arr.has_children()), (11, false));
+        // Merges go at the end, after the untouched originals
+        let merged = arr.get(8);
+        assert!(approx(merged.center().x, 0.0005, 1e-5) && approx(merged.opacity(), 0.75, 1e-3));
+        assert!(approx(merged.scales().max_element(), 0.3, 1e-3) && approx(merged.scales().min_element(), 0.1, 1e-3));
+        assert!(approx(arr.get_sh1(8)[0], 0.2, 1e-3));
+        assert!(approx(arr.get(7).rgb().y, 1.0, 1e-6));
+
+        let report = Dedup::new().merge(&mut csplats);
+        assert_eq!((report.groups, report.merged), (3, 6));
+        assert_eq!(csplats.len(), 11);
+        assert!(approx(csplats.get(8).center().x, 0.0005, 1e-2) && approx(csplats.get(8).opacity(), 0.75, 1e-2));
+
+        // Nothing above a strict threshold
+        let report = Dedup::new().with_threshold(0.99999).merge(&mut arr);
+        assert_eq!((report.groups, arr.len()), (0, 11));
+    }
+
+    #[test]
+    fn sogs_roundtrip_sh_degree1() {
+        let mut arr = GsplatArray::new_capacity(3, 1);
+        let splats = [
+            make_splat([0.5, -1.5, 2.0], 0.8, [0.3, 0.6, 0.9], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]),
+            make_splat([-3.0, 0.25, 10.0], 0.2, [0.9, 0.1, 0.4], [0.5, 0.4, 0.3], [-0.7, 0.1, 0.2, 0.3]),
+            make_splat([0.0, 4.0, -0.75], 1.0, [0.5, 0.5, 0.5], [1.0, 2.0, 0.01], [0.2, 0.8, -0.1, 0.1]),
+        ];
+        let sh1_vals: [[f32; 9]; 3] = [
+            [0.1, 0.4, 0.7, 0.2, 0.5, 0.8, 0.3, 0.6, 0.9],
+            [-0.3, 0.1, 0.4, 0.2, -0.2, 0.5, 0.0, 0.3, -0.1],
+            [0.05, -0.05, 0.15, -0.15, 0.25, -0.25, 0.35, -0.35, 0.0],
+        ];
+        for (splat, vals) in splats.iter().zip(sh1_vals.iter()) {
+            let mut sh1 = GsplatSH1::default();
+            sh1.set_from_array(vals);
+            arr.push_splat(splat.clone(), Some(sh1), None, None);
+        }
+
+        let encoded = SogsEncoder::new(arr).encode().expect("encode ok");
+        let mut dec = SogsDecoder::new(GsplatArray::new());
+        dec.push(&encoded).expect("push ok");
+        dec.finish().expect("finish ok");
+        let out = dec.into_splats();
+
+        assert_eq!(out.max_sh_degree, 1);
+        assert_eq!(out.len(), 3);
+        let quat = |s: &Gsplat| Quat::from_array(s.quaternion.map(|v| v.to_f32())).normalize();
+        for (i, expected) in splats.iter().enumerate() {
+            let got = &out.splats[i];
+            for d in 0..3 {
+                assert!(approx(got.center[d], expected.center[d], 1e-3), "center[{i}][{d}] {} vs {}", got.center[d], expected.center[d]);
+            }
+            assert!(approx(got.opacity.to_f32(), expected.opacity.to_f32(), 0.01));
+            for d in 0..3 {
+                assert!(approx(got.rgb[d].to_f32(), expected.rgb[d].to_f32(), 0.01), "rgb[{i}][{d}]");
+                assert!(approx(got.ln_scales[d].to_f32(), expected.ln_scales[d].to_f32(), 0.01), "ln_scales[{i}][{d}]");
+            }
+            let (got_q, expected_q) = (quat(got), quat(expected));
+            assert!(got_q.dot(expected_q).abs() > 0.999, "quat[{i}] {:?} vs {:?}", got_q, expected_q);
+            let got_sh1 = out.sh1[i].to_array();
+            for k in 0..9 {
+                assert!(approx(got_sh1[k], sh1_vals[i][k], 0.01), "sh1[{i}][{k}] {} vs {}", got_sh1[k], sh1_vals[i][k]);
+            }
+        }
+
+        // Empty arrays still write an SH palette
+        let encoded = SogsEncoder::new(GsplatArray::new_capacity(0, 1)).encode().expect("encode empty ok");
+        let mut dec = SogsDecoder::new(GsplatArray::new());
+        dec.push(&encoded).expect("push ok");
+        dec.finish().expect("finish ok");
+        assert_eq!(dec.into_splats().len(), 0);
+    }
+
+    #[test]
+    fn sogs_streaming_data_descriptors() {
+        use std::io::{Cursor, Read, Write};
+        use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+        let mut arr = GsplatArray::new_capacity(100, 1);
+        for i in 0..100 {
+            let t = i as f32 / 100.0;
+            let mut sh1 = GsplatSH1::default();
+            sh1.set_from_array(&[t - 0.5; 9]);
+            let splat = make_splat([t * 4.0 - 2.0, t, -t], 0.3 + 0.5 * t, [t, 1.0 - t, 0.5], [0.05 + t, 0.1, 0.2], [t, 0.2, 0.3, 0.9]);
+            arr.push_splat(splat, Some(sh1), None, None);
+        }
+        let encoded = SogsEncoder::new(arr).encode().expect("encode ok");
+
+        let mut dec = SogsDecoder::new(GsplatArray::new());
+        dec.push(&encoded).expect("push ok");
+        dec.finish().expect("finish ok");
+        let expected = dec.into_splats();
+
+        // Re-pack as a non-seekable stream with meta.json last, deflating every entry
+        let mut archive = ZipArchive::new(Cursor::new(&encoded)).expect("zip ok");
+        let mut names: Vec<String> = archive.file_names().map(|n| format!("scene/{n}")).collect();
+        names.sort_by_key(|n| n.ends_with("meta.json"));
+        let mut writer = ZipWriter::new_stream(Vec::new());
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for name in names.iter() {
+            let mut data = Vec::new();
+            archive.by_name(&name["scene/".len()..]).unwrap().read_to_end(&mut data).unwrap();
+            writer.start_file(name.as_str(), options).unwrap();
+            writer.write_all(&data).unwrap();
+        }
+        let streamed = writer.finish().unwrap().into_inner();
+        assert_ne!(streamed[6] & 0x8, 0, "expected data descriptors");
+
+        let mut dec = SogsDecoder::new(GsplatArray::new());
+        for piece in streamed.chunks(997) {
+            dec.push(piece).expect("push ok");
+        }
+        dec.finish().expect("finish ok");
+        let out = dec.into_splats();
+
+        assert_eq!(out.len(), expected.len());
+        assert_eq!(out.max_sh_degree, 1);
+        for i in 0..out.len() {
+            let (a, b) = (&out.splats[i], &expected.splats[i]);
+            assert_eq!(a.center, b.center);
+            assert_eq!(a.rgb, b.rgb);
+            assert_eq!(a.opacity, b.opacity);
+            assert_eq!(a.ln_scales, b.ln_scales);
+            assert_eq!(a.quaternion, b.quaternion);
+            assert_eq!(out.sh1[i].to_array(), expected.sh1[i].to_array());
+        }
+    }
+
+    #[test]
+    fn sogs_loose_meta_json_with_resolver() {
+        use std::{collections::HashMap, io::{Cursor, Read}};
+        use zip::ZipArchive;
+
+        let mut arr = GsplatArray::new_capacity(2, 0);
+        arr.push_splat(make_splat([0.5, -1.5, 2.0], 0.8, [0.3, 0.6, 0.9], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]), None, None, None);
+        arr.push_splat(make_splat([-3.0, 0.25, 10.0], 0.2, [0.9, 0.1, 0.4], [0.5, 0.4, 0.3], [-0.7, 0.1, 0.2, 0.3]), None, None, None);
+        let encoded = SogsEncoder::new(arr).encode().expect("encode ok");
+
+        let mut archive = ZipArchive::new(Cursor::new(&encoded)).expect("zip ok");
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).unwrap();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            files.insert(entry.name().to_string(), data);
+        }
+        let meta = files.remove("meta.json").unwrap();
+
+        let resolver = move |name: &str| files.get(name).cloned().ok_or_else(|| anyhow::anyhow!("missing {name}"));
+        let mut dec = MultiDecoder::new_with_resolver(GsplatArray::new(), None, Some("scene/meta.json"), Some(Box::new(resolver)));
+        dec.push(&meta).expect("push ok");
+        dec.finish().expect("finish ok");
+        let out = dec.into_splats();
+        assert_eq!(out.len(), 2);
+        assert!(approx(out.splats[1].center[2], 10.0, 1e-2));
+
+        let mut dec = SogsDecoder::new(GsplatArray::new());
+        dec.push(&meta).expect("push ok");
+        assert!(dec.finish().is_err());
+        assert_eq!(SplatFileType::from_pathname("scene/meta.json?v=2"), Some(SplatFileType::SOGS));
+        assert_eq!(SplatFileType::from_pathname("meta.json"), Some(SplatFileType::SOGS));
+        assert_eq!(SplatFileType::from_pathname("scene/foometa.json"), None);
+    }
+
+    #[test]
+    fn npz_roundtrip_dtypes_and_mapping() {
+        let splats = [
+            make_splat([0.5, -1.5, 2.0], 0.8, [0.3, 0.6, 0.9], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]),
+            make_splat([-3.0, 0.25, 10.0], 0.2, [0.9, 0.1, 0.4], [0.5, 0.4, 0.3], [-0.7, 0.1, 0.2, 0.3]),
+        ];
+        let sh2_vals: [[f32; 15]; 2] = [
+            std::array::from_fn(|k| k as f32 * 0.05 - 0.3),
+            std::array::from_fn(|k| 0.4 - k as f32 * 0.04),
+        ];
+        let plain = NpzMapping {
+            means: "xyz".to_string(),
+            log_scales: false,
+            logit_opacities: false,
+            quats_wxyz: false,
+            ..Default::default()
+        };
+
+        for (dtype, mapping, pathname) in [
+            (NpyDtype::F32, NpzMapping::default(), "splats.npz"),
+            (NpyDtype::F16, plain, "splats.npz"),
+        ] {
+            let mut arr = GsplatArray::new_capacity(splats.len(), 2);
+            for (splat, vals) in splats.iter().zip(sh2_vals.iter()) {
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&vals[..9]);
+                let mut sh2 = GsplatSH2::default();
+                sh2.set_from_array(vals);
+                arr.push_splat(splat.clone(), Some(sh1), Some(sh2), None);
+            }
+
+            let encoded = NpzEncoder::new(arr).with_dtype(dtype).with_mapping(mapping.clone()).encode().expect("encode ok");
+            let out = if mapping.means == "means" {
+                let mut dec = MultiDecoder::new(GsplatArray::new(), None, Some(pathname));
+                dec.push(&encoded).expect("push ok");
+                dec.finish().expect("finish ok");
+                dec.into_splats()
+            } else {
+                let mut dec = NpzDecoder::new(GsplatArray::new()).with_mapping(mapping);
+                dec.push(&encoded).expect("push ok");
+                dec.finish().expect("finish ok");
+                dec.into_splats()
+            };
+
+            let eps = if dtype == NpyDtype::F16 { 0.01 } else { 1e-3 };
+            assert_eq!(out.max_sh_degree, 2);
+            assert_eq!(out.len(), splats.len());
+            let quat = |s: &Gsplat| Quat::from_array(s.quaternion.map(|v| v.to_f32())).normalize();
+            for (i, expected) in splats.iter().enumerate() {
+                let got = &out.splats[i];
+                for d in 0..3 {
+                    assert!(approx(got.center[d], expected.center[d], eps * 10.0), "center[{i}][{d}]");
+                    assert!(approx(got.rgb[d].to_f32(), expected.rgb[d].to_f32(), eps), "rgb[{i}][{d}]");
+                    assert!(approx(got.ln_scales[d].to_f32(), expected.ln_scales[d].to_f32(), eps), "ln_scales[{i}][{d}]");
+                }
+                assert!(approx(got.opacity.to_f32(), expected.opacity.to_f32(), eps));
+                assert!(quat(got).dot(quat(expected)).abs() > 0.999);
+                let got_sh2 = out.sh2[i].to_array();
+                for k in 0..15 {
+                    assert!(approx(got_sh2[k], sh2_vals[i][k], eps), "sh2[{i}][{k}] {} vs {}", got_sh2[k], sh2_vals[i][k]);
+                }
+            }
+        }
+
+        // Shapes whose size overflows are errors
+        for shape in [vec![usize::MAX, 2], vec![0, usize::MAX, 2]] {
+            let mut bytes = Vec::new();
+            write_npy(&mut bytes, &shape, &[], NpyDtype::F32).unwrap();
+            assert!(read_npy(&bytes).is_err(), "{shape:?}");
+        }
+
+        // shN must hold a whole number of SH degrees
+        let mut arr = GsplatArray::new_capacity(splats.len(), 0);
+        for splat in splats.iter() {
+            arr.push_splat(splat.clone(), None, None, None);
+        }
+        let encoded = NpzEncoder::new(arr).encode().expect("encode ok");
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&encoded)).unwrap();
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for i in 0..archive.len() {
+            writer.raw_copy_file(archive.by_index(i).unwrap()).unwrap();
+        }
+        writer.start_file("shN.npy", zip::write::SimpleFileOptions::default()).unwrap();
+        write_npy(&mut writer, &[2, 4, 3], &[0.0; 24], NpyDtype::F32).unwrap();
+        let encoded = writer.finish().unwrap().into_inner();
+        let mut dec = NpzDecoder::new(GsplatArray::new());
+        let err = dec.push(&encoded).and_then(|_| dec.finish()).unwrap_err();
+        assert!(err.to_string().contains("Unexpected shape"), "{err}");
+    }
+
+    #[test]
+    fn rad_reader_inline_and_external_chunks() {
+        let num_splats = 65536 + 100;
+        let mut arr = GsplatArray::new_capacity(num_splats, 0);
+        for i in 0..num_splats {
+            let t = i as f32;
+            arr.push_splat(make_splat([t, -t, 0.5 * t], 0.5, [0.5, 0.5, 0.5], [0.1, 0.1, 0.1], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        let mut encoder = RadEncoder::new(arr);
+
+        let mut inline = Vec::new();
+        encoder.encode(&mut inline).expect("encode ok");
+        let mut reader = RadReader::new(std::io::Cursor::new(inline)).expect("header ok");
+        assert_eq!(reader.chunk_count(), 2);
+        assert!(reader.chunk_range(1).unwrap().filename().is_none());
+        let chunk = reader.read_chunk(1, GsplatArray::new()).expect("chunk ok");
+        assert_eq!(chunk.len(), 100);
+        assert_eq!(chunk.splats[7].center.x, (65536 + 7) as f32);
+        assert!(reader.read_chunk(2, GsplatArray::new()).is_err());
+        let err = RadReader::new(std::io::Cursor::new(b"ply\nformat ascii 1.0\n".to_vec())).err().expect("not RAD");
+        assert!(err.to_string().contains("Invalid RAD magic"), "{err}");
+        let huge: Vec<u8> = crate::rad::RAD_MAGIC.to_le_bytes().into_iter().chain(u32::MAX.to_le_bytes()).collect();
+        let err = RadReader::new(std::io::Cursor::new(huge)).err().expect("truncated");
+        assert!(err.to_string().contains("past end of data"), "{err}");
+
+        let dir = std::env::temp_dir().join(format!("spark-rad-reader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut header = Vec::new();
+        let chunks = encoder.encode_with_chunks(&mut header, "scene-").expect("encode ok");
+        for (filename, bytes) in chunks.iter() {
+            std::fs::write(dir.join(filename), bytes).unwrap();
+        }
+        std::fs::write(dir.join("scene.rad"), &header).unwrap();
+
+        let mut reader = RadReader::open(dir.join("scene.rad")).expect("open ok");
+        assert_eq!(reader.chunk_range(0).unwrap().filename(), Some("scene-0.radc"));
+        let chunk = reader.read_chunk(1, GsplatArray::new()).expect("chunk ok");
+        assert_eq!(chunk.splats[99].center.y, -((65536 + 99) as f32));
+        let chunk = reader.read_chunk(0, GsplatArray::new()).expect("chunk ok");
+        assert_eq!(chunk.len(), 65536);
+
+        // Chunk filenames can't leave the directory, and lengths must fit the file
+        let replace = |from: &[u8], to: &[u8]| {
+            let index = header.windows(from.len()).position(|w| w == from).expect("filename in header");
+            let mut bytes = header.clone();
+            bytes[index..index + from.len()].copy_from_slice(to);
+            std::fs::write(dir.join("bad.rad"), bytes).unwrap();
+            RadReader::open(dir.join("bad.rad")).expect("open ok")
+        };
+        for filename in [b"../ne-0.radc", b"/tmp/e-0.rad", b"a/scene-0.rc"] {
+            let err = replace(b"scene-0.radc", filename).read_chunk_bytes(0).unwrap_err();
+            assert!(err.to_string().contains("invalid filename"), "{err}");
+        }
+        std::fs::write(dir.join("scene-1.radc"), &chunks[1].1[..64]).unwrap();
+        let err = reader.read_chunk_bytes(1).unwrap_err();
+        assert!(err.to_string().contains("past end of data"), "{err}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rad_stream_encoder_roundtrip() {
+        let num_splats = 2500;
+        let mut arr = GsplatArray::new_capacity(num_splats, 0);
+        for i in 0..num_splats {
+            let t = i as f32;
+            arr.push_splat(make_splat([t, -t, 0.5 * t], 0.5, [0.5, 0.5, 0.5], [0.1, 0.1, 0.1], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        // Binary tree in breadth-first order, so splat i sits at depth log2(i + 1)
+        arr.children = (0..num_splats).map(|i| (2 * i + 1..(2 * i + 3).min(num_splats)).collect()).collect();
+
+        let chunk_size = 1000;
+        let mut stream = RadEncoder::new(GsplatArray::new())
+            .into_stream(std::io::Cursor::new(Vec::new()), num_splats).expect("stream ok")
+            .with_chunk_size(chunk_size);
+        assert_eq!(stream.num_chunks(), 3);
+        assert!(stream.push_chunk(arr.clone_subset(0, 10)).is_err());
+        for chunk in 0..stream.num_chunks() {
+            let start = chunk * chunk_size;
+            stream.push_chunk(arr.clone_subset(start, (num_splats - start).min(chunk_size))).expect("push ok");
+        }
+        let bytes = stream.finish().expect("finish ok").into_inner();
+
+        let mut reader = RadReader::new(std::io::Cursor::new(bytes)).expect("header ok");
+        assert_eq!(reader.chunk_count(), 3);
+        let depth = |i: usize| (i + 1).ilog2();
+        for chunk in 0..3 {
+            let start = chunk * chunk_size;
+            let end = (start + chunk_size).min(num_splats);
+            let range = reader.chunk_range(chunk).unwrap();
+            assert_eq!(range.depth_range(), Some((depth(start), depth(end - 1))));
+            assert!(approx(range.bounds().unwrap().0[0], start as f32 - 0.3, 1e-3));
+
+            let decoded = reader.read_chunk(chunk, GsplatArray::new()).expect("chunk ok");
+            assert_eq!(decoded.len(), end - start);
+            assert_eq!(decoded.splats[7].center.y, -((start + 7) as f32));
+            assert_eq!(decoded.children[3].as_slice(), arr.children[start + 3].as_slice());
+        }
+
+        let dir = std::env::temp_dir().join(format!("spark-rad-stream-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let chunk_dir = dir.clone();
+        let mut stream = RadEncoder::new(GsplatArray::new())
+            .into_stream(std::fs::File::create(dir.join("scene.rad")).unwrap(), num_splats).expect("stream ok")
+            .with_chunk_size(chunk_size)
+            .with_chunk_files("scene-", move |filename, bytes| Ok(std::fs::write(chunk_dir.join(filename), bytes)?));
+        for chunk in 0..3 {
+            let start = chunk * chunk_size;
+            stream.push_chunk(arr.clone_subset(start, (num_splats - start).min(chunk_size))).expect("push ok");
+        }
+        stream.finish().expect("finish ok");
+
+        let mut reader = RadReader::open(dir.join("scene.rad")).expect("open ok");
+        assert_eq!(reader.chunk_range(2).unwrap().filename(), Some("scene-2.radc"));
+        let decoded = reader.read_chunk(2, GsplatArray::new()).expect("chunk ok");
+        assert_eq!(decoded.len(), 500);
+        assert_eq!(decoded.splats[499].center.x, 2499.0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rad_chunk_bounds_and_depth() {
+        let mut arr = GsplatArray::new_capacity(4, 0);
+        let centers = [[0.0, 0.0, 0.0], [-1.0, 2.0, 0.5], [3.0, -4.0, 1.0], [0.5, 0.5, -2.0]];
+        for (i, center) in centers.iter().enumerate() {
+            let scale = 0.1 * (i + 1) as f32;
+            arr.push_splat(make_splat(*center, 0.5, [0.5, 0.5, 0.5], [scale, scale * 0.5, scale], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        arr.children = vec![smallvec::smallvec![1, 2], smallvec::smallvec![3], Default::default(), Default::default()];
+
+        let mut bytes = Vec::new();
+        RadEncoder::new(arr).encode(&mut bytes).expect("encode ok");
+        let (meta, _) = crate::rad::decode_rad_header(&bytes).expect("header ok").expect("complete header");
+        let range = &meta.chunks()[0];
+
+        // Centers padded by three standard deviations
+        let (min, max) = range.bounds().expect("bounds");
+        for (actual, expected) in min.iter().chain(max.iter()).zip([-1.6, -4.45, -3.2, 3.9, 2.3, 1.9]) {
+            assert!(approx(*actual, expected, 1e-3), "{min:?} {max:?}");
+        }
+        let (min_size, max_size) = range.feature_size_range().expect("feature sizes");
+        assert!(approx(min_size, 0.2, 1e-3) && approx(max_size, 0.8, 1e-3), "{min_size} {max_size}");
+        assert_eq!(range.depth_range(), Some((0, 2)));
+    }
+
+    #[test]
+    fn rad_compression_and_filters_roundtrip() {
+        let num_splats = 1003;
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(num_splats, 1);
+            for i in 0..num_splats {
+                let t = i as f32 * 0.01;
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&std::array::from_fn::<f32, 9, _>(|k| ((i % 7) as f32 - 3.0) * 0.05 + k as f32 * 0.01));
+                arr.push_splat(make_splat([t, t.sin(), 0.5 * t], 0.5, [0.2, 0.4, (i % 5) as f32 * 0.2], [0.1, 0.2, 0.1], [0.0, 0.0, 0.0, 1.0]), Some(sh1), None, None);
+            }
+            arr
+        };
+
+        let encode = |compression, filter| {
+            let mut bytes = Vec::new();
+            RadEncoder::new(make_array()).with_compression(compression).with_filter(filter).encode(&mut bytes).expect("encode ok");
+            bytes
+        };
+        let decode = |bytes: &[u8]| {
+            let mut reader = RadReader::new(std::io::Cursor::new(bytes.to_vec())).expect("header ok");
+            reader.read_chunk(0, GsplatArray::new()).expect("chunk ok")
+        };
+
+        let baseline_bytes = encode(RadCompression::Gz, RadFilter::None);
+        let baseline = decode(&baseline_bytes);
+        let combos = [
+            (RadCompression::None, RadFilter::None),
+            (RadCompression::Lz4, RadFilter::None),
+            (RadCompression::Gz, RadFilter::Shuffle),
+            (RadCompression::Lz4, RadFilter::BitShuffle),
+            (RadCompression::None, RadFilter::BitShuffle),
+            (RadCompression::Auto, RadFilter::Auto),
+        ];
+        for (compression, filter) in combos {
+            let bytes = encode(compression, filter);
+            let out = decode(&bytes);
+            assert_eq!(out.len(), num_splats);
+            for i in 0..num_splats {
+                let (a, b) = (&out.splats[i], &baseline.splats[i]);
+                assert_eq!(a.center, b.center, "{compression:?}/{filter:?} center[{i}]");
+                assert_eq!(a.rgb, b.rgb, "{compression:?}/{filter:?} rgb[{i}]");
+                assert_eq!(out.sh1[i].to_array(), baseline.sh1[i].to_array(), "{compression:?}/{filter:?} sh1[{i}]");
+            }
+            if compression == RadCompression::Auto {
+                assert!(bytes.len() <= baseline_bytes.len(), "{} > {}", bytes.len(), baseline_bytes.len());
+            }
+        }
+
+        // An lz4 property that expands past its size fails while decoding
+        let mut bytes = Vec::new();
+        RadEncoder::new(make_array()).with_compression(RadCompression::Lz4).with_checksums(false).encode(&mut bytes).expect("encode ok");
+        let chunk = RadReader::new(std::io::Cursor::new(bytes.clone())).unwrap().read_chunk_bytes(0).expect("chunk ok");
+        let chunk_start = bytes.windows(chunk.len()).position(|w| w == chunk.as_slice()).unwrap();
+        let length = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as usize;
+        let meta: serde_json::Value = serde_json::from_slice(&chunk[8..8 + length]).unwrap();
+        let prop = meta["properties"].as_array().unwrap().iter().find(|prop| prop["compression"] == "lz4").expect("lz4 property");
+        let start = chunk_start + 8 + length.div_ceil(8) * 8 + 8 + prop["offset"].as_u64().unwrap() as usize;
+        let stored = &mut bytes[start..start + prop["bytes"].as_u64().unwrap() as usize];
+        // One literal, then a run-length match extended by every other byte
+        stored.fill(255);
+        stored[..4].copy_from_slice(&[0x1f, 0, 1, 0]);
+        *stored.last_mut().unwrap() = 0;
+        let err = RadReader::new(std::io::Cursor::new(bytes)).unwrap().read_chunk(0, GsplatArray::new()).err().expect("expands");
+        assert!(err.to_string().contains("expands past expected"), "{err}");
+    }
+
+    #[test]
+    fn rad_arith_smaller_than_gz() {
+        let num_splats = 8192;
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(num_splats, 1);
+            let mut seed = 12345u32;
+            let mut noise = move || {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+            };
+            for i in 0..num_splats {
+                let t = i as f32 / num_splats as f32;
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(&std::array::from_fn::<f32, 9, _>(|k| 0.2 * (t * 6.0 + k as f32).sin() + 0.05 * noise()));
+                let rgb = [0.5 + 0.3 * (t * 9.0).sin() + 0.05 * noise(), 0.4 + 0.05 * noise(), 0.6 - 0.3 * t];
+                let scale = 0.02 * (1.0 + t + 0.3 * noise());
+                let quat = Quat::from_rotation_y(t * 3.0 + 0.1 * noise());
+                arr.push_splat(make_splat([t * 10.0, noise(), t], 0.7 + 0.2 * noise(), rgb, [scale, scale * 0.5, scale], quat.to_array()), Some(sh1), None, None);
+            }
+            arr
+        };
+
+        let mut gz_bytes = Vec::new();
+        RadEncoder::new(make_array()).encode(&mut gz_bytes).expect("encode ok");
+        let mut arith_bytes = Vec::new();
+        RadEncoder::new(make_array()).with_compression(RadCompression::Arith).encode(&mut arith_bytes).expect("encode ok");
+        assert!(arith_bytes.len() < gz_bytes.len(), "arith {} vs gz {}", arith_bytes.len(), gz_bytes.len());
+
+        let decode = |bytes: Vec<u8>| RadReader::new(std::io::Cursor::new(bytes)).expect("header ok").read_chunk(0, GsplatArray::new()).expect("chunk ok");
+        let (gz, arith) = (decode(gz_bytes), decode(arith_bytes));
+        for i in 0..num_splats {
+            let (a, b) = (&arith.splats[i], &gz.splats[i]);
+            assert_eq!((a.center, a.opacity, a.rgb, a.ln_scales, a.quaternion), (b.center, b.opacity, b.rgb, b.ln_scales, b.quaternion), "splat[{i}]");
+            assert_eq!(arith.sh1[i].to_array(), gz.sh1[i].to_array(), "sh1[{i}]");
+        }
+
+        // RGB is quantized, so its arith header is its length then a stride of 1
+        let mut bytes = Vec::new();
+        RadEncoder::new(make_array()).with_compression(RadCompression::Arith).with_checksums(false).encode(&mut bytes).expect("encode ok");
+        let header: Vec<u8> = (3 * num_splats as u32).to_le_bytes().into_iter().chain(1u16.to_le_bytes()).collect();
+        let index = bytes.windows(header.len()).position(|window| window == header).expect("rgb header");
+        bytes[index..index + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = RadReader::new(std::io::Cursor::new(bytes)).unwrap().read_chunk(0, GsplatArray::new()).err().expect("bad length");
+        assert!(err.to_string().contains("Invalid arith length"), "{err}");
+    }
+
+    #[test]
+    fn rad_checksums_detect_corruption() {
+        assert_eq!(crate::rad::crc32(b"123456789"), 0xCBF43926);
+
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(300, 0);
+            for i in 0..300 {
+                let t = i as f32;
+                arr.push_splat(make_splat([t, 0.5 * t, -t], 0.5, [0.1, 0.5, 0.9], [0.1, 0.1, 0.1], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+            }
+            arr
+        };
+        let mut bytes = Vec::new();
+        RadEncoder::new(make_array()).encode(&mut bytes).expect("encode ok");
+        let verified = RadReader::new(std::io::Cursor::new(bytes.clone())).unwrap().verify().expect("verify ok");
+        assert_eq!(verified, 6);
+
+        let mut corrupt = bytes.clone();
+        let index = corrupt.len() - 20;
+        corrupt[index] ^= 0x5a;
+        let err = RadReader::new(std::io::Cursor::new(corrupt.clone())).unwrap().verify().unwrap_err();
+        assert!(err.to_string().contains("RAD chunk 0"), "{err}");
+        let mut decoder = RadDecoder::new(GsplatArray::new());
+        let err = decoder.push(&corrupt).and_then(|_| decoder.finish()).unwrap_err();
+        assert!(err.to_string().contains("RAD chunk 0"), "{err}");
+
+        let err = RadReader::new(std::io::Cursor::new(bytes[..bytes.len() - 16].to_vec())).unwrap().verify().unwrap_err();
+        assert!(err.to_string().contains("RAD chunk 0"), "{err}");
+
+        let mut bytes = Vec::new();
+        RadEncoder::new(make_array()).with_checksums(false).encode(&mut bytes).expect("encode ok");
+        assert_eq!(RadReader::new(std::io::Cursor::new(bytes)).unwrap().verify().expect("verify ok"), 0);
+    }
+
+    #[test]
+    fn rad_extras_roundtrip_and_merge() {
+        use crate::decoder::{SplatExtraEncoding, SplatExtraInfo, SplatExtraMerge};
+
+        let mut arr = GsplatArray::new_capacity(100, 0);
+        for i in 0..100 {
+            let t = i as f32;
+            arr.push_splat(make_splat([t, 0.0, 0.0], 0.5, [0.5, 0.5, 0.5], [0.1, 0.1, 0.1], [0.0, 0.0, 0.0, 1.0]), None, None, None);
+        }
+        let class = SplatExtraInfo::new("class", 1).with_encoding(SplatExtraEncoding::U8).with_merge(SplatExtraMerge::Vote);
+        arr.add_extra(class).values.iter_mut().enumerate().for_each(|(i, v)| *v = (i % 7) as f64);
+        let normal = SplatExtraInfo::new("normal", 3);
+        arr.add_extra(normal).values.iter_mut().enumerate().for_each(|(i, v)| *v = i as f64 * 0.25);
+        let time = SplatExtraInfo::new("time", 1).with_encoding(SplatExtraEncoding::U32).with_merge(SplatExtraMerge::Max);
+        arr.add_extra(time).values.iter_mut().enumerate().for_each(|(i, v)| *v = (1_700_000_123 + i * 3) as f64);
+
+        let mut bytes = Vec::new();
+        RadEncoder::new(arr).encode(&mut bytes).expect("encode ok");
+        let mut decoder = RadDecoder::new(GsplatArray::new());
+        decoder.push(&bytes).expect("push ok");
+        decoder.finish().expect("finish ok");
+        let mut out = decoder.into_splats();
+
+        assert_eq!(out.len(), 100);
+        assert_eq!(out.extras.len(), 3);
+        let class = out.extra("class").unwrap();
+        assert_eq!(class.info.encoding, SplatExtraEncoding::U8);
+        assert_eq!(class.info.merge, SplatExtraMerge::Vote);
+        assert!(class.values.iter().enumerate().all(|(i, &v)| v == (i % 7) as f64));
+        let normal = out.extra("normal").unwrap();
+        assert_eq!(normal.values.len(), 300);
+        assert!(normal.values.iter().enumerate().all(|(i, &v)| v == i as f64 * 0.25));
+        let time = out.extra("time").unwrap();
+        assert!(time.values.iter().enumerate().all(|(i, &v)| v == (1_700_000_123 + i * 3) as f64));
+
+        // Splats 0, 7, 14 share class 0 and outvote splat 1
+        let merged = out.new_merged(&[0, 7, 14, 1], 1.0);
+        assert_eq!(out.extra("class").unwrap().get(merged), &[0.0]);
+        assert_eq!(out.extra("time").unwrap().get(merged), &[1_700_000_165.0]);
+        let expected: Vec<f64> = (0..3).map(|d| [0, 7, 14, 1].iter().map(|&i| (i * 3 + d) as f64 * 0.25).sum::<f64>() / 4.0).collect();
+        let normal = out.extra("normal").unwrap().get(merged);
+        assert!(normal.iter().zip(expected.iter()).all(|(&a, &b)| (a - b).abs() < 1e-4), "{normal:?} vs {expected:?}");
+    }
+
+    #[test]
+    fn surfel_rad_ply_roundtrip_and_planar_lod() {
+        use crate::decoder::{SplatReceiver, SplatType};
+        use crate::ply::{PlyDecoder, PlyEncoder};
+        use crate::tsplat::{Tsplat, planar_normal};
+
+        let normal = Vec3A::new(0.2, 0.1, 1.0).normalize();
+        let (u, v) = normal.any_orthonormal_pair();
+        let make_array = || {
+            let mut arr = GsplatArray::new_capacity(256, 0);
+            arr.set_splat_type(SplatType::Surfel).unwrap();
+            for i in 0..256 {
+                let (x, y) = ((i % 16) as f32 * 0.1, (i / 16) as f32 * 0.1);
+                let twist = Quat::from_rotation_z(i as f32 * 0.3);
+                let quat = (Quat::from_rotation_arc(glam::Vec3::Z, normal.into()) * twist).to_array();
+                let center = (u * x + v * y).to_array();
+                arr.push_splat(make_splat(center, 0.7, [0.4, 0.5, 0.6], [0.04, 0.02, 0.0], quat), None, None, None);
+            }
+            arr.normals = (0..256).map(|_| normal.into()).collect();
+            arr.distortions = (0..256).map(|i| i as f32 / 256.0).collect();
+            arr
+        };
+
+        let mut bytes = Vec::new();
+        RadEncoder::new(make_array()).encode(&mut bytes).expect("encode ok");
+        let mut decoder = RadDecoder::new(GsplatArray::new());
+        decoder.push(&bytes).expect("push ok");
+        decoder.finish().expect("finish ok");
+        let out = decoder.into_splats();
+        assert_eq!(out.splat_type, SplatType::Surfel);
+        assert_eq!(out.len(), 256);
+        for i in 0..256 {
+            let scales = out.get(i).scales();
+            assert_eq!(scales.z, 0.0);
+            assert!(approx(scales.x, 0.04, 0.002) && approx(scales.y, 0.02, 0.001), "{scales:?}");
+            assert!(planar_normal(&out.get(i)).dot(normal).abs() > 0.99);
+            assert!(out.normals[i].dot(normal.into()) > 0.999);
+            assert!(approx(out.distortions[i], i as f32 / 256.0, 1e-3));
+        }
+
+        let mut ply = Vec::new();
+        PlyEncoder::new(make_array()).encode_to_writer(&mut ply).expect("ply encode ok");
+        let header = String::from_utf8_lossy(&ply[..400]);
+        assert!(header.contains("scale_1") && !header.contains("scale_2"));
+        let mut decoder = PlyDecoder::new(GsplatArray::new());
+        decoder.push(&ply).expect("push ok");
+        decoder.finish().expect("finish ok");
+        let out = decoder.into_splats();
+        assert_eq!(out.splat_type, SplatType::Surfel);
+        assert_eq!(out.get(5).scales().z, 0.0);
+
+        let mut arr = make_array();
+        crate::bhatt_lod::compute_lod_tree(&mut arr, 1.5, |_| {});
+        assert!(arr.len() > 256);
+        for i in 0..arr.len() {
+            let splat = arr.get(i);
+            assert_eq!(splat.scales().z, 0.0);
+            assert!(planar_normal(&splat).dot(normal).abs() > 0.98, "splat {i}: {splat:?}");
+        }
+    }
+
+    #[test]
+    fn gltf_roundtrip_glb_and_data_uri() {
+        let splats = [
+            make_splat([0.5, -1.5, 2.0], 0.8, [0.3, 0.6, 0.9], [0.05, 0.1, 0.2], [0.1, 0.2, 0.3, 0.9]),
+            make_splat([-3.0, 0.25, 10.0], 0.2, [0.9, 0.1, 0.4], [0.5, 0.4, 0.3], [-0.7, 0.1, 0.2, 0.3]),
+        ];
+        let sh1_vals: [[f32; 9]; 2] = [
+            [0.1, 0.4, 0.7, 0.2, 0.5, 0.8, 0.3, 0.6, 0.9],
+            [-0.3, 0.1, 0.4, 0.2, -0.2, 0.5, 0.0, 0.3, -0.1],
+        ];
+
+        for (binary, pathname) in [(true, None), (false, Some("scene.gltf"))] {
+            let mut arr = GsplatArray::new_capacity(splats.len(), 1);
+            for (splat, vals) in splats.iter().zip(sh1_vals.iter()) {
+                let mut sh1 = GsplatSH1::default();
+                sh1.set_from_array(vals);
+                arr.push_splat(splat.clone(), Some(sh1), None, None);
+            }
+
+            let encoded = GltfEncoder::new(arr).with_binary(binary).encode().expect("encode ok");
+            let mut dec = MultiDecoder::new(GsplatArray::new(), None, pathname);
+            dec.push(&encoded).expect("push ok");
+            dec.finish().expect("finish ok");
+            let out = dec.into_splats();
+
+            assert_eq!(out.max_sh_degree, 1);
+            assert_eq!(out.len(), splats.len());
+            for (i, expected) in splats.iter().enumerate() {
+                let got = &out.splats[i];
+                for d in 0..3 {
+                    assert!(approx(got.center[d], expected.center[d], 1e-6));
+                    assert!(approx(got.rgb[d].to_f32(), expected.rgb[d].to_f32(), 0.01), "rgb[{i}][{d}]");
+                    assert!(approx(got.ln_scales[d].to_f32(), expected.ln_scales[d].to_f32(), 0.01), "ln_scales[{i}][{d}]");
+                }
+                assert!(approx(got.opacity.to_f32(), expected.opacity.to_f32(), 0.01));
+                let got_sh1 = out.sh1[i].to_array();
+                for k in 0..9 {
+                    assert!(approx(got_sh1[k], sh1_vals[i][k], 0.01), "sh1[{i}][{k}] {} vs {}", got_sh1[k], sh1_vals[i][k]);
+                }
+            }
+        }
+
+        // A bufferView range that overflows is an error, not a panic
+        let mut arr = GsplatArray::new_capacity(1, 0);
+        arr.push_splat(splats[0].clone(), None, None, None);
+        let encoded = GltfEncoder::new(arr).with_binary(false).encode().expect("encode ok");
+        let mut json: serde_json::Value = serde_json::from_slice(&encoded).unwrap();
+        json["bufferViews"][0]["byteOffset"] = serde_json::json!(u64::MAX);
+        let mut dec = MultiDecoder::new(GsplatArray::new(), None, Some("scene.gltf"));
+        let err = dec.push(&serde_json::to_vec(&json).unwrap()).and_then(|_| dec.finish()).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{err}");
+    }
+
     #[test]
     fn spz_roundtrip_sh_degree1_interleaving() {
         // Prepare a single splat with distinct RGB values per SH1 coefficient
@@ -159,6 +1443,33 @@ mod tests {
         let got2 = out.sh2[0].to_array();
         for i in 0..15 { assert!(approx(got2[i], sh2_vals[i], 0.20), "sh2[{}] {} vs {}", i, got2[i], sh2_vals[i]); }
     }
+
+    #[test]
+    fn spz_v3_roundtrip_quaternions() {
+        let quats: [[f32; 4]; 4] = [
+            [0.1, 0.2, 0.3, 0.93],
+            [0.9, -0.3, 0.1, -0.2],
+            [-0.2, -0.95, 0.1, 0.05],
+            [0.01, 0.3, -0.8, -0.5],
+        ];
+        let mut arr = GsplatArray::new_capacity(quats.len(), 0);
+        for q in quats.iter() {
+            arr.push_splat(make_splat([0.0, 0.0, 0.0], 0.5, [0.5, 0.5, 0.5], [0.01, 1.0, 0.1], *q), None, None, None);
+        }
+
+        let encoded = SpzEncoder::new(arr).with_version(3).encode().expect("encode ok");
+        let mut dec = SpzDecoder::new(GsplatArray::new());
+        dec.push(&encoded).expect("push ok");
+        dec.finish().expect("finish ok");
+        let out = dec.into_splats();
+
+        assert_eq!(out.len(), quats.len());
+        for (i, q) in quats.iter().enumerate() {
+            let expected = Quat::from_array(*q).normalize();
+            let got = Quat::from_array(out.splats[i].quaternion.map(|v| v.to_f32())).normalize();
+            assert!(got.dot(expected).abs() > 0.9995, "quat[{i}] {:?} vs {:?}", got, expected);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -218,4 +1529,170 @@ mod ply_tests {
         let got2 = out.sh2[0].to_array();
         for i in 0..15 { assert!(approx(got2[i], sh2_vals[i], 3e-4), "sh2[{}] {} vs {}", i, got2[i], sh2_vals[i]); }
     }
+
+    #[test]
+    fn ply_compressed_roundtrip_sh_degree1() {
+        // 300 splats span two 256-splat chunks
+        let mut arr = GsplatArray::new_capacity(300, 1);
+        let mut sh1_vals = Vec::new();
+        for i in 0..300 {
+            let t = i as f32 / 300.0;
+            let splat = make_splat([t * 4.0 - 2.0, (t * 7.0).sin(), t * t], 0.2 + 0.6 * t, [t, 1.0 - t, 0.5], [0.01 + t, 0.05, 0.2 * (1.0 - t) + 0.01], [t - 0.5, 0.3, -0.2, 0.8]);
+            let vals: [f32; 9] = std::array::from_fn(|k| (k as f32 - 4.0) * 0.1 * (1.0 - t));
+            let mut sh1 = GsplatSH1::default(); sh1.set_from_array(&vals);
+            arr.push_splat(splat, Some(sh1), None, None);
+            sh1_vals.push(vals);
+        }
+        let expected = arr.splats.clone();
+
+        let encoded = PlyEncoder::new(arr).with_compressed(true).encode().expect("encode ok");
+        let mut dec = PlyDecoder::new(GsplatArray::new());
+        dec.push(&encoded).expect("push ok");
+        dec.finish().expect("finish ok");
+        let out = dec.into_splats();
+
+        assert_eq!(out.max_sh_degree, 1);
+        assert_eq!(out.len(), 300);
+        let quat = |s: &Gsplat| Quat::from_array(s.quaternion.map(|v| v.to_f32())).normalize();
+        for i in 0..300 {
+            let (got, exp) = (&out.splats[i], &expected[i]);
+            for d in 0..3 {
+                assert!(approx(got.center[d], exp.center[d], 5e-3), "center[{i}][{d}] {} vs {}", got.center[d], exp.center[d]);
+                assert!(approx(got.rgb[d].to_f32(), exp.rgb[d].to_f32(), 5e-3), "rgb[{i}][{d}]");
+                assert!(approx(got.ln_scales[d].to_f32(), exp.ln_scales[d].to_f32(), 1e-2), "ln_scales[{i}][{d}]");
+            }
+            assert!(approx(got.opacity.to_f32(), exp.opacity.to_f32(), 5e-3));
+            assert!(quat(got).dot(quat(exp)).abs() > 0.999, "quat[{i}]");
+            let got1 = out.sh1[i].to_array();
+            for k in 0..9 { assert!(approx(got1[k], sh1_vals[i][k], 0.02), "sh1[{i}][{k}] {} vs {}", got1[k], sh1_vals[i][k]); }
+        }
+    }
+
+    // Rewrites a binary_little_endian PLY into `ascii` or `binary_big_endian`
+    fn reencode_ply(bytes: &[u8], format: &str) -> Vec<u8> {
+        let header_end = bytes.windows(11).position(|w| w == b"end_header\n").unwrap() + 11;
+        let header = std::str::from_utf8(&bytes[..header_end]).unwrap();
+        let mut elements: Vec<(usize, Vec<String>)> = Vec::new();
+        for line in header.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[0] {
+                "element" => elements.push((fields[2].parse().unwrap(), Vec::new())),
+                "property" => elements.last_mut().unwrap().1.push(fields[1].to_string()),
+                _ => {}
+            }
+        }
+
+        let mut out = header.replace("binary_little_endian", format).into_bytes();
+        let mut offset = header_end;
+        for (count, types) in elements.iter() {
+            for _ in 0..*count {
+                let mut values = Vec::new();
+                for ty in types.iter() {
+                    let size = match ty.as_str() { "uchar" => 1, "float" | "uint" => 4, _ => unreachable!() };
+                    let raw = &bytes[offset..offset + size];
+                    offset += size;
+                    if format == "ascii" {
+                        values.push(match ty.as_str() {
+                            "uchar" => raw[0].to_string(),
+                            "uint" => u32::from_le_bytes(raw.try_into().unwrap()).to_string(),
+                            _ => f32::from_le_bytes(raw.try_into().unwrap()).to_string(),
+                        });
+                    } else {
+                        out.extend(raw.iter().rev());
+                    }
+                }
+                if format == "ascii" {
+                    out.extend_from_slice(values.join(" ").as_bytes());
+                    out.push(b'\n');
+                }
+            }
+        }
+        out
+    }
+
+    fn decode_in_pieces(bytes: &[u8]) -> GsplatArray {
+        let mut dec = PlyDecoder::new(GsplatArray::new());
+        for piece in bytes.chunks(13) {
+            dec.push(piece).expect("push ok");
+        }
+        dec.finish().expect("finish ok");
+        dec.into_splats()
+    }
+
+    #[test]
+    fn ply_ascii_and_big_endian_match_little_endian() {
+        for compressed in [false, true] {
+            let mut arr = GsplatArray::new_capacity(5, 2);
+            for i in 0..5 {
+                let t = i as f32 * 0.2;
+                let splat = make_splat([t, -t, 2.0 * t], 0.3 + t, [t, 0.5, 1.0 - t], [0.1 + t, 0.2, 0.3], [0.1, t, -0.2, 0.9]);
+                let mut sh1 = GsplatSH1::default(); let vals1: [f32; 9] = std::array::from_fn(|k| k as f32 * 0.05 - t); sh1.set_from_array(&vals1);
+                let mut sh2 = GsplatSH2::default(); let vals2: [f32; 15] = std::array::from_fn(|k| t - k as f32 * 0.03); sh2.set_from_array(&vals2);
+                arr.push_splat(splat, Some(sh1), Some(sh2), None);
+            }
+            let encoded = PlyEncoder::new(arr).with_compressed(compressed).encode().expect("encode ok");
+            let reference = decode_in_pieces(&encoded);
+
+            for format in ["ascii", "binary_big_endian"] {
+                let out = decode_in_pieces(&reencode_ply(&encoded, format));
+                assert_eq!(out.len(), reference.len());
+                assert_eq!(out.max_sh_degree, 2);
+                for i in 0..out.len() {
+                    let (a, b) = (&out.splats[i], &reference.splats[i]);
+                    for d in 0..3 {
+                        assert!(approx(a.center[d], b.center[d], 1e-5), "{format} compressed={compressed} center[{i}][{d}]");
+                        assert_eq!(a.ln_scales[d], b.ln_scales[d]);
+                        assert_eq!(a.rgb[d], b.rgb[d]);
+                    }
+                    assert_eq!(a.opacity, b.opacity);
+                    assert_eq!(a.quaternion, b.quaternion);
+                    assert_eq!(out.sh2[i].to_array(), reference.sh2[i].to_array());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ply_extras_are_opt_in() {
+        use crate::decoder::{MultiDecoder, SplatExtraEncoding, SplatExtraMerge, SplatFileType};
+        use crate::ply::PlyDecoder;
+
+        let mut text = String::from("ply\nformat ascii 1.0\nelement vertex 2\n");
+        for name in ["x", "y", "z", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0", "scale_1", "scale_2", "rot_0", "rot_1", "rot_2", "rot_3"] {
+            text.push_str(&format!("property float {name}\n"));
+        }
+        text.push_str("property uint instance\nproperty float confidence\nend_header\n");
+        text.push_str("0 0 0 0 0 0 0 -2 -2 -2 1 0 0 0 16777216 0.5\n1 0 0 0 0 0 0 -2 -2 -2 1 0 0 0 16777217 0.25\n");
+
+        let out = decode_in_pieces(text.as_bytes());
+        assert_eq!((out.len(), out.extras.len()), (2, 0));
+
+        let mut decoder = MultiDecoder::new(GsplatArray::new(), Some(SplatFileType::PLY), None).with_ply_extras(true);
+        decoder.push(text.as_bytes()).expect("push ok");
+        decoder.finish().expect("finish ok");
+        let out = decoder.into_splats();
+        assert_eq!(out.extras.len(), 2);
+        let instance = out.extra("instance").unwrap();
+        assert_eq!((instance.info.encoding, instance.info.merge), (SplatExtraEncoding::U32, SplatExtraMerge::Vote));
+        // Integers above 2^24 stay exact
+        assert_eq!(instance.values, vec![16777216.0, 16777217.0]);
+        assert_eq!(out.extra("confidence").unwrap().values, vec![0.5, 0.25]);
+
+        let mut decoder = PlyDecoder::new(GsplatArray::new()).with_extras(true);
+        decoder.push(text.as_bytes()).expect("push ok");
+        decoder.finish().expect("finish ok");
+        assert_eq!(decoder.into_splats().extras.len(), 2);
+    }
+
+    #[test]
+    fn ply_ascii_point_cloud() {
+        let text = "ply\r\nformat ascii 1.0\r\nelement vertex 2\r\nproperty float x\r\nproperty float y\r\nproperty float z\r\nproperty uchar red\r\nproperty uchar green\r\nproperty uchar blue\r\nend_header\r\n1.5 -2 3e-1 255 0 51\r\n0 0 -4.25 0 255 102";
+        let out = decode_in_pieces(text.as_bytes());
+        assert_eq!(out.len(), 2);
+        assert!(approx(out.splats[0].center.x, 1.5, 1e-6));
+        assert!(approx(out.splats[0].center.z, 0.3, 1e-6));
+        assert!(approx(out.splats[1].center.z, -4.25, 1e-6));
+        assert!(approx(out.splats[0].rgb[2].to_f32(), 0.2, 1e-3));
+        assert!(approx(out.splats[1].rgb[1].to_f32(), 1.0, 1e-3));
+    }
 }
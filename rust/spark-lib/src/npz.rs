@@ -0,0 +1,426 @@
+use std::io::{Cursor, Read, Seek, Write};
+
+use anyhow::{anyhow, Context};
+use half::f16;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::decoder::{ChunkReceiver, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+const SH_C0: f32 = 0.28209479177387814;
+const MAX_SPLAT_CHUNK: usize = 65536;
+const MIN_OPACITY: f32 = 1.0e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpyDtype {
+    F16,
+    F32,
+}
+
+impl NpyDtype {
+    fn descr(self) -> &'static str {
+        match self {
+            Self::F16 => "<f2",
+            Self::F32 => "<f4",
+        }
+    }
+}
+
+// Array names and activation conventions of an .npz splat archive.
+// Defaults follow gsplat/nerfstudio checkpoints.
+#[derive(Debug, Clone)]
+pub struct NpzMapping {
+    pub means: String,
+    pub scales: String,
+    pub quats: String,
+    pub opacities: String,
+    pub sh0: String,
+    pub shn: String,
+    // Scales stored as ln(scale)
+    pub log_scales: bool,
+    // Opacities stored as logit(opacity)
+    pub logit_opacities: bool,
+    // Quaternions stored as (w, x, y, z) instead of (x, y, z, w)
+    pub quats_wxyz: bool,
+}
+
+impl Default for NpzMapping {
+    fn default() -> Self {
+        Self {
+            means: "means".to_string(),
+            scales: "scales".to_string(),
+            quats: "quats".to_string(),
+            opacities: "opacities".to_string(),
+            sh0: "sh0".to_string(),
+            shn: "shN".to_string(),
+            log_scales: true,
+            logit_opacities: true,
+            quats_wxyz: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NpyArray {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+impl NpyArray {
+    // Number of values per leading index
+    fn row_len(&self) -> usize {
+        self.shape.iter().skip(1).product()
+    }
+}
+
+pub fn read_npy(bytes: &[u8]) -> anyhow::Result<NpyArray> {
+    if bytes.len() < 10 || &bytes[0..6] != NPY_MAGIC {
+        return Err(anyhow!("Invalid .npy magic"));
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 => {
+            if bytes.len() < 12 {
+                return Err(anyhow!("Truncated .npy header"));
+            }
+            (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12)
+        },
+        version => return Err(anyhow!("Unsupported .npy version: {}", version)),
+    };
+    let data_start = header_len.checked_add(header_start).ok_or_else(|| anyhow!("Invalid .npy header length"))?;
+    let header = bytes.get(header_start..data_start).ok_or_else(|| anyhow!("Truncated .npy header"))?;
+    let header = std::str::from_utf8(header).context("Invalid .npy header")?;
+
+    let descr = header_value(header, "descr")
+        .and_then(|v| {
+            let quote = v.chars().next()?;
+            v.strip_prefix(quote)?.split_once(quote).map(|(descr, _)| descr)
+        })
+        .ok_or_else(|| anyhow!("Missing descr in .npy header"))?;
+    if header_value(header, "fortran_order").is_some_and(|v| v.starts_with("True")) {
+        return Err(anyhow!("Fortran-order .npy arrays are not supported"));
+    }
+    let shape = header_value(header, "shape")
+        .and_then(|v| v.strip_prefix('('))
+        .and_then(|v| v.split_once(')'))
+        .map(|(v, _)| v)
+        .ok_or_else(|| anyhow!("Missing shape in .npy header"))?;
+    let shape = shape.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .context("Invalid shape in .npy header")?;
+
+    // Rows are checked too, since a leading 0 hides their overflow
+    let row_len = shape.iter().skip(1).try_fold(1usize, |a, &b| a.checked_mul(b));
+    let count = row_len.and_then(|row_len| row_len.checked_mul(shape.first().copied().unwrap_or(1)))
+        .ok_or_else(|| anyhow!("Invalid shape in .npy header"))?;
+    let (big_endian, kind) = match descr.as_bytes().first() {
+        Some(b'>') => (true, &descr[1..]),
+        Some(b'<' | b'|' | b'=') => (false, &descr[1..]),
+        _ => (false, descr),
+    };
+    let size = match kind {
+        "f2" => 2,
+        "f4" => 4,
+        "f8" => 8,
+        "u1" | "i1" | "b1" => 1,
+        _ => return Err(anyhow!("Unsupported .npy dtype: {}", descr)),
+    };
+    let data_end = count.checked_mul(size).and_then(|len| len.checked_add(data_start))
+        .ok_or_else(|| anyhow!("Invalid shape in .npy header"))?;
+    let body = bytes.get(data_start..data_end)
+        .ok_or_else(|| anyhow!("Truncated .npy data"))?;
+
+    let data = body.chunks_exact(size).map(|b| {
+        let mut v = [0u8; 8];
+        v[..size].copy_from_slice(b);
+        if big_endian {
+            v[..size].reverse();
+        }
+        match kind {
+            "f2" => f16::from_le_bytes([v[0], v[1]]).to_f32(),
+            "f4" => f32::from_le_bytes([v[0], v[1], v[2], v[3]]),
+            "f8" => f64::from_le_bytes(v) as f32,
+            "i1" => v[0] as i8 as f32,
+            _ => v[0] as f32,
+        }
+    }).collect();
+    Ok(NpyArray { shape, data })
+}
+
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key)).or_else(|| header.find(&format!("\"{}\"", key)))?;
+    let rest = &header[start + key.len() + 2..];
+    Some(rest.trim_start().strip_prefix(':')?.trim_start())
+}
+
+pub fn write_npy<W: Write>(writer: &mut W, shape: &[usize], data: &[f32], dtype: NpyDtype) -> anyhow::Result<()> {
+    let shape_str = match shape {
+        [n] => format!("({},)", n),
+        _ => format!("({})", shape.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", dtype.descr(), shape_str);
+    // Pad so the data starts on a 64-byte boundary
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    let bytes: Vec<u8> = match dtype {
+        NpyDtype::F16 => data.iter().flat_map(|v| f16::from_f32(*v).to_le_bytes()).collect(),
+        NpyDtype::F32 => data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    };
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+pub struct NpzDecoder<T: SplatReceiver> {
+    splats: T,
+    mapping: NpzMapping,
+    buffer: Vec<u8>,
+}
+
+impl<T: SplatReceiver> NpzDecoder<T> {
+    pub fn new(splats: T) -> Self {
+        Self {
+            splats,
+            mapping: NpzMapping::default(),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn with_mapping(mut self, mapping: NpzMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn into_splats(self) -> T {
+        self.splats
+    }
+}
+
+impl<T: SplatReceiver> ChunkReceiver for NpzDecoder<T> {
+    fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        let mut zip = ZipArchive::new(Cursor::new(&self.buffer)).context("Not a .npz file")?;
+        let mut read_array = |name: &str, required: bool| -> anyhow::Result<Option<NpyArray>> {
+            let mut entry = match zip.by_name(&format!("{}.npy", name)) {
+                Ok(entry) => entry,
+                Err(_) if !required => return Ok(None),
+                Err(_) => return Err(anyhow!("Missing array {} in .npz", name)),
+            };
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            read_npy(&bytes).with_context(|| format!("Failed to read {}.npy", name)).map(Some)
+        };
+
+        let mapping = &self.mapping;
+        let means = read_array(&mapping.means, true)?.unwrap();
+        let scales = read_array(&mapping.scales, true)?.unwrap();
+        let quats = read_array(&mapping.quats, true)?.unwrap();
+        let opacities = read_array(&mapping.opacities, true)?.unwrap();
+        let sh0 = read_array(&mapping.sh0, true)?.unwrap();
+        let shn = read_array(&mapping.shn, false)?;
+
+        let num_splats = means.shape.first().copied().unwrap_or(0);
+        for (name, array, row_len) in [
+            (&mapping.means, &means, 3),
+            (&mapping.scales, &scales, 3),
+            (&mapping.quats, &quats, 4),
+            (&mapping.opacities, &opacities, 1),
+            (&mapping.sh0, &sh0, 3),
+        ] {
+            if array.shape.first() != Some(&num_splats) || array.row_len() != row_len {
+                return Err(anyhow!("Unexpected shape {:?} for {}", array.shape, name));
+            }
+        }
+
+        // shN is (N, K, 3) with K = 3, 8 or 15 coefficients
+        let shn_coefs = match shn.as_ref() {
+            Some(shn) if shn.shape.first() == Some(&num_splats) && [9, 24, 45].contains(&shn.row_len()) => shn.row_len() / 3,
+            Some(shn) => return Err(anyhow!("Unexpected shape {:?} for {}", shn.shape, mapping.shn)),
+            None => 0,
+        };
+        let max_sh_degree = match shn_coefs {
+            15 => 3,
+            8 => 2,
+            3 => 1,
+            _ => 0,
+        };
+        self.splats.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
+
+        let opacity: Vec<f32> = opacities.data.iter()
+            .map(|&v| if mapping.logit_opacities { 1.0 / (1.0 + (-v).exp()) } else { v })
+            .collect();
+        let scale: Vec<f32> = scales.data.iter()
+            .map(|&v| if mapping.log_scales { v.exp() } else { v })
+            .collect();
+        let rgb: Vec<f32> = sh0.data.iter().map(|&v| 0.5 + SH_C0 * v).collect();
+        let quat: Vec<f32> = quats.data.chunks_exact(4).flat_map(|q| {
+            let q = if mapping.quats_wxyz { [q[1], q[2], q[3], q[0]] } else { [q[0], q[1], q[2], q[3]] };
+            let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if norm > 0.0 { q.map(|v| v / norm) } else { [0.0, 0.0, 0.0, 1.0] }
+        }).collect();
+
+        let shn_data = shn.map(|shn| shn.data).unwrap_or_default();
+        let sh_band = |start: usize, coefs: usize| -> Vec<f32> {
+            if start + coefs > shn_coefs {
+                return Vec::new();
+            }
+            (0..num_splats).flat_map(|i| {
+                let o = (i * shn_coefs + start) * 3;
+                shn_data[o..o + coefs * 3].iter().copied()
+            }).collect()
+        };
+        let sh1 = sh_band(0, 3);
+        let sh2 = sh_band(3, 5);
+        let sh3 = sh_band(8, 7);
+
+        let mut base = 0;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            let [i3, i4] = [base * 3, base * 4];
+            self.splats.set_batch(base, count, &SplatProps {
+                center: &means.data[i3..i3 + count * 3],
+                opacity: &opacity[base..base + count],
+                rgb: &rgb[i3..i3 + count * 3],
+                scale: &scale[i3..i3 + count * 3],
+                quat: &quat[i4..i4 + count * 4],
+                sh1: if max_sh_degree >= 1 { &sh1[base * 9..(base + count) * 9] } else { &[] },
+                sh2: if max_sh_degree >= 2 { &sh2[base * 15..(base + count) * 15] } else { &[] },
+                sh3: if max_sh_degree >= 3 { &sh3[base * 21..(base + count) * 21] } else { &[] },
+                ..Default::default()
+            });
+            base += count;
+        }
+        self.splats.finish()
+    }
+}
+
+pub struct NpzEncoder<T: SplatGetter> {
+    getter: T,
+    mapping: NpzMapping,
+    dtype: NpyDtype,
+    max_sh_out: Option<u8>,
+}
+
+impl<T: SplatGetter> NpzEncoder<T> {
+    pub fn new(getter: T) -> Self {
+        Self {
+            getter,
+            mapping: NpzMapping::default(),
+            dtype: NpyDtype::F32,
+            max_sh_out: None,
+        }
+    }
+
+    pub fn with_mapping(mut self, mapping: NpzMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn with_dtype(mut self, dtype: NpyDtype) -> Self {
+        self.dtype = dtype;
+        self
+    }
+
+    pub fn with_max_sh(mut self, max_sh: u8) -> Self {
+        self.max_sh_out = Some(max_sh.min(3));
+        self
+    }
+
+    pub fn encode_to_writer<W: Write + Seek>(mut self, writer: &mut W) -> anyhow::Result<()> {
+        let num_splats = self.getter.num_splats();
+        let sh_src = self.getter.max_sh_degree() as u8;
+        let sh_degree = self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) as usize;
+        let shn_coefs = [0, 3, 8, 15][sh_degree];
+
+        let mut center = vec![0.0f32; num_splats * 3];
+        let mut opacity = vec![0.0f32; num_splats];
+        let mut rgb = vec![0.0f32; num_splats * 3];
+        let mut scale = vec![0.0f32; num_splats * 3];
+        let mut quat = vec![0.0f32; num_splats * 4];
+        let mut shn = vec![0.0f32; num_splats * shn_coefs * 3];
+        let mut sh1 = vec![0.0f32; MAX_SPLAT_CHUNK * 9];
+        let mut sh2 = vec![0.0f32; MAX_SPLAT_CHUNK * 15];
+        let mut sh3 = vec![0.0f32; MAX_SPLAT_CHUNK * 21];
+
+        let mut base = 0;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            let [i3, i4] = [base * 3, base * 4];
+            self.getter.get_center(base, count, &mut center[i3..i3 + count * 3]);
+            self.getter.get_opacity(base, count, &mut opacity[base..base + count]);
+            self.getter.get_rgb(base, count, &mut rgb[i3..i3 + count * 3]);
+            self.getter.get_scale(base, count, &mut scale[i3..i3 + count * 3]);
+            self.getter.get_quat(base, count, &mut quat[i4..i4 + count * 4]);
+            let bands: [(&mut Vec<f32>, usize, usize); 3] = [(&mut sh1, 0, 3), (&mut sh2, 3, 5), (&mut sh3, 8, 7)];
+            for (degree, (band, start, coefs)) in bands.into_iter().enumerate() {
+                if degree >= sh_degree {
+                    break;
+                }
+                let band = &mut band[..count * coefs * 3];
+                match degree {
+                    0 => self.getter.get_sh1(base, count, band),
+                    1 => self.getter.get_sh2(base, count, band),
+                    _ => self.getter.get_sh3(base, count, band),
+                }
+                for i in 0..count {
+                    let o = ((base + i) * shn_coefs + start) * 3;
+                    shn[o..o + coefs * 3].copy_from_slice(&band[i * coefs * 3..(i + 1) * coefs * 3]);
+                }
+            }
+            base += count;
+        }
+
+        let mapping = &self.mapping;
+        if mapping.log_scales {
+            scale.iter_mut().for_each(|v| *v = v.max(f32::MIN_POSITIVE).ln());
+        }
+        if mapping.logit_opacities {
+            opacity.iter_mut().for_each(|v| {
+                let p = v.clamp(MIN_OPACITY, 1.0 - MIN_OPACITY);
+                *v = (p / (1.0 - p)).ln();
+            });
+        }
+        if mapping.quats_wxyz {
+            quat.chunks_exact_mut(4).for_each(|q| q.rotate_right(1));
+        }
+        let sh0: Vec<f32> = rgb.iter().map(|v| (v - 0.5) / SH_C0).collect();
+
+        let mut zip = ZipWriter::new(writer);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut arrays: Vec<(&str, Vec<usize>, &[f32])> = vec![
+            (&mapping.means, vec![num_splats, 3], &center),
+            (&mapping.scales, vec![num_splats, 3], &scale),
+            (&mapping.quats, vec![num_splats, 4], &quat),
+            (&mapping.opacities, vec![num_splats], &opacity),
+            (&mapping.sh0, vec![num_splats, 1, 3], &sh0),
+        ];
+        if shn_coefs > 0 {
+            arrays.push((&mapping.shn, vec![num_splats, shn_coefs, 3], &shn));
+        }
+        for (name, shape, data) in arrays {
+            zip.start_file(format!("{}.npy", name), options)?;
+            write_npy(&mut zip, &shape, data, self.dtype)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    pub fn encode(self) -> anyhow::Result<Vec<u8>> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.encode_to_writer(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+}
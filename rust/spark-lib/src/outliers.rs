@@ -0,0 +1,162 @@
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::bvh::SplatBvh;
+use crate::tsplat::{Tsplat, TsplatArray};
+
+// Splat counts flagged by each test. A splat can fail several, so the
+// total flagged may be less than their sum.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct OutlierReport {
+    pub statistical: usize,
+    pub radius: usize,
+    pub size: usize,
+    pub flagged: usize,
+}
+
+// Finds floaters with the point-cloud style filters, applied to splat
+// centers and restricted to splats below max_opacity:
+// - statistical: mean distance to the k nearest neighbours more than
+//   std_ratio standard deviations above the scene-wide mean
+// - radius: fewer than min_neighbors other centers within radius
+// - size: max scale more than size_ratio times the median among the k
+//   nearest neighbours
+// Each test is off unless configured.
+#[derive(Clone, Debug, Serialize)]
+pub struct OutlierFilter {
+    pub k: usize,
+    pub std_ratio: Option<f32>,
+    pub radius: Option<(f32, usize)>,
+    pub size_ratio: Option<f32>,
+    pub max_opacity: f32,
+}
+
+impl Default for OutlierFilter {
+    fn default() -> Self {
+        Self {
+            k: 8,
+            std_ratio: None,
+            radius: None,
+            size_ratio: None,
+            max_opacity: f32::INFINITY,
+        }
+    }
+}
+
+impl OutlierFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_k(mut self, k: usize) -> Self {
+        self.k = k.max(1);
+        self
+    }
+
+    pub fn with_std_ratio(mut self, std_ratio: f32) -> Self {
+        self.std_ratio = Some(std_ratio);
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f32, min_neighbors: usize) -> Self {
+        self.radius = Some((radius, min_neighbors));
+        self
+    }
+
+    pub fn with_size_ratio(mut self, size_ratio: f32) -> Self {
+        self.size_ratio = Some(size_ratio);
+        self
+    }
+
+    pub fn with_max_opacity(mut self, max_opacity: f32) -> Self {
+        self.max_opacity = max_opacity;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.std_ratio.is_some() || self.radius.is_some() || self.size_ratio.is_some()
+    }
+
+    // Flags outliers without changing the splats
+    pub fn find<T: TsplatArray>(&self, splats: &T) -> (Vec<bool>, OutlierReport) {
+        let mut report = OutlierReport::default();
+        let mut flags = vec![false; splats.len()];
+        if !self.is_enabled() || splats.len() < 2 {
+            return (flags, report);
+        }
+
+        let bvh = SplatBvh::new(splats);
+        let candidate = |index: usize| splats.get(index).opacity() < self.max_opacity;
+
+        if self.std_ratio.is_some() || self.size_ratio.is_some() {
+            let mut mean_distances = Vec::with_capacity(splats.len());
+            let mut size_outliers = Vec::new();
+            for index in 0..splats.len() {
+                let splat = splats.get(index);
+                // The splat itself comes back first at distance 0
+                let neighbors = bvh.nearest_k(splat.center(), self.k + 1);
+                let neighbors: Vec<(usize, f32)> = neighbors.into_iter().filter(|&(other, _)| other != index).take(self.k).collect();
+                mean_distances.push(neighbors.iter().map(|&(_, distance)| distance).sum::<f32>() / neighbors.len().max(1) as f32);
+
+                if let Some(size_ratio) = self.size_ratio {
+                    let mut sizes: Vec<f32> = neighbors.iter().map(|&(other, _)| splats.get(other).max_scale()).collect();
+                    if !sizes.is_empty() {
+                        let mid = sizes.len() / 2;
+                        let (_, median, _) = sizes.select_nth_unstable_by(mid, f32::total_cmp);
+                        if splat.max_scale() > size_ratio * *median && candidate(index) {
+                            size_outliers.push(index);
+                        }
+                    }
+                }
+            }
+
+            if let Some(std_ratio) = self.std_ratio {
+                let count = mean_distances.len() as f64;
+                let mean = mean_distances.iter().map(|&d| d as f64).sum::<f64>() / count;
+                let variance = mean_distances.iter().map(|&d| (d as f64 - mean).powi(2)).sum::<f64>() / count;
+                let threshold = (mean + std_ratio as f64 * variance.sqrt()) as f32;
+                for (index, &distance) in mean_distances.iter().enumerate() {
+                    if distance > threshold && candidate(index) {
+                        flags[index] = true;
+                        report.statistical += 1;
+                    }
+                }
+            }
+            for index in size_outliers {
+                flags[index] = true;
+                report.size += 1;
+            }
+        }
+
+        if let Some((radius, min_neighbors)) = self.radius {
+            for (index, flag) in flags.iter_mut().enumerate() {
+                let center = splats.get(index).center();
+                // Sphere query is by AABB, so check centers again
+                let neighbors = bvh.query_sphere(center, radius).into_iter()
+                    .filter(|&other| other != index && splats.get(other).center().distance(center) <= radius)
+                    .count();
+                if neighbors < min_neighbors && candidate(index) {
+                    *flag = true;
+                    report.radius += 1;
+                }
+            }
+        }
+
+        report.flagged = flags.iter().filter(|&&flag| flag).count();
+        (flags, report)
+    }
+
+    // Removes flagged splats. Removal would orphan the children of an LoD
+    // tree, so arrays with one are rejected and this runs before building it.
+    pub fn remove<T: TsplatArray>(&self, splats: &mut T) -> anyhow::Result<OutlierReport> {
+        if splats.has_children() {
+            return Err(anyhow!("Cannot remove outliers from splats with an LoD tree"));
+        }
+        let (flags, report) = self.find(splats);
+        if report.flagged > 0 {
+            let mut flags = flags.into_iter();
+            splats.retain(|_| !flags.next().unwrap());
+        }
+        Ok(report)
+    }
+}
@@ -4,19 +4,28 @@ use std::f32::consts::SQRT_2;
 
 use anyhow::anyhow;
 
-use crate::decoder::{ChunkReceiver, SplatGetter, SplatInit, SplatProps, SplatReceiver};
+use crate::decoder::{ChunkReceiver, SplatExtraEncoding, SplatExtraInfo, SplatExtraMerge, SplatFileType, SplatGetter, SplatInit, SplatProps, SplatReceiver, SplatType};
+use crate::probe::{need_more, union_bounds, SplatFileInfo};
 
 pub const PLY_MAGIC: u32 = 0x00796c70; // "ply"
 const MAX_SPLAT_CHUNK: usize = 65536;
+const MAX_HEADER_BYTES: usize = 65536;
 const SH_C0: f32 = 0.28209479177387814;
 const SUPER_CHUNK_SIZE: usize = 256;
 const POINT_CLOUD_PROPERTIES: [&str; 6] = ["x", "y", "z", "red", "green", "blue"];
 const DEFAULT_POINT_SCALE: f32 = 0.001;
+const SUPER_CHUNK_PROPERTIES: [&str; 18] = [
+    "min_x", "min_y", "min_z", "max_x", "max_y", "max_z",
+    "min_scale_x", "min_scale_y", "min_scale_z", "max_scale_x", "max_scale_y", "max_scale_z",
+    "min_r", "min_g", "min_b", "max_r", "max_g", "max_b",
+];
 
 pub struct PlyDecoder<T: SplatReceiver> {
     splats: T,
     buffer: Vec<u8>,
     state: Option<PlyState>,
+    body: Option<PlyBodyConverter>,
+    extras: bool,
 }
 
 impl<T: SplatReceiver> PlyDecoder<T> {
@@ -25,17 +34,30 @@ impl<T: SplatReceiver> PlyDecoder<T> {
             splats,
             buffer: Vec::new(),
             state: None,
+            body: None,
+            extras: false,
         }
     }
 
+    // Pass non-standard scalar vertex properties through as extras (default
+    // false). Exporters often add many that would otherwise end up in every
+    // RAD file.
+    pub fn with_extras(mut self, extras: bool) -> Self {
+        self.extras = extras;
+        self
+    }
+
     pub fn into_splats(self) -> T {
         self.splats
     }
 
-    fn poll(&mut self) -> anyhow::Result<()> {
+    fn poll(&mut self, finished: bool) -> anyhow::Result<()> {
         if self.state.is_none() {
             self.poll_header()?;
         }
+        if let Some(body) = self.body.as_mut() {
+            body.convert(&mut self.buffer, finished)?;
+        }
         if self.state.is_some() {
             self.poll_data()?;
         }
@@ -51,10 +73,8 @@ impl<T: SplatReceiver> PlyDecoder<T> {
             return Err(anyhow!("Invalid PLY file"));
         }
 
-        const TERMINATOR: &[u8] = b"end_header\n";
-        let header_end = self.buffer.windows(TERMINATOR.len()).position(|window| window == TERMINATOR);
-        let Some(header_end) = header_end else {
-            if self.buffer.len() >= 65536 {
+        let Some((header_end, terminator_len)) = find_header_end(&self.buffer) else {
+            if self.buffer.len() >= MAX_HEADER_BYTES {
                 return Err(anyhow!("PLY header too large"));
             }
             return Ok(());
@@ -62,6 +82,10 @@ impl<T: SplatReceiver> PlyDecoder<T> {
 
         let header = std::str::from_utf8(&self.buffer[..header_end])?;
         let parsed = parse_header(header)?;
+        let body = match parsed.format {
+            PlyFormat::BinaryLittleEndian => None,
+            format => Some(PlyBodyConverter::new(format, &parsed.elements)),
+        };
 
         let state = if parsed.is_supersplat {
             let state = SuperSplatState::new(parsed)?;
@@ -80,16 +104,30 @@ impl<T: SplatReceiver> PlyDecoder<T> {
             })?;
             PlyState::PointCloud(state)
         } else {
-            let state = PlyDecoderState::new(parsed.num_splats, parsed.vertex.record_size, parsed.vertex.properties.clone())?;
+            let mut state = PlyDecoderState::new(parsed.num_splats, parsed.vertex.record_size, parsed.vertex.properties.clone())?;
+            if !self.extras {
+                state.extras.clear();
+            }
             self.splats.init_splats(&SplatInit {
                 num_splats: parsed.num_splats,
                 max_sh_degree: state.max_sh_degree,
                 lod_tree: false,
             })?;
+            if state.scale_2.is_none() {
+                self.splats.set_splat_type(SplatType::Surfel)?;
+            }
+            let extras: Vec<SplatExtraInfo> = state.extras.iter().map(|(info, _)| info.clone()).collect();
+            if !extras.is_empty() {
+                self.splats.init_extras(&extras)?;
+            }
             PlyState::Standard(state)
         };
 
-        self.buffer.drain(..header_end + TERMINATOR.len());
+        self.buffer.drain(..header_end + terminator_len);
+        if let Some(mut body) = body {
+            body.pending = std::mem::take(&mut self.buffer);
+            self.body = Some(body);
+        }
         self.state = Some(state);
         Ok(())
     }
@@ -180,9 +218,10 @@ impl<T: SplatReceiver> PlyDecoder<T> {
                 for d in 0..3 {
                     state.out_rgb[i3 + d] = 0.5 + state.f_dc[d].get_f32(&self.buffer, base) * SH_C0;
                 }
-                for d in 0..3 {
+                for d in 0..2 {
                     state.out_scale[i3 + d] = state.scale[d].get_f32(&self.buffer, base).exp();
                 }
+                state.out_scale[i3 + 2] = state.scale_2.map_or(0.0, |scale| scale.get_f32(&self.buffer, base).exp());
                 let quat: [f32; 4] = array::from_fn(|d| state.rot[d].get_f32(&self.buffer, base));
                 let quat_magnitude = quat.map(|x| x.powi(2)).iter().sum::<f32>().sqrt();
                 for d in 0..4 {
@@ -221,6 +260,13 @@ impl<T: SplatReceiver> PlyDecoder<T> {
                 ..Default::default()
             });
 
+            for (info, property) in state.extras.iter() {
+                for i in 0..count {
+                    state.out_extra[i] = property.get_raw_f64(&self.buffer, offset + i * state.record_size);
+                }
+                self.splats.set_extra(state.next_splat, count, &info.name, &state.out_extra[..count]);
+            }
+
             state.next_splat += count;
             offset += count * state.record_size;
         }
@@ -304,13 +350,16 @@ impl<T: SplatReceiver> PlyDecoder<T> {
 
 impl<T: SplatReceiver> ChunkReceiver for PlyDecoder<T> {
     fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
-        self.buffer.extend_from_slice(bytes);
-        self.poll()?;
+        match self.body.as_mut() {
+            Some(body) => body.pending.extend_from_slice(bytes),
+            None => self.buffer.extend_from_slice(bytes),
+        }
+        self.poll(false)?;
         Ok(())
     }
 
     fn finish(&mut self) -> anyhow::Result<()> {
-        self.poll()?;
+        self.poll(true)?;
 
         let Some(state) = self.state.as_ref() else {
             return Err(anyhow!("Invalid PLY file"));
@@ -363,6 +412,7 @@ struct PlyElementDesc {
     count: usize,
     record_size: usize,
     properties: HashMap<String, PlyProperty>,
+    property_types: Vec<PlyPropertyType>,
 }
 
 #[derive(Default)]
@@ -393,12 +443,21 @@ impl PlyElementBuilder {
             name: self.name,
             count: self.count,
             record_size: self.record_size,
+            property_types: self.properties.iter().map(|(_, prop)| prop.ty).collect(),
             properties: self.properties.into_iter().collect(),
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlyFormat {
+    BinaryLittleEndian,
+    BinaryBigEndian,
+    Ascii,
+}
+
 struct ParsedHeader {
+    format: PlyFormat,
     elements: Vec<PlyElementDesc>,
     vertex: PlyElementDesc,
     chunk: Option<PlyElementDesc>,
@@ -426,7 +485,7 @@ fn parse_property_type(s: &str) -> anyhow::Result<PlyPropertyType> {
 fn parse_header(header: &str) -> anyhow::Result<ParsedHeader> {
     let mut builders: Vec<PlyElementBuilder> = Vec::new();
     let mut current: Option<PlyElementBuilder> = None;
-    let mut format_seen = false;
+    let mut format = None;
 
     for (line_index, raw_line) in header.lines().enumerate() {
         let line = raw_line.trim();
@@ -443,10 +502,12 @@ fn parse_header(header: &str) -> anyhow::Result<ParsedHeader> {
         let fields: Vec<_> = line.split_whitespace().collect();
         match fields[0] {
             "format" if fields.len() == 3 => {
-                format_seen = true;
-                if fields[1] != "binary_little_endian" {
-                    return Err(anyhow!("Unsupported PLY format: {}", fields[1]));
-                }
+                format = Some(match fields[1] {
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    "ascii" => PlyFormat::Ascii,
+                    _ => return Err(anyhow!("Unsupported PLY format: {}", fields[1])),
+                });
                 if fields[2] != "1.0" {
                     return Err(anyhow!("Unsupported PLY version: {}", fields[2]));
                 }
@@ -483,9 +544,9 @@ fn parse_header(header: &str) -> anyhow::Result<ParsedHeader> {
     if let Some(cur) = current.take() {
         builders.push(cur);
     }
-    if !format_seen {
+    let Some(format) = format else {
         return Err(anyhow!("Missing PLY format line"));
-    }
+    };
 
     let elements: Vec<PlyElementDesc> = builders.into_iter().map(|b| b.build()).collect();
     let vertex = elements.iter().find(|e| e.name == "vertex").cloned().ok_or(anyhow!("Missing vertex element"))?;
@@ -494,6 +555,7 @@ fn parse_header(header: &str) -> anyhow::Result<ParsedHeader> {
     let is_pointcloud = POINT_CLOUD_PROPERTIES.iter().all(|&p| vertex.properties.contains_key(p));
 
     Ok(ParsedHeader {
+        format,
         num_splats: vertex.count,
         vertex,
         chunk,
@@ -504,6 +566,65 @@ fn parse_header(header: &str) -> anyhow::Result<ParsedHeader> {
     })
 }
 
+// Offset of "end_header" and the length of its line, if present
+fn find_header_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    const TERMINATOR: &[u8] = b"end_header\n";
+    const TERMINATOR_CRLF: &[u8] = b"end_header\r\n";
+    buffer.windows(TERMINATOR.len()).position(|window| window == TERMINATOR)
+        .map(|end| (end, TERMINATOR.len()))
+        .or_else(|| buffer.windows(TERMINATOR_CRLF.len()).position(|window| window == TERMINATOR_CRLF).map(|end| (end, TERMINATOR_CRLF.len())))
+}
+
+// Reads the PLY header, plus the chunk table of compressed PLYs for bounds
+pub fn probe_ply(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let Some((header_end, terminator_len)) = find_header_end(bytes) else {
+        if bytes.len() >= MAX_HEADER_BYTES {
+            return None;
+        }
+        return Some(SplatFileInfo::needs(Some(SplatFileType::PLY), need_more(bytes.len())));
+    };
+    let parsed = parse_header(std::str::from_utf8(&bytes[..header_end]).ok()?).ok()?;
+    let mut info = SplatFileInfo {
+        num_splats: Some(parsed.num_splats),
+        lod_tree: Some(false),
+        ..SplatFileInfo::new(SplatFileType::PLY)
+    };
+
+    if parsed.is_supersplat {
+        // Chunk records come first in the body, each with the AABB of 256 splats
+        let chunk = parsed.chunk.as_ref()?;
+        let table_start = header_end + terminator_len;
+        let table_end = table_start + chunk.count * chunk.record_size;
+        let (num_chunks, record_size) = (chunk.count, chunk.record_size);
+        let readable = parsed.format == PlyFormat::BinaryLittleEndian
+            && parsed.elements.first().is_some_and(|element| element.name == "chunk");
+        let state = SuperSplatState::new(parsed).ok()?;
+        info.max_sh_degree = Some(state.max_sh_degree);
+        if readable {
+            if bytes.len() < table_end {
+                info.need_bytes = Some(table_end - bytes.len());
+                return Some(info);
+            }
+            let props = &state.chunk_props;
+            let chunk_bounds = (0..num_chunks).map(|index| {
+                let offset = table_start + index * record_size;
+                let get = |prop: &PlyProperty| prop.get_f32(bytes, offset);
+                Some((
+                    [get(&props.min_x), get(&props.min_y), get(&props.min_z)],
+                    [get(&props.max_x), get(&props.max_y), get(&props.max_z)],
+                ))
+            });
+            info = info.with_bounds(union_bounds(chunk_bounds));
+        }
+    } else if parsed.is_pointcloud {
+        info.max_sh_degree = Some(0);
+    } else {
+        let state = PlyDecoderState::new(parsed.num_splats, parsed.vertex.record_size, parsed.vertex.properties).ok()?;
+        info.max_sh_degree = Some(state.max_sh_degree);
+    }
+    Some(info)
+}
+
 #[derive(Clone, Copy, Debug)]
 struct SuperSplatChunk {
     min_x: f32,
@@ -881,6 +1002,123 @@ enum PlyElementKind {
     Other,
 }
 
+// Rewrites ASCII and big-endian PLY bodies into little-endian binary records
+// as they stream in, so the decoder states only deal with one layout.
+struct PlyBodyConverter {
+    format: PlyFormat,
+    elements: Vec<(usize, Vec<PlyPropertyType>)>,
+    current_element: usize,
+    current_read: usize,
+    pending: Vec<u8>,
+}
+
+impl PlyBodyConverter {
+    fn new(format: PlyFormat, elements: &[PlyElementDesc]) -> Self {
+        Self {
+            format,
+            elements: elements.iter().map(|e| (e.count, e.property_types.clone())).collect(),
+            current_element: 0,
+            current_read: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn next_element(&mut self) -> Option<usize> {
+        while self.current_element < self.elements.len() && self.current_read >= self.elements[self.current_element].0 {
+            self.current_element += 1;
+            self.current_read = 0;
+        }
+        (self.current_element < self.elements.len()).then_some(self.current_element)
+    }
+
+    fn convert(&mut self, out: &mut Vec<u8>, finished: bool) -> anyhow::Result<()> {
+        let consumed = match self.format {
+            PlyFormat::BinaryLittleEndian => {
+                out.append(&mut self.pending);
+                return Ok(());
+            },
+            PlyFormat::BinaryBigEndian => self.convert_big_endian(out),
+            PlyFormat::Ascii => self.convert_ascii(out, finished)?,
+        };
+        self.pending.drain(..consumed);
+        Ok(())
+    }
+
+    fn convert_big_endian(&mut self, out: &mut Vec<u8>) -> usize {
+        let mut offset = 0;
+        while let Some(element) = self.next_element() {
+            let types = &self.elements[element].1;
+            let record_size: usize = types.iter().map(|ty| ty.size()).sum();
+            if record_size == 0 || self.pending.len() - offset < record_size {
+                break;
+            }
+            for ty in types {
+                let size = ty.size();
+                out.extend(self.pending[offset..offset + size].iter().rev());
+                offset += size;
+            }
+            self.current_read += 1;
+        }
+        offset
+    }
+
+    fn convert_ascii(&mut self, out: &mut Vec<u8>, finished: bool) -> anyhow::Result<usize> {
+        // Only tokens followed by whitespace are complete unless the stream has ended
+        let limit = if finished {
+            self.pending.len()
+        } else {
+            match self.pending.iter().rposition(|b| b.is_ascii_whitespace()) {
+                Some(end) => end + 1,
+                None => return Ok(0),
+            }
+        };
+
+        let mut offset = 0;
+        let mut record = Vec::new();
+        while let Some(element) = self.next_element() {
+            let types = &self.elements[element].1;
+            let mut cursor = offset;
+            record.clear();
+            for ty in types.iter() {
+                while cursor < limit && self.pending[cursor].is_ascii_whitespace() {
+                    cursor += 1;
+                }
+                let start = cursor;
+                while cursor < limit && !self.pending[cursor].is_ascii_whitespace() {
+                    cursor += 1;
+                }
+                if start == cursor {
+                    break;
+                }
+                let token = std::str::from_utf8(&self.pending[start..cursor])?;
+                write_ascii_value(&mut record, *ty, token)?;
+            }
+            if record.len() < types.iter().map(|ty| ty.size()).sum() {
+                break;
+            }
+            out.extend_from_slice(&record);
+            offset = cursor;
+            self.current_read += 1;
+        }
+        Ok(offset)
+    }
+}
+
+fn write_ascii_value(out: &mut Vec<u8>, ty: PlyPropertyType, token: &str) -> anyhow::Result<()> {
+    let value: f64 = token.parse().map_err(|_| anyhow!("Invalid PLY ASCII value: {}", token))?;
+    match ty {
+        PlyPropertyType::Char => out.push(value as i8 as u8),
+        PlyPropertyType::Uchar => out.push(value as u8),
+        PlyPropertyType::Short => out.extend_from_slice(&(value as i16).to_le_bytes()),
+        PlyPropertyType::Ushort => out.extend_from_slice(&(value as u16).to_le_bytes()),
+        PlyPropertyType::Int => out.extend_from_slice(&(value as i32).to_le_bytes()),
+        PlyPropertyType::Uint => out.extend_from_slice(&(value as u32).to_le_bytes()),
+        PlyPropertyType::Float => out.extend_from_slice(&(value as f32).to_le_bytes()),
+        PlyPropertyType::Double => out.extend_from_slice(&value.to_le_bytes()),
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 struct PlyDecoderState {
     num_splats: usize,
@@ -890,7 +1128,9 @@ struct PlyDecoderState {
     #[allow(unused)]
     properties: HashMap<String, PlyProperty>,
     xyz: [PlyProperty; 3],
-    scale: [PlyProperty; 3],
+    scale: [PlyProperty; 2],
+    // Missing for 2DGS surfel PLYs, which only have two scales
+    scale_2: Option<PlyProperty>,
     rot: [PlyProperty; 4],
     op_logi: PlyProperty,
     f_dc: [PlyProperty; 3],
@@ -898,6 +1138,8 @@ struct PlyDecoderState {
     sh1: Option<[PlyProperty; 9]>,
     sh2: Option<[PlyProperty; 15]>,
     sh3: Option<[PlyProperty; 21]>,
+    // Unrecognized scalar vertex properties, passed through as extras
+    extras: Vec<(SplatExtraInfo, PlyProperty)>,
 
     out_center: Vec<f32>,
     out_opacity: Vec<f32>,
@@ -907,6 +1149,7 @@ struct PlyDecoderState {
     out_sh1: Vec<f32>,
     out_sh2: Vec<f32>,
     out_sh3: Vec<f32>,
+    out_extra: Vec<f64>,
 }
 
 impl PlyDecoderState {
@@ -919,8 +1162,8 @@ impl PlyDecoderState {
         let scale = [
             *properties.get("scale_0").ok_or(anyhow!("Missing scale_0 property"))?,
             *properties.get("scale_1").ok_or(anyhow!("Missing scale_1 property"))?,
-            *properties.get("scale_2").ok_or(anyhow!("Missing scale_2 property"))?,
         ];
+        let scale_2 = properties.get("scale_2").copied();
         let rot = [
             *properties.get("rot_1").ok_or(anyhow!("Missing rot_0 property"))?,
             *properties.get("rot_2").ok_or(anyhow!("Missing rot_1 property"))?,
@@ -974,6 +1217,21 @@ impl PlyDecoderState {
             None
         };
 
+        let mut extras: Vec<(SplatExtraInfo, PlyProperty)> = properties.iter()
+            .filter(|(name, _)| !is_standard_property(name))
+            .map(|(name, property)| {
+                let (encoding, merge) = match property.ty {
+                    PlyPropertyType::Uchar => (SplatExtraEncoding::U8, SplatExtraMerge::Vote),
+                    PlyPropertyType::Ushort => (SplatExtraEncoding::U16, SplatExtraMerge::Vote),
+                    PlyPropertyType::Uint => (SplatExtraEncoding::U32, SplatExtraMerge::Vote),
+                    PlyPropertyType::Char | PlyPropertyType::Short | PlyPropertyType::Int => (SplatExtraEncoding::F32, SplatExtraMerge::Vote),
+                    PlyPropertyType::Float | PlyPropertyType::Double => (SplatExtraEncoding::F32, SplatExtraMerge::Mean),
+                };
+                (SplatExtraInfo::new(name, 1).with_encoding(encoding).with_merge(merge), *property)
+            })
+            .collect();
+        extras.sort_by_key(|(_, property)| property.offset);
+
         Ok(Self {
             num_splats,
             record_size,
@@ -981,6 +1239,7 @@ impl PlyDecoderState {
             properties,
             xyz,
             scale,
+            scale_2,
             rot,
             op_logi,
             f_dc,
@@ -988,6 +1247,7 @@ impl PlyDecoderState {
             sh1,
             sh2,
             sh3,
+            extras,
             out_center: Vec::new(),
             out_opacity: Vec::new(),
             out_rgb: Vec::new(),
@@ -996,10 +1256,14 @@ impl PlyDecoderState {
             out_sh1: Vec::new(),
             out_sh2: Vec::new(),
             out_sh3: Vec::new(),
+            out_extra: Vec::new(),
         })
     }
 
     fn ensure_out(&mut self, count: usize) {
+        if !self.extras.is_empty() && self.out_extra.len() < count {
+            self.out_extra.resize(count, 0.0);
+        }
         if self.out_center.len() < (count * 3) {
             self.out_center.resize(count * 3, 0.0);
         }
@@ -1179,6 +1443,26 @@ impl PlyPropertyType {
         }
     }
 
+    // Exact for every integer type, for extras like IDs and timestamps
+    pub fn get_raw_f64(&self, data: &[u8], offset: usize) -> f64 {
+        match self {
+            PlyPropertyType::Double => {
+                let bytes: [u8; 8] = data[offset..offset + 8].try_into().unwrap();
+                f64::from_le_bytes(bytes)
+            },
+            PlyPropertyType::Int => {
+                let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+                i32::from_le_bytes(bytes) as f64
+            },
+            PlyPropertyType::Uint => {
+                let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+                u32::from_le_bytes(bytes) as f64
+            },
+            // Narrower types are already exact in f32
+            _ => self.get_raw_f32(data, offset) as f64,
+        }
+    }
+
     pub fn get_u32(&self, data: &[u8], offset: usize) -> u32 {
         match self {
             PlyPropertyType::Uint | PlyPropertyType::Int | PlyPropertyType::Float => {
@@ -1223,11 +1507,20 @@ impl PlyProperty {
         self.ty.get_raw_f32(data, record_offset + self.offset)
     }
 
+    pub fn get_raw_f64(&self, data: &[u8], record_offset: usize) -> f64 {
+        self.ty.get_raw_f64(data, record_offset + self.offset)
+    }
+
     pub fn get_u32(&self, data: &[u8], record_offset: usize) -> u32 {
         self.ty.get_u32(data, record_offset + self.offset)
     }
 }
 
+fn is_standard_property(name: &str) -> bool {
+    matches!(name, "x" | "y" | "z" | "nx" | "ny" | "nz" | "opacity")
+        || ["scale_", "rot_", "f_dc_", "f_rest_"].iter().any(|prefix| name.starts_with(prefix))
+}
+
 fn f_rest_offset(degree: usize) -> usize {
     match degree {
         0 => 0,
@@ -1248,6 +1541,7 @@ pub struct PlyEncoder<T: SplatGetter> {
     getter: T,
     max_sh_out: Option<u8>,
     compatibility: bool,
+    compressed: bool,
 }
 
 impl<T: SplatGetter> PlyEncoder<T> {
@@ -1256,6 +1550,7 @@ impl<T: SplatGetter> PlyEncoder<T> {
             getter,
             max_sh_out: None,
             compatibility: false,
+            compressed: false,
         }
     }
 
@@ -1269,10 +1564,18 @@ impl<T: SplatGetter> PlyEncoder<T> {
         self
     }
 
+    pub fn with_compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
     pub fn encode_to_writer<W: std::io::Write>(mut self, writer: &mut W) -> anyhow::Result<()> {
         let num_splats = self.getter.num_splats();
         let sh_src = self.getter.max_sh_degree() as u8;
         let sh_degree = self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) as usize;
+        if self.compressed {
+            return self.encode_compressed_to_writer(writer, num_splats, sh_degree);
+        }
 
         // Header (UTF-8 text)
         let mut header = String::new();
@@ -1287,9 +1590,13 @@ impl<T: SplatGetter> PlyEncoder<T> {
             header.push_str("property float ny\n");
             header.push_str("property float nz\n");
         }
+        // Surfels are written in the 2DGS layout with only two scales
+        let surfel = self.getter.splat_type() == SplatType::Surfel;
         header.push_str("property float scale_0\n");
         header.push_str("property float scale_1\n");
-        header.push_str("property float scale_2\n");
+        if !surfel {
+            header.push_str("property float scale_2\n");
+        }
         header.push_str("property float rot_0\n");
         header.push_str("property float rot_1\n");
         header.push_str("property float rot_2\n");
@@ -1363,7 +1670,9 @@ impl<T: SplatGetter> PlyEncoder<T> {
                 // ln scales
                 write_f32_le(scales[i3 + 0].ln())?;
                 write_f32_le(scales[i3 + 1].ln())?;
-                write_f32_le(scales[i3 + 2].ln())?;
+                if !surfel {
+                    write_f32_le(scales[i3 + 2].ln())?;
+                }
 
                 // quat (rot_0..rot_3), write normalized to be safe
                 let mut qx = quats[i4 + 0];
@@ -1426,6 +1735,177 @@ impl<T: SplatGetter> PlyEncoder<T> {
         Ok(())
     }
 
+    // SuperSplat "compressed.ply" layout: a chunk element with per-256-splat
+    // bounds, packed vertex words quantized within those bounds, and an
+    // optional uchar sh element. The getter is read once per element.
+    fn encode_compressed_to_writer<W: std::io::Write>(&mut self, writer: &mut W, num_splats: usize, sh_degree: usize) -> anyhow::Result<()> {
+        let num_chunks = num_splats.div_ceil(SUPER_CHUNK_SIZE);
+        let num_f_rest = match sh_degree { 0 => 0, 1 => 9, 2 => 24, 3 => 45, _ => 0 };
+
+        let mut header = String::new();
+        header.push_str("ply\n");
+        header.push_str("format binary_little_endian 1.0\n");
+        header.push_str(&format!("element chunk {}\n", num_chunks));
+        for name in SUPER_CHUNK_PROPERTIES {
+            header.push_str(&format!("property float {}\n", name));
+        }
+        header.push_str(&format!("element vertex {}\n", num_splats));
+        header.push_str("property uint packed_position\n");
+        header.push_str("property uint packed_rotation\n");
+        header.push_str("property uint packed_scale\n");
+        header.push_str("property uint packed_color\n");
+        if num_f_rest > 0 {
+            header.push_str(&format!("element sh {}\n", num_splats));
+            for i in 0..num_f_rest {
+                header.push_str(&format!("property uchar f_rest_{}\n", i));
+            }
+        }
+        header.push_str("end_header\n");
+        writer.write_all(header.as_bytes())?;
+
+        let mut centers: Vec<f32> = Vec::new();
+        let mut opacities: Vec<f32> = Vec::new();
+        let mut rgbs: Vec<f32> = Vec::new();
+        let mut scales: Vec<f32> = Vec::new();
+        let mut quats: Vec<f32> = Vec::new();
+        let mut sh1: Vec<f32> = Vec::new();
+        let mut sh2: Vec<f32> = Vec::new();
+        let mut sh3: Vec<f32> = Vec::new();
+
+        // Pass 1: chunk bounds for center, ln scale and rgb
+        let mut chunks: Vec<SuperSplatChunk> = Vec::with_capacity(num_chunks);
+        let mut base = 0usize;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            ensure_len(&mut centers, count * 3);
+            ensure_len(&mut rgbs, count * 3);
+            ensure_len(&mut scales, count * 3);
+            self.getter.get_center(base, count, &mut centers[..count * 3]);
+            self.getter.get_rgb(base, count, &mut rgbs[..count * 3]);
+            self.getter.get_scale(base, count, &mut scales[..count * 3]);
+
+            for start in (0..count).step_by(SUPER_CHUNK_SIZE) {
+                let end = (start + SUPER_CHUNK_SIZE).min(count);
+                let mut min = [f32::INFINITY; 9];
+                let mut max = [f32::NEG_INFINITY; 9];
+                for i in start..end {
+                    let i3 = i * 3;
+                    for d in 0..3 {
+                        let values = [centers[i3 + d], super_ln_scale(scales[i3 + d]), rgbs[i3 + d]];
+                        for (j, value) in values.into_iter().enumerate() {
+                            min[j * 3 + d] = min[j * 3 + d].min(value);
+                            max[j * 3 + d] = max[j * 3 + d].max(value);
+                        }
+                    }
+                }
+                chunks.push(SuperSplatChunk {
+                    min_x: min[0], min_y: min[1], min_z: min[2],
+                    max_x: max[0], max_y: max[1], max_z: max[2],
+                    min_scale_x: min[3], min_scale_y: min[4], min_scale_z: min[5],
+                    max_scale_x: max[3], max_scale_y: max[4], max_scale_z: max[5],
+                    min_r: min[6], min_g: min[7], min_b: min[8],
+                    max_r: max[6], max_g: max[7], max_b: max[8],
+                });
+            }
+            base += count;
+        }
+
+        for c in chunks.iter() {
+            let values = [
+                c.min_x, c.min_y, c.min_z, c.max_x, c.max_y, c.max_z,
+                c.min_scale_x, c.min_scale_y, c.min_scale_z, c.max_scale_x, c.max_scale_y, c.max_scale_z,
+                c.min_r, c.min_g, c.min_b, c.max_r, c.max_g, c.max_b,
+            ];
+            for v in values {
+                writer.write_all(&v.to_le_bytes())?;
+            }
+        }
+
+        // Pass 2: packed vertex words
+        let mut base = 0usize;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            ensure_len(&mut centers, count * 3);
+            ensure_len(&mut opacities, count);
+            ensure_len(&mut rgbs, count * 3);
+            ensure_len(&mut scales, count * 3);
+            ensure_len(&mut quats, count * 4);
+            self.getter.get_center(base, count, &mut centers[..count * 3]);
+            self.getter.get_opacity(base, count, &mut opacities[..count]);
+            self.getter.get_rgb(base, count, &mut rgbs[..count * 3]);
+            self.getter.get_scale(base, count, &mut scales[..count * 3]);
+            self.getter.get_quat(base, count, &mut quats[..count * 4]);
+
+            for i in 0..count {
+                let c = &chunks[(base + i) / SUPER_CHUNK_SIZE];
+                let i3 = i * 3;
+                let i4 = i * 4;
+
+                let packed_position =
+                    (quantize_unorm(centers[i3], c.min_x, c.max_x, 2047) << 21) |
+                    (quantize_unorm(centers[i3 + 1], c.min_y, c.max_y, 1023) << 11) |
+                    quantize_unorm(centers[i3 + 2], c.min_z, c.max_z, 2047);
+
+                let packed_rotation = pack_quat_smallest3(array::from_fn(|k| quats[i4 + k]));
+
+                let packed_scale =
+                    (quantize_unorm(super_ln_scale(scales[i3]), c.min_scale_x, c.max_scale_x, 2047) << 21) |
+                    (quantize_unorm(super_ln_scale(scales[i3 + 1]), c.min_scale_y, c.max_scale_y, 1023) << 11) |
+                    quantize_unorm(super_ln_scale(scales[i3 + 2]), c.min_scale_z, c.max_scale_z, 2047);
+
+                let packed_color =
+                    (quantize_unorm(rgbs[i3], c.min_r, c.max_r, 255) << 24) |
+                    (quantize_unorm(rgbs[i3 + 1], c.min_g, c.max_g, 255) << 16) |
+                    (quantize_unorm(rgbs[i3 + 2], c.min_b, c.max_b, 255) << 8) |
+                    quantize_unorm(opacities[i], 0.0, 1.0, 255);
+
+                writer.write_all(&packed_position.to_le_bytes())?;
+                writer.write_all(&packed_rotation.to_le_bytes())?;
+                writer.write_all(&packed_scale.to_le_bytes())?;
+                writer.write_all(&packed_color.to_le_bytes())?;
+            }
+            base += count;
+        }
+
+        // Pass 3: sh element, channel-planar like the uncompressed f_rest layout
+        if num_f_rest > 0 {
+            let stride = num_f_rest / 3;
+            let mut record = vec![0u8; num_f_rest];
+            let mut base = 0usize;
+            while base < num_splats {
+                let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+                if sh_degree >= 1 { ensure_len(&mut sh1, count * 9); }
+                if sh_degree >= 2 { ensure_len(&mut sh2, count * 15); }
+                if sh_degree >= 3 { ensure_len(&mut sh3, count * 21); }
+                if sh_degree >= 1 { self.getter.get_sh1(base, count, &mut sh1[..count * 9]); }
+                if sh_degree >= 2 { self.getter.get_sh2(base, count, &mut sh2[..count * 15]); }
+                if sh_degree >= 3 { self.getter.get_sh3(base, count, &mut sh3[..count * 21]); }
+
+                for i in 0..count {
+                    for d in 0..3 {
+                        for k in 0..3 {
+                            record[k + d * stride] = quantize_super_sh(sh1[i * 9 + k * 3 + d]);
+                        }
+                        if sh_degree >= 2 {
+                            for k in 0..5 {
+                                record[3 + k + d * stride] = quantize_super_sh(sh2[i * 15 + k * 3 + d]);
+                            }
+                        }
+                        if sh_degree >= 3 {
+                            for k in 0..7 {
+                                record[8 + k + d * stride] = quantize_super_sh(sh3[i * 21 + k * 3 + d]);
+                            }
+                        }
+                    }
+                    writer.write_all(&record)?;
+                }
+                base += count;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn encode(self) -> anyhow::Result<Vec<u8>> {
         let mut out: Vec<u8> = Vec::new();
         self.encode_to_writer(&mut out)?;
@@ -1437,3 +1917,37 @@ impl<T: SplatGetter> PlyEncoder<T> {
 fn ensure_len(buf: &mut Vec<f32>, len: usize) {
     if buf.len() < len { buf.resize(len, 0.0); }
 }
+
+#[inline]
+fn super_ln_scale(scale: f32) -> f32 {
+    scale.max(1.0e-30).ln().clamp(-20.0, 20.0)
+}
+
+#[inline]
+fn quantize_unorm(value: f32, min: f32, max: f32, max_code: u32) -> u32 {
+    let range = max - min;
+    let t = if range > 0.0 { (value - min) / range } else { 0.0 };
+    (t * max_code as f32).round().clamp(0.0, max_code as f32) as u32
+}
+
+#[inline]
+fn quantize_super_sh(value: f32) -> u8 {
+    ((value + 4.0) * 255.0 / 8.0).round().clamp(0.0, 255.0) as u8
+}
+
+// Inverse of the packed_rotation decode: 2-bit index of the dropped largest
+// component followed by the remaining three at 10 bits each.
+fn pack_quat_smallest3(quat: [f32; 4]) -> u32 {
+    let [x, y, z, w] = quat;
+    let norm = (x * x + y * y + z * z + w * w).sqrt();
+    let [x, y, z, w] = if norm > 0.0 { [x / norm, y / norm, z / norm, w / norm] } else { [0.0, 0.0, 0.0, 1.0] };
+    let (order, largest, rest) = [
+        (0u32, w, [x, y, z]),
+        (1, x, [w, y, z]),
+        (2, y, [w, x, z]),
+        (3, z, [w, x, y]),
+    ].into_iter().max_by(|a, b| a.1.abs().total_cmp(&b.1.abs())).unwrap();
+    let sign = if largest < 0.0 { -1.0 } else { 1.0 };
+    let [r0, r1, r2] = rest.map(|v| ((sign * v / SQRT_2 + 0.5) * 1023.0).round().clamp(0.0, 1023.0) as u32);
+    (order << 30) | (r0 << 20) | (r1 << 10) | r2
+}
@@ -0,0 +1,212 @@
+use serde::Serialize;
+
+use crate::{
+    antisplat::probe_antisplat,
+    decoder::{is_sogs_meta, parse_zip_entry_header, Inflater, ZipEntryHeader, SetSplatEncoding, SplatFileType, GZIP_MAGIC},
+    gltf::{probe_gltf, GLB_MAGIC},
+    ksplat::probe_ksplat,
+    ply::{probe_ply, PLY_MAGIC},
+    rad::{probe_rad, RAD_CHUNK_MAGIC, RAD_MAGIC},
+    sogs::probe_sogs_meta,
+    spz::{probe_spz, SPZ_MAGIC},
+};
+
+const ZIP_MAGIC: u32 = 0x04034b50;
+// Compressed input per inflate step, and the inflated size past which a
+// gzip header is no longer searched
+const GZIP_PROBE_STEP: usize = 4096;
+const GZIP_PROBE_LIMIT: usize = 16 * 1024 * 1024;
+
+// Header metadata read from the start of a splat file, without decoding any
+// splats. Fields are None when the format doesn't record them.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplatFileInfo {
+    // None only while too few bytes are available to detect the format
+    pub file_type: Option<SplatFileType>,
+    pub num_splats: Option<usize>,
+    pub max_sh_degree: Option<usize>,
+    pub lod_tree: Option<bool>,
+    pub antialias: Option<bool>,
+    pub encoding: Option<SetSplatEncoding>,
+    // AABB of splat centers, or of their extent for RAD
+    pub bounds_min: Option<[f32; 3]>,
+    pub bounds_max: Option<[f32; 3]>,
+    // Further bytes needed to read the rest of the header, None once complete.
+    // Exact for fixed-size headers, an estimate for text and compressed ones.
+    pub need_bytes: Option<usize>,
+}
+
+impl SplatFileInfo {
+    pub fn new(file_type: SplatFileType) -> Self {
+        Self { file_type: Some(file_type), ..Default::default() }
+    }
+
+    pub fn needs(file_type: Option<SplatFileType>, need_bytes: usize) -> Self {
+        Self { file_type, need_bytes: Some(need_bytes.max(1)), ..Default::default() }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.need_bytes.is_none()
+    }
+
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        Some((self.bounds_min?, self.bounds_max?))
+    }
+
+    pub(crate) fn with_bounds(mut self, bounds: Option<([f32; 3], [f32; 3])>) -> Self {
+        if let Some((bounds_min, bounds_max)) = bounds {
+            self.bounds_min = Some(bounds_min);
+            self.bounds_max = Some(bounds_max);
+        }
+        self
+    }
+}
+
+// Guess for headers without a stated length, doubling the prefix each time
+pub(crate) fn need_more(have: usize) -> usize {
+    have.max(4096)
+}
+
+// Union of per-chunk AABBs, or None if any chunk lacks one
+pub(crate) fn union_bounds(bounds: impl IntoIterator<Item = Option<([f32; 3], [f32; 3])>>) -> Option<([f32; 3], [f32; 3])> {
+    let mut union: Option<([f32; 3], [f32; 3])> = None;
+    for item in bounds {
+        let (min, max) = item?;
+        let (union_min, union_max) = union.get_or_insert((min, max));
+        for d in 0..3 {
+            union_min[d] = union_min[d].min(min[d]);
+            union_max[d] = union_max[d].max(max[d]);
+        }
+    }
+    union
+}
+
+// Reads file type, splat count, SH degree, LoD and other header fields from
+// a prefix of a splat file. Returns None if the bytes aren't a recognized
+// format, or Some with need_bytes set if the prefix ends inside the header.
+pub fn probe(bytes: &[u8]) -> Option<SplatFileInfo> {
+    if bytes.len() < 4 {
+        return Some(SplatFileInfo::needs(None, 4 - bytes.len()));
+    }
+
+    let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if (magic & 0x00ffffff) == PLY_MAGIC {
+        probe_ply(bytes)
+    } else if (magic & 0x00ffffff) == GZIP_MAGIC {
+        probe_gzip(bytes)
+    } else if magic == ZIP_MAGIC {
+        probe_zip(bytes)
+    } else if magic == RAD_MAGIC || magic == RAD_CHUNK_MAGIC {
+        probe_rad(bytes)
+    } else if magic == GLB_MAGIC {
+        probe_gltf(bytes)
+    } else if bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+        probe_json(bytes)
+    } else {
+        // .ksplat and .splat have no magic number, so sniff their contents
+        let ksplat = probe_ksplat(bytes);
+        if ksplat.as_ref().is_some_and(|info| info.file_type.is_some()) {
+            return ksplat;
+        }
+        let antisplat = probe_antisplat(bytes);
+        match (ksplat, antisplat) {
+            (None, antisplat) => antisplat,
+            (Some(ksplat), Some(antisplat)) if antisplat.file_type.is_none() => {
+                Some(SplatFileInfo::needs(None, ksplat.need_bytes?.max(antisplat.need_bytes?)))
+            },
+            (ksplat, _) => ksplat,
+        }
+    }
+}
+
+fn probe_gzip(bytes: &[u8]) -> Option<SplatFileInfo> {
+    // Inflate in steps, stopping once the inner header is complete, so a
+    // whole compressed file is never expanded just to read its header
+    let mut inflater = Inflater::gzip();
+    let mut inflated = Vec::new();
+    let mut info = None;
+    for piece in bytes.chunks(GZIP_PROBE_STEP) {
+        inflater.push(piece, &mut inflated).ok()?;
+        if inflated.len() < 4 {
+            continue;
+        }
+        let magic = u32::from_le_bytes([inflated[0], inflated[1], inflated[2], inflated[3]]);
+        info = if magic == SPZ_MAGIC { probe_spz(&inflated) } else { probe(&inflated) };
+        if info.as_ref().is_some_and(|info| info.is_complete()) || inflated.len() >= GZIP_PROBE_LIMIT {
+            break;
+        }
+    }
+    if inflated.len() < 4 {
+        return Some(SplatFileInfo::needs(None, need_more(bytes.len())));
+    }
+
+    // Compressed size of the rest of the header is unknown
+    let info = info?;
+    Some(match info.need_bytes {
+        Some(_) => SplatFileInfo { need_bytes: Some(need_more(bytes.len())), ..info },
+        None => info,
+    })
+}
+
+fn probe_zip(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let Some(entry) = parse_zip_entry_header(bytes).ok()? else {
+        return Some(SplatFileInfo::needs(None, need_more(bytes.len())));
+    };
+    let name = entry.name.to_lowercase();
+    if name.ends_with(".npy") {
+        return Some(SplatFileInfo::new(SplatFileType::NPZ));
+    }
+    if is_sogs_meta(&name) {
+        let contents = zip_entry_contents(bytes, &entry)?;
+        return match serde_json::from_slice::<serde_json::Value>(&contents) {
+            Ok(_) => probe_sogs_meta(&contents),
+            Err(err) if err.is_eof() => {
+                let need_bytes = match entry.compressed_size {
+                    Some(size) => (entry.data_start + size).saturating_sub(bytes.len()),
+                    None => need_more(bytes.len()),
+                };
+                Some(SplatFileInfo::needs(Some(SplatFileType::SOGS), need_bytes))
+            },
+            Err(_) => None,
+        };
+    }
+
+    match SplatFileType::from_pathname(&name) {
+        // SOGS images stored ahead of meta.json, which may be anywhere
+        None if name.ends_with(".webp") => Some(SplatFileInfo::new(SplatFileType::SOGS)),
+        None | Some(SplatFileType::SOGS) | Some(SplatFileType::NPZ) => None,
+        Some(file_type) => {
+            // A zip holding a single splat file is probed by its contents
+            let mut info = probe(&zip_entry_contents(bytes, &entry)?)?;
+            info.file_type.get_or_insert(file_type);
+            if info.need_bytes.is_some() {
+                info.need_bytes = Some(need_more(bytes.len()));
+            }
+            Some(info)
+        },
+    }
+}
+
+fn zip_entry_contents(bytes: &[u8], entry: &ZipEntryHeader) -> Option<Vec<u8>> {
+    let mut contents = Vec::new();
+    let mut inflater = Inflater::zip_entry(entry.method, entry.compressed_size).ok()?;
+    inflater.push(&bytes[entry.data_start..], &mut contents).ok()?;
+    Some(contents)
+}
+
+// Bare SOGS meta.json or .gltf JSON
+fn probe_json(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let value: serde_json::Value = match serde_json::from_slice(bytes) {
+        Ok(value) => value,
+        Err(err) if err.is_eof() => return Some(SplatFileInfo::needs(None, need_more(bytes.len()))),
+        Err(_) => return None,
+    };
+    if value.get("means").is_some() {
+        probe_sogs_meta(bytes)
+    } else if value.get("asset").is_some() || value.get("meshes").is_some() {
+        probe_gltf(bytes)
+    } else {
+        None
+    }
+}
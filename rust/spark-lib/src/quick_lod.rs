@@ -4,7 +4,7 @@ use ahash::AHashMap;
 use glam::I64Vec3;
 use smallvec::{smallvec, SmallVec};
 
-use crate::{gsplat::*, tsplat::{Tsplat, TsplatMut, TsplatArray}};
+use crate::{bhatt_lod::flatten_merged, gsplat::*, tsplat::{Tsplat, TsplatMut, TsplatArray}};
 
 const CHUNK_LEVELS: i16 = 2;
 
@@ -86,6 +86,9 @@ pub fn compute_lod_tree(splats: &mut GsplatArray, lod_base: f32, merge_filter: b
             if indices.len() > 1 {
                 let merge_step = if merge_filter { step } else { 0.0 };
                 let merged = splats.new_merged(indices, merge_step);
+                if splats.is_planar() {
+                    flatten_merged(splats, merged, indices, merge_step);
+                }
                 // splats.extras[merged].level = level + 1;
                 indices.clear();
                 indices.push(merged);
@@ -158,6 +161,9 @@ pub fn compute_lod_tree(splats: &mut GsplatArray, lod_base: f32, merge_filter: b
                 .collect();
             let merge_step = if merge_filter { step } else { 0.0 };
             let merged = splats.new_merged(&indices, merge_step);
+            if splats.is_planar() {
+                flatten_merged(splats, merged, &indices, merge_step);
+            }
             merged
         } else {
             let only = previous.values().next().unwrap();
@@ -1,6 +1,10 @@
 use std::array;
-use std::io::Write;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
 
+use glam::{Quat, Vec3A};
 use half::f16;
 
 use ordered_float::OrderedFloat;
@@ -17,15 +21,24 @@ use miniz_oxide::inflate::decompress_to_vec;
 //     encode_all(data, 19).unwrap()
 // }
 
-use crate::decoder::{ChunkReceiver, SetSplatEncoding, SplatEncoding, SplatGetter, SplatInit, SplatReceiver};
+use crate::chunk_tree::Aabb;
+use crate::decoder::{ChunkReceiver, SetSplatEncoding, SplatEncoding, SplatExtraEncoding, SplatExtraInfo, SplatFileType, SplatGetter, SplatInit, SplatReceiver, SplatType};
+use crate::gsplat::Gsplat;
+use crate::probe::{union_bounds, SplatFileInfo};
 use crate::sh_clustering::ShClusters;
 use crate::splat_encode::{self, decode_scale8, encode_scale8_zero};
+use crate::range_coder::{AdaptiveModel, RangeDecoder, RangeEncoder};
 
 pub const RAD_MAGIC: u32 = 0x30444152; // 'RAD0'
 pub const RAD_CHUNK_MAGIC: u32 = 0x43444152; // 'RADC'
+pub const RAD_CHUNK_SIZE: usize = 65536;
 
 const GZ_LEVEL: u8 = 6;
 
+// Chunk bounds cover each splat's ellipsoid out to this many standard
+// deviations, as in the BVH
+const RAD_BOUNDS_STD_DEVS: f32 = 3.0;
+
 
 pub struct RadEncoder<T: SplatGetter> {
     pub getter: T,
@@ -39,9 +52,34 @@ pub struct RadEncoder<T: SplatGetter> {
     pub sh_encoding: RadShEncoding,
     pub sh_label_encoding: RadShLabelEncoding,
     pub sh_clusters: Option<ShClusters>,
+    pub compression: RadCompression,
+    pub filter: RadFilter,
+    pub checksums: bool,
     pub comment: Option<String>,
 }
 
+// Auto tries every candidate per property and keeps the smallest output.
+// Gz remains the default so files stay readable by older decoders.
+// Arith range-codes quantized properties and falls back to Gz for the rest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RadCompression {
+    Auto,
+    None,
+    #[default]
+    Gz,
+    Lz4,
+    Arith,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RadFilter {
+    Auto,
+    #[default]
+    None,
+    Shuffle,
+    BitShuffle,
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RadCenterEncoding {
     #[default]
@@ -117,6 +155,42 @@ pub struct RadChunkRange {
     count: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     filename: Option<String>,
+    // AABB of the chunk's splats at RAD_BOUNDS_STD_DEVS
+    #[serde(rename = "boundsMin", skip_serializing_if = "Option::is_none")]
+    bounds_min: Option<[f32; 3]>,
+    #[serde(rename = "boundsMax", skip_serializing_if = "Option::is_none")]
+    bounds_max: Option<[f32; 3]>,
+    #[serde(rename = "minFeatureSize", skip_serializing_if = "Option::is_none")]
+    min_feature_size: Option<f32>,
+    #[serde(rename = "maxFeatureSize", skip_serializing_if = "Option::is_none")]
+    max_feature_size: Option<f32>,
+    // LoD tree depth range, root at depth 0
+    #[serde(rename = "minDepth", skip_serializing_if = "Option::is_none")]
+    min_depth: Option<u32>,
+    #[serde(rename = "maxDepth", skip_serializing_if = "Option::is_none")]
+    max_depth: Option<u32>,
+    // CRC32 of the complete chunk bytes, including the RADC header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crc32: Option<u32>,
+}
+
+impl RadChunkRange {
+    pub fn offset(&self) -> u64 { self.offset }
+    pub fn bytes(&self) -> u64 { self.bytes }
+    pub fn filename(&self) -> Option<&str> { self.filename.as_deref() }
+    pub fn crc32(&self) -> Option<u32> { self.crc32 }
+
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        Some((self.bounds_min?, self.bounds_max?))
+    }
+
+    pub fn feature_size_range(&self) -> Option<(f32, f32)> {
+        Some((self.min_feature_size?, self.max_feature_size?))
+    }
+
+    pub fn depth_range(&self) -> Option<(u32, u32)> {
+        Some((self.min_depth?, self.max_depth?))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -139,13 +213,45 @@ pub struct RadMeta {
     #[serde(rename = "shCodeCount", skip_serializing_if = "Option::is_none")]
     sh_code_count: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    extras: Option<Vec<SplatExtraInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     comment: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl RadMeta {
+    pub fn count(&self) -> u64 { self.count }
+    pub fn splat_type(&self) -> SplatType { SplatType::from(self.ty) }
+    pub fn chunk_size(&self) -> Option<usize> { self.chunk_size }
+    pub fn chunks(&self) -> &[RadChunkRange] { &self.chunks }
+    pub fn extras(&self) -> &[SplatExtraInfo] { self.extras.as_deref().unwrap_or_default() }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RadType {
     #[serde(rename = "gsplat")]
     Gsplat,
+    // 2DGS surfels: two scales, orientation with the normal along local z,
+    // and optional normal and distortion properties
+    #[serde(rename = "surfel")]
+    Surfel,
+}
+
+impl From<SplatType> for RadType {
+    fn from(splat_type: SplatType) -> Self {
+        match splat_type {
+            SplatType::Gsplat => Self::Gsplat,
+            SplatType::Surfel => Self::Surfel,
+        }
+    }
+}
+
+impl From<RadType> for SplatType {
+    fn from(ty: RadType) -> Self {
+        match ty {
+            RadType::Gsplat => Self::Gsplat,
+            RadType::Surfel => Self::Surfel,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -161,6 +267,11 @@ pub struct RadChunkMeta {
     lod_tree: Option<bool>,
     #[serde(rename = "splatEncoding", skip_serializing_if = "Option::is_none")]
     splat_encoding: Option<SetSplatEncoding>,
+    // Repeated from RadMeta so chunks can be decoded in isolation
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    ty: Option<RadType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extras: Option<Vec<SplatExtraInfo>>,
     properties: Vec<RadChunkProperty>,
 }
 
@@ -169,10 +280,20 @@ pub struct RadChunkProperty {
     offset: u64,
     bytes: u64,
     property: RadChunkPropertyName,
+    // Extra channel name for property "extra"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
     encoding: RadChunkPropertyEncoding,
     #[serde(skip_serializing_if = "Option::is_none")]
     compression: Option<RadChunkPropertyCompression>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<RadChunkPropertyFilter>,
+    #[serde(rename = "filterStride", skip_serializing_if = "Option::is_none")]
+    filter_stride: Option<u32>,
+    // CRC32 of the stored (compressed) property bytes, excluding padding
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crc32: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     min: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max: Option<f32>,
@@ -209,6 +330,12 @@ pub enum RadChunkPropertyName {
     Sh3Code,
     #[serde(rename = "sh_label")]
     ShLabel,
+    #[serde(rename = "extra")]
+    Extra,
+    #[serde(rename = "normal")]
+    Normal,
+    #[serde(rename = "distortion")]
+    Distortion,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -236,16 +363,29 @@ pub enum RadChunkPropertyEncoding {
     LnF16,
     #[serde(rename = "oct88r8")]
     Oct88R8,
+    #[serde(rename = "u8")]
+    U8,
     #[serde(rename = "u16")]
     U16,
     #[serde(rename = "u32")]
     U32,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RadChunkPropertyCompression {
     Gz,
+    Lz4,
+    Arith,
+}
+
+// Reversible byte transforms applied before compression, with filterStride
+// giving the number of bytes per element (typically bytes per splat).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RadChunkPropertyFilter {
+    Shuffle,
+    BitShuffle,
 }
 
 impl<T: SplatGetter> RadEncoder<T> {
@@ -262,6 +402,9 @@ impl<T: SplatGetter> RadEncoder<T> {
             sh_encoding: RadShEncoding::default(),
             sh_label_encoding: RadShLabelEncoding::default(),
             sh_clusters: None,
+            compression: RadCompression::default(),
+            filter: RadFilter::default(),
+            checksums: true,
             comment: None,
         }
     }
@@ -306,6 +449,22 @@ impl<T: SplatGetter> RadEncoder<T> {
         self
     }
 
+    pub fn with_compression(mut self, compression: RadCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: RadFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // Write CRC32 values for each chunk and property (default true)
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
     pub fn with_comment(mut self, comment: String) -> Self {
         self.comment = Some(comment);
         self
@@ -520,7 +679,6 @@ impl<T: SplatGetter> RadEncoder<T> {
 
     pub fn encode_with_chunks<W: Write>(&mut self, writer: &mut W, chunk_prefix: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
         const PRETTY: bool = true;
-        const CHUNK_SIZE: usize = 65536;
 
         let num_splats = self.getter.num_splats();
         let max_sh = self.getter.max_sh_degree().min(self.max_sh);
@@ -528,58 +686,52 @@ impl<T: SplatGetter> RadEncoder<T> {
 
         let mut buffer = Vec::new();
         let buffer_dim = if max_sh == 0 { 4 } else if max_sh == 1 { 9 } else if max_sh == 2 { 15 } else { 21 };
-        buffer.resize(CHUNK_SIZE * buffer_dim, 0.0);
+        buffer.resize(RAD_CHUNK_SIZE * buffer_dim, 0.0);
 
         let mut buffer_u16 = Vec::new();
         let mut buffer_usize = Vec::new();        
         if self.getter.has_lod_tree() {
-            buffer_u16.resize(CHUNK_SIZE, 0);
-            buffer_usize.resize(CHUNK_SIZE, 0);
+            buffer_u16.resize(RAD_CHUNK_SIZE, 0);
+            buffer_usize.resize(RAD_CHUNK_SIZE, 0);
         }
 
-        let num_chunks = num_splats.div_ceil(CHUNK_SIZE);
+        let depths = if self.getter.has_lod_tree() { Some(self.compute_lod_depths(num_splats)) } else { None };
+
+        let num_chunks = num_splats.div_ceil(RAD_CHUNK_SIZE);
         let mut chunks = Vec::with_capacity(num_chunks);
         let mut chunk_ranges = Vec::with_capacity(num_chunks);
         let mut offset: u64 = 0;
 
         for chunk_index in 0..num_chunks {
-            let base = chunk_index * CHUNK_SIZE;
-            let count = (num_splats - base).min(CHUNK_SIZE);
-            let chunk = self.encode_chunk(base, count, &encoding, &mut buffer, &mut buffer_u16, &mut buffer_usize)?;
+            let base = chunk_index * RAD_CHUNK_SIZE;
+            let count = (num_splats - base).min(RAD_CHUNK_SIZE);
+            let chunk = self.encode_chunk(base, base, count, &encoding, &mut buffer, &mut buffer_u16, &mut buffer_usize)?;
 
             let filename = if chunk_prefix.is_empty() { None } else {
                 Some(format!("{}{}.radc", chunk_prefix, chunk_index))
             };
-            chunk_ranges.push(RadChunkRange {
+            let mut range = RadChunkRange {
                 offset: if chunk_prefix.is_empty() { offset } else { 0 },
                 bytes: chunk.len() as u64,
                 // base: Some(base),
                 // count: Some(count),
                 filename,
+                crc32: if self.checksums { Some(crc32(&chunk)) } else { None },
                 ..Default::default()
-            });
+            };
+            self.compute_chunk_bounds(base, count, &mut buffer, &mut range);
+            if let Some(depths) = depths.as_ref() {
+                let chunk_depths = &depths[base..base + count];
+                range.min_depth = chunk_depths.iter().min().copied();
+                range.max_depth = chunk_depths.iter().max().copied();
+            }
+            chunk_ranges.push(range);
             offset += chunk.len() as u64;
             chunks.push(chunk);
         }
         let all_chunk_bytes = offset;
 
-        let mut meta = RadMeta {
-            version: 1,
-            ty: RadType::Gsplat,
-            count: num_splats as u64,
-            max_sh: Some(max_sh),
-            lod_tree: if self.getter.has_lod_tree() { Some(true) } else { None },
-            chunk_size: Some(CHUNK_SIZE),
-            all_chunk_bytes: all_chunk_bytes,
-            chunks: chunk_ranges,
-            splat_encoding: None,
-            sh_code_count: self.sh_clusters.as_ref().map(|c| c.num_clusters as u32),
-            comment: self.comment.clone(),
-        };
-        if let Some(mut encoding) = self.encoding.clone().or_else(|| self.getter.get_encoding()) {
-            encoding.lod_opacity = self.getter.has_lod_tree();
-            meta.splat_encoding = Some(SetSplatEncoding::from(encoding));
-        }
+        let meta = self.new_meta(num_splats, RAD_CHUNK_SIZE, chunk_ranges, all_chunk_bytes);
         let meta_bytes = if PRETTY {
             let mut meta_bytes = serde_json::to_vec_pretty(&meta)?;
             meta_bytes.push(b'\n');
@@ -602,6 +754,171 @@ impl<T: SplatGetter> RadEncoder<T> {
         Ok(chunks)
     }
 
+    // Converts into a RadStreamEncoder with the same settings. The getter is
+    // replaced by each pushed chunk, so it can be left empty.
+    pub fn into_stream<W: Write + Seek>(self, writer: W, num_splats: usize) -> anyhow::Result<RadStreamEncoder<T, W>> {
+        RadStreamEncoder::new(self, writer, num_splats)
+    }
+
+    fn new_meta(&mut self, num_splats: usize, chunk_size: usize, chunks: Vec<RadChunkRange>, all_chunk_bytes: u64) -> RadMeta {
+        let mut meta = RadMeta {
+            version: 1,
+            ty: RadType::from(self.getter.splat_type()),
+            count: num_splats as u64,
+            max_sh: Some(self.getter.max_sh_degree().min(self.max_sh)),
+            lod_tree: if self.getter.has_lod_tree() { Some(true) } else { None },
+            chunk_size: Some(chunk_size),
+            all_chunk_bytes,
+            chunks,
+            splat_encoding: None,
+            sh_code_count: self.sh_clusters.as_ref().map(|c| c.num_clusters as u32),
+            extras: Some(self.getter.get_extras()).filter(|extras| !extras.is_empty()),
+            comment: self.comment.clone(),
+        };
+        if let Some(mut encoding) = self.encoding.clone().or_else(|| self.getter.get_encoding()) {
+            encoding.lod_opacity = self.getter.has_lod_tree();
+            meta.splat_encoding = Some(SetSplatEncoding::from(encoding));
+        }
+        meta
+    }
+
+    fn compute_lod_depths(&mut self, num_splats: usize) -> Vec<u32> {
+        let mut child_count = vec![0u16; num_splats];
+        let mut child_start = vec![0usize; num_splats];
+        let mut base = 0;
+        while base < num_splats {
+            let count = (num_splats - base).min(65536);
+            self.getter.get_child_count(base, count, &mut child_count[base..base + count]);
+            self.getter.get_child_start(base, count, &mut child_start[base..base + count]);
+            base += count;
+        }
+
+        let mut depths = vec![0u32; num_splats];
+        let mut queue = std::collections::VecDeque::from([0usize]);
+        while let Some(index) = queue.pop_front() {
+            let start = child_start[index];
+            let end = (start + child_count[index] as usize).min(num_splats);
+            for child in start..end {
+                depths[child] = depths[index] + 1;
+                queue.push_back(child);
+            }
+        }
+        depths
+    }
+
+    fn compute_chunk_bounds(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>, range: &mut RadChunkRange) {
+        if buffer.len() < count * 10 {
+            buffer.resize(count * 10, 0.0);
+        }
+        let (centers, rest) = buffer.split_at_mut(count * 3);
+        let (scales, rest) = rest.split_at_mut(count * 3);
+        let quats = &mut rest[..count * 4];
+        self.getter.get_center(base, count, centers);
+        self.getter.get_scale(base, count, scales);
+        self.getter.get_quat(base, count, quats);
+        let mut aabb = Aabb::empty();
+        for ((center, scale), quat) in centers.chunks_exact(3).zip(scales.chunks_exact(3)).zip(quats.chunks_exact(4)) {
+            let splat = Gsplat::new(
+                Vec3A::from_slice(center), 1.0, Vec3A::ZERO, Vec3A::from_slice(scale),
+                Quat::from_slice(quat).normalize(),
+            );
+            let splat_aabb = Aabb::from_splat(&&splat, RAD_BOUNDS_STD_DEVS);
+            if splat_aabb.min.is_finite() && splat_aabb.max.is_finite() {
+                aabb = aabb.extend(&splat_aabb);
+            }
+        }
+
+        let mut opacity = vec![0.0; count];
+        self.getter.get_opacity(base, count, &mut opacity);
+        let (mut min_size, mut max_size) = (f32::INFINITY, f32::NEG_INFINITY);
+        for (scale, &opacity) in scales.chunks_exact(3).zip(opacity.iter()) {
+            let size = feature_size(scale, opacity);
+            if size.is_finite() {
+                min_size = min_size.min(size);
+                max_size = max_size.max(size);
+            }
+        }
+
+        if !aabb.is_empty() {
+            range.bounds_min = Some(aabb.min.to_array());
+            range.bounds_max = Some(aabb.max.to_array());
+        }
+        if min_size <= max_size {
+            range.min_feature_size = Some(min_size);
+            range.max_feature_size = Some(max_size);
+        }
+    }
+
+    fn compress_property(&self, mut meta: RadChunkProperty, bytes: &[u8]) -> (RadChunkProperty, Vec<u8>) {
+        // Most encodings store one plane per dimension, so filters and the range
+        // coder operate on the bytes of a single element (or interleaved record)
+        let stride = match meta.encoding {
+            RadChunkPropertyEncoding::F32 | RadChunkPropertyEncoding::U32 => 4,
+            RadChunkPropertyEncoding::F16 | RadChunkPropertyEncoding::LnF16 | RadChunkPropertyEncoding::U16 => 2,
+            RadChunkPropertyEncoding::Oct88R8 => 3,
+            _ => 1,
+        };
+        let filters: &[Option<RadChunkPropertyFilter>] = match self.filter {
+            RadFilter::None => &[None],
+            RadFilter::Shuffle => &[Some(RadChunkPropertyFilter::Shuffle)],
+            RadFilter::BitShuffle => &[Some(RadChunkPropertyFilter::BitShuffle)],
+            RadFilter::Auto => &[None, Some(RadChunkPropertyFilter::Shuffle), Some(RadChunkPropertyFilter::BitShuffle)],
+        };
+        let quantized = matches!(meta.encoding,
+            RadChunkPropertyEncoding::R8 | RadChunkPropertyEncoding::R8Delta |
+            RadChunkPropertyEncoding::S8 | RadChunkPropertyEncoding::S8Delta |
+            RadChunkPropertyEncoding::Ln0R8 | RadChunkPropertyEncoding::Oct88R8 |
+            RadChunkPropertyEncoding::U8 | RadChunkPropertyEncoding::U16
+        );
+        let codecs: &[Option<RadChunkPropertyCompression>] = match self.compression {
+            RadCompression::None => &[None],
+            // Range coding is only used for quantized properties, which are lossless to model bytewise
+            RadCompression::Gz | RadCompression::Arith if !quantized => &[Some(RadChunkPropertyCompression::Gz)],
+            RadCompression::Gz => &[Some(RadChunkPropertyCompression::Gz)],
+            RadCompression::Lz4 => &[Some(RadChunkPropertyCompression::Lz4)],
+            RadCompression::Arith => &[Some(RadChunkPropertyCompression::Arith)],
+            RadCompression::Auto if !quantized => &[None, Some(RadChunkPropertyCompression::Gz), Some(RadChunkPropertyCompression::Lz4)],
+            RadCompression::Auto => &[None, Some(RadChunkPropertyCompression::Gz), Some(RadChunkPropertyCompression::Lz4), Some(RadChunkPropertyCompression::Arith)],
+        };
+
+        let mut best: Option<(Option<RadChunkPropertyFilter>, Option<RadChunkPropertyCompression>, Vec<u8>)> = None;
+        let mut consider = |filter, codec, output: Vec<u8>| {
+            if best.as_ref().is_none_or(|(_, _, best)| output.len() < best.len()) {
+                best = Some((filter, codec, output));
+            }
+        };
+        if codecs.contains(&Some(RadChunkPropertyCompression::Arith)) {
+            // The range coder models byte lanes itself, so it never needs a filter
+            consider(None, Some(RadChunkPropertyCompression::Arith), arith_compress(bytes, stride));
+        }
+        for &filter in filters {
+            if filter == Some(RadChunkPropertyFilter::Shuffle) && stride == 1 && filters.len() > 1 {
+                // Shuffle with a single-byte stride is the identity
+                continue;
+            }
+            let filtered = match filter {
+                None => bytes.to_vec(),
+                Some(RadChunkPropertyFilter::Shuffle) => shuffle_bytes(bytes, stride),
+                Some(RadChunkPropertyFilter::BitShuffle) => bitshuffle_bytes(bytes, stride),
+            };
+            for &codec in codecs {
+                let output = match codec {
+                    None => filtered.clone(),
+                    Some(RadChunkPropertyCompression::Gz) => compress_to_vec(&filtered, GZ_LEVEL),
+                    Some(RadChunkPropertyCompression::Lz4) => lz4_compress(&filtered),
+                    Some(RadChunkPropertyCompression::Arith) => continue,
+                };
+                consider(filter, codec, output);
+            }
+        }
+
+        let (filter, compression, output) = best.unwrap();
+        meta.compression = compression;
+        meta.filter = filter;
+        meta.filter_stride = filter.map(|_| stride as u32);
+        (meta, output)
+    }
+
     fn encode_chunk_center(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>) -> (RadChunkProperty, Vec<u8>) {
         if buffer.len() < count * 3 {
             buffer.resize(count * 3, 0.0);
@@ -618,10 +935,9 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::Center,
             encoding: enc,
-            compression: Some(RadChunkPropertyCompression::Gz),
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_alpha(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>) -> (RadChunkProperty, Vec<u8>) {
@@ -640,12 +956,11 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::Alpha,
             encoding: enc,
-            compression: Some(RadChunkPropertyCompression::Gz),
             min,
             max,
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_rgb(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>, encoding: &SplatEncoding) -> (RadChunkProperty, Vec<u8>) {
@@ -664,12 +979,11 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::Rgb,
             encoding: enc,
-            compression: Some(RadChunkPropertyCompression::Gz),
             min,
             max,
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_scales(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>, encoding: &SplatEncoding) -> (RadChunkProperty, Vec<u8>) {
@@ -678,21 +992,29 @@ impl<T: SplatGetter> RadEncoder<T> {
         }
         self.getter.get_scale(base, count, &mut buffer[..count * 3]);
 
+        // Surfels only store their two in-plane scales
+        let dims = if self.getter.splat_type() == SplatType::Surfel { 2 } else { 3 };
+        if dims == 2 {
+            for i in 0..count {
+                buffer[i * 2] = buffer[i * 3];
+                buffer[i * 2 + 1] = buffer[i * 3 + 1];
+            }
+        }
+
         let (enc, bytes, min, max) = match self.scales_encoding {
-            RadScalesEncoding::F32 => (RadChunkPropertyEncoding::F32, encode_f32(&buffer, 3, count), None, None),
+            RadScalesEncoding::F32 => (RadChunkPropertyEncoding::F32, encode_f32(buffer, dims, count), None, None),
             RadScalesEncoding::Auto |
-            RadScalesEncoding::Ln0R8 => (RadChunkPropertyEncoding::Ln0R8, encode_ln_0r8(&buffer, 3, count, -30.0, encoding.ln_scale_min, encoding.ln_scale_max), Some(encoding.ln_scale_min), Some(encoding.ln_scale_max)),
-            RadScalesEncoding::LnF16 => (RadChunkPropertyEncoding::LnF16, encode_ln_f16(&buffer, 3, count), None, None),
+            RadScalesEncoding::Ln0R8 => (RadChunkPropertyEncoding::Ln0R8, encode_ln_0r8(buffer, dims, count, -30.0, encoding.ln_scale_min, encoding.ln_scale_max), Some(encoding.ln_scale_min), Some(encoding.ln_scale_max)),
+            RadScalesEncoding::LnF16 => (RadChunkPropertyEncoding::LnF16, encode_ln_f16(buffer, dims, count), None, None),
         };
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::Scales,
             encoding: enc,
-            compression: Some(RadChunkPropertyCompression::Gz),
             min,
             max,
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_orientation(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>) -> (RadChunkProperty, Vec<u8>) {
@@ -706,10 +1028,9 @@ impl<T: SplatGetter> RadEncoder<T> {
             let meta = RadChunkProperty {
                 property: RadChunkPropertyName::Orientation,
                 encoding: RadChunkPropertyEncoding::Oct88R8,
-                compression: Some(RadChunkPropertyCompression::Gz),
                 ..Default::default()
             };
-            (meta, compress_to_vec(&bytes, GZ_LEVEL))
+            self.compress_property(meta, &bytes)
         } else {
             for i in 0..count {
                 for d in 0..3 {
@@ -724,10 +1045,9 @@ impl<T: SplatGetter> RadEncoder<T> {
             let meta = RadChunkProperty {
                 property: RadChunkPropertyName::Orientation,
                 encoding: enc,
-                compression: Some(RadChunkPropertyCompression::Gz),
                 ..Default::default()
             };
-            (meta, compress_to_vec(&bytes, GZ_LEVEL))
+            self.compress_property(meta, &bytes)
         }
     }
 
@@ -780,12 +1100,11 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property,
             encoding,
-            compression: Some(RadChunkPropertyCompression::Gz),
             min,
             max,
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_sh_label(&mut self, base: usize, count: usize, buffer: &mut Vec<usize>) -> (RadChunkProperty, Vec<u8>) {
@@ -808,10 +1127,9 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::ShLabel,
             encoding,
-            compression: Some(RadChunkPropertyCompression::Gz),
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_child_count(&mut self, base: usize, count: usize, buffer: &mut Vec<u16>) -> (RadChunkProperty, Vec<u8>) {
@@ -824,10 +1142,9 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::ChildCount,
             encoding: RadChunkPropertyEncoding::U16,
-            compression: Some(RadChunkPropertyCompression::Gz),
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
     }
 
     fn encode_chunk_child_start(&mut self, base: usize, count: usize, buffer: &mut Vec<usize>) -> (RadChunkProperty, Vec<u8>) {
@@ -840,14 +1157,74 @@ impl<T: SplatGetter> RadEncoder<T> {
         let meta = RadChunkProperty {
             property: RadChunkPropertyName::ChildStart,
             encoding: RadChunkPropertyEncoding::U32,
-            compression: Some(RadChunkPropertyCompression::Gz),
             ..Default::default()
         };
-        (meta, compress_to_vec(&bytes, GZ_LEVEL))
+        self.compress_property(meta, &bytes)
+    }
+
+    fn encode_chunk_extra(&mut self, base: usize, count: usize, buffer: &mut Vec<f64>, info: &SplatExtraInfo) -> (RadChunkProperty, Vec<u8>) {
+        let dims = info.dims;
+        if buffer.len() < count * dims {
+            buffer.resize(count * dims, 0.0);
+        }
+        self.getter.get_extra(base, count, &info.name, &mut buffer[..count * dims]);
+
+        let values = &buffer[..count * dims];
+        let floats = || values.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+        let (encoding, bytes) = match info.encoding {
+            SplatExtraEncoding::F32 => (RadChunkPropertyEncoding::F32, encode_f32(&floats(), dims, count)),
+            SplatExtraEncoding::F16 => (RadChunkPropertyEncoding::F16, encode_f16(&floats(), dims, count)),
+            SplatExtraEncoding::U8 => (RadChunkPropertyEncoding::U8, encode_u8(&floats(), dims, count)),
+            SplatExtraEncoding::U16 => {
+                let values: Vec<u16> = values.iter().map(|&v| v.round().clamp(0.0, u16::MAX as f64) as u16).collect();
+                (RadChunkPropertyEncoding::U16, encode_u16(&values, dims, count))
+            },
+            SplatExtraEncoding::U32 => {
+                let values: Vec<u32> = values.iter().map(|&v| v.round().clamp(0.0, u32::MAX as f64) as u32).collect();
+                (RadChunkPropertyEncoding::U32, encode_u32(&values, dims, count))
+            },
+        };
+        let meta = RadChunkProperty {
+            property: RadChunkPropertyName::Extra,
+            name: Some(info.name.clone()),
+            encoding,
+            ..Default::default()
+        };
+        self.compress_property(meta, &bytes)
     }
 
+    fn encode_chunk_normal(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>) -> (RadChunkProperty, Vec<u8>) {
+        if buffer.len() < count * 3 {
+            buffer.resize(count * 3, 0.0);
+        }
+        self.getter.get_normal(base, count, &mut buffer[..count * 3]);
+
+        let meta = RadChunkProperty {
+            property: RadChunkPropertyName::Normal,
+            encoding: RadChunkPropertyEncoding::F16,
+            ..Default::default()
+        };
+        self.compress_property(meta, &encode_f16(buffer, 3, count))
+    }
+
+    fn encode_chunk_distortion(&mut self, base: usize, count: usize, buffer: &mut Vec<f32>) -> (RadChunkProperty, Vec<u8>) {
+        if buffer.len() < count {
+            buffer.resize(count, 0.0);
+        }
+        self.getter.get_distortion(base, count, &mut buffer[..count]);
+
+        let meta = RadChunkProperty {
+            property: RadChunkPropertyName::Distortion,
+            encoding: RadChunkPropertyEncoding::F16,
+            ..Default::default()
+        };
+        self.compress_property(meta, &encode_f16(buffer, 1, count))
+    }
+
+    // base indexes the getter, chunk_base is the chunk's first splat in the file
+    #[allow(clippy::too_many_arguments)]
     fn encode_chunk(
-        &mut self, base: usize, count: usize, encoding: &SplatEncoding,
+        &mut self, base: usize, chunk_base: usize, count: usize, encoding: &SplatEncoding,
         buffer: &mut Vec<f32>, buffer_u16: &mut Vec<u16>, buffer_usize: &mut Vec<usize>,
     ) -> anyhow::Result<Vec<u8>> {
         let max_sh = self.getter.max_sh_degree().min(self.max_sh);
@@ -862,7 +1239,7 @@ impl<T: SplatGetter> RadEncoder<T> {
 
         let num_clusters = self.sh_clusters.as_ref().map(|c| c.num_clusters);
         if let Some(num_clusters) = num_clusters {
-            if base == 0 {
+            if chunk_base == 0 {
                 if max_sh >= 1 {
                     props.push(self.encode_chunk_sh(0, num_clusters, buffer, encoding, RadChunkPropertyName::Sh1Code));
                 }
@@ -874,7 +1251,7 @@ impl<T: SplatGetter> RadEncoder<T> {
                 }
             }
             if max_sh >= 1 {
-                props.push(self.encode_chunk_sh_label(base, count, buffer_usize));
+                props.push(self.encode_chunk_sh_label(chunk_base, count, buffer_usize));
             }
         } else {
             if max_sh >= 1 {
@@ -893,22 +1270,41 @@ impl<T: SplatGetter> RadEncoder<T> {
             props.push(self.encode_chunk_child_start(base, count, buffer_usize));
         }
 
+        let splat_type = self.getter.splat_type();
+        if splat_type == SplatType::Surfel {
+            if self.getter.has_normals() {
+                props.push(self.encode_chunk_normal(base, count, buffer));
+            }
+            if self.getter.has_distortion() {
+                props.push(self.encode_chunk_distortion(base, count, buffer));
+            }
+        }
+
+        let extras = self.getter.get_extras();
+        let mut buffer_f64 = Vec::new();
+        for info in extras.iter() {
+            props.push(self.encode_chunk_extra(base, count, &mut buffer_f64, info));
+        }
+
         let mut offset = 0u64;
         for (prop, data) in props.iter_mut() {
             prop.offset = offset;
             prop.bytes = data.len() as u64;
+            prop.crc32 = if self.checksums { Some(crc32(data)) } else { None };
             offset += roundup8(data.len()) as u64;
         }
         let payload_bytes = offset;
 
         let mut meta = RadChunkMeta {
             version: 1,
-            base: base as u64,
+            base: chunk_base as u64,
             count: count as u64,
             payload_bytes,
             max_sh: Some(self.getter.max_sh_degree().min(self.max_sh)),
             lod_tree: if self.getter.has_lod_tree() { Some(true) } else { None },
             splat_encoding: None,
+            ty: if splat_type == SplatType::Gsplat { None } else { Some(RadType::from(splat_type)) },
+            extras: if extras.is_empty() { None } else { Some(extras) },
             properties: props.iter().map(|(prop, _)| prop.clone()).collect::<Vec<_>>(),
         };
         if let Some(mut encoding) = self.encoding.clone().or_else(|| self.getter.get_encoding()) {
@@ -936,6 +1332,242 @@ impl<T: SplatGetter> RadEncoder<T> {
     }
 }
 
+type RadChunkWriter = Box<dyn FnMut(&str, &[u8]) -> anyhow::Result<()>>;
+
+// Writes a RAD file one chunk at a time, so the whole scene never has to be
+// held by a single SplatGetter. Chunks must be pushed in final order, each with
+// exactly chunk_size splats except the last, and LoD child_start indices
+// relative to the whole file. Space for the header is reserved before the
+// first chunk and filled in by finish(), which seeks back to it.
+pub struct RadStreamEncoder<T: SplatGetter, W: Write + Seek> {
+    encoder: RadEncoder<T>,
+    writer: W,
+    num_splats: usize,
+    chunk_size: usize,
+    chunk_prefix: String,
+    write_chunk: Option<RadChunkWriter>,
+    header_start: u64,
+    header_size: Option<usize>,
+    next_base: usize,
+    chunk_ranges: Vec<RadChunkRange>,
+    all_chunk_bytes: u64,
+    // Child ranges of already written splats that reach past the current
+    // chunk, as start -> (end, depth). Adjacent ranges of equal depth are
+    // joined, so this grows with the number of depth changes across the
+    // unwritten splats (a few per level in breadth-first or chunk_tree
+    // order) rather than with the number of pending parents.
+    pending_depths: std::collections::BTreeMap<usize, (usize, u32)>,
+    buffer: Vec<f32>,
+    buffer_u16: Vec<u16>,
+    buffer_usize: Vec<usize>,
+}
+
+impl<T: SplatGetter, W: Write + Seek> RadStreamEncoder<T, W> {
+    pub fn new(encoder: RadEncoder<T>, mut writer: W, num_splats: usize) -> anyhow::Result<Self> {
+        let header_start = writer.stream_position()?;
+        Ok(Self {
+            encoder,
+            writer,
+            num_splats,
+            chunk_size: RAD_CHUNK_SIZE,
+            chunk_prefix: String::new(),
+            write_chunk: None,
+            header_start,
+            header_size: None,
+            next_base: 0,
+            chunk_ranges: Vec::new(),
+            all_chunk_bytes: 0,
+            pending_depths: std::collections::BTreeMap::new(),
+            buffer: Vec::new(),
+            buffer_u16: Vec::new(),
+            buffer_usize: Vec::new(),
+        })
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(self.header_size.is_none(), "chunk size must be set before pushing chunks");
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    // Write each chunk through write_chunk as {chunk_prefix}{index}.radc
+    // instead of inline after the header
+    pub fn with_chunk_files<F: FnMut(&str, &[u8]) -> anyhow::Result<()> + 'static>(mut self, chunk_prefix: &str, write_chunk: F) -> Self {
+        assert!(self.header_size.is_none(), "chunk files must be set before pushing chunks");
+        self.chunk_prefix = chunk_prefix.to_string();
+        self.write_chunk = Some(Box::new(write_chunk));
+        self
+    }
+
+    pub fn num_chunks(&self) -> usize {
+        self.num_splats.div_ceil(self.chunk_size)
+    }
+
+    pub fn push_chunk(&mut self, getter: T) -> anyhow::Result<()> {
+        let chunk_base = self.next_base;
+        let count = getter.num_splats();
+        let expected = (self.num_splats - chunk_base).min(self.chunk_size);
+        if count != expected {
+            return Err(anyhow::anyhow!("Expected chunk at {} with {} splats, got {}", chunk_base, expected, count));
+        }
+        self.encoder.getter = getter;
+
+        if self.header_size.is_none() {
+            self.reserve_header()?;
+        }
+
+        let encoding = self.encoder.encoding.clone().or_else(|| self.encoder.getter.get_encoding()).unwrap_or_default();
+        let chunk = self.encoder.encode_chunk(0, chunk_base, count, &encoding, &mut self.buffer, &mut self.buffer_u16, &mut self.buffer_usize)?;
+
+        let chunk_index = self.chunk_ranges.len();
+        let mut range = RadChunkRange {
+            offset: if self.write_chunk.is_none() { self.all_chunk_bytes } else { 0 },
+            bytes: chunk.len() as u64,
+            crc32: if self.encoder.checksums { Some(crc32(&chunk)) } else { None },
+            ..Default::default()
+        };
+        if let Some(write_chunk) = self.write_chunk.as_mut() {
+            let filename = format!("{}{}.radc", self.chunk_prefix, chunk_index);
+            write_chunk(&filename, &chunk)?;
+            range.filename = Some(filename);
+        } else {
+            self.writer.write_all(&chunk)?;
+        }
+        self.encoder.compute_chunk_bounds(0, count, &mut self.buffer, &mut range);
+        if self.encoder.getter.has_lod_tree() {
+            (range.min_depth, range.max_depth) = self.chunk_depths(chunk_base, count);
+        }
+
+        self.chunk_ranges.push(range);
+        self.all_chunk_bytes += chunk.len() as u64;
+        self.next_base += count;
+        Ok(())
+    }
+
+    // Writes the header into the reserved space and returns the writer
+    // positioned after the last chunk
+    pub fn finish(mut self) -> anyhow::Result<W> {
+        if self.next_base != self.num_splats {
+            return Err(anyhow::anyhow!("Expected {} splats, got {}", self.num_splats, self.next_base));
+        }
+        if self.header_size.is_none() {
+            self.reserve_header()?;
+        }
+        let header_size = self.header_size.unwrap();
+
+        let chunk_ranges = std::mem::take(&mut self.chunk_ranges);
+        let meta = self.encoder.new_meta(self.num_splats, self.chunk_size, chunk_ranges, self.all_chunk_bytes);
+        let mut meta_bytes = serde_json::to_vec_pretty(&meta)?;
+        meta_bytes.push(b'\n');
+        if meta_bytes.len() > header_size {
+            return Err(anyhow::anyhow!("RAD header of {} bytes exceeds reserved {}", meta_bytes.len(), header_size));
+        }
+        // Trailing whitespace is ignored when parsing the meta JSON
+        meta_bytes.resize(header_size, b' ');
+
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.header_start))?;
+        self.writer.write_all(&RAD_MAGIC.to_le_bytes())?;
+        self.writer.write_all(&(header_size as u32).to_le_bytes())?;
+        self.writer.write_all(&meta_bytes)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn reserve_header(&mut self) -> anyhow::Result<()> {
+        // Size the header for the widest value every chunk range could hold,
+        // plus room for the longest f32 formatting of the 8 float fields
+        let num_chunks = self.num_chunks();
+        let widest = RadChunkRange {
+            offset: u64::MAX,
+            bytes: u64::MAX,
+            base: None,
+            count: None,
+            filename: self.write_chunk.as_ref().map(|_| format!("{}{}.radc", self.chunk_prefix, num_chunks)),
+            bounds_min: Some([f32::MIN; 3]),
+            bounds_max: Some([f32::MIN; 3]),
+            min_feature_size: Some(f32::MIN),
+            max_feature_size: Some(f32::MIN),
+            min_depth: Some(u32::MAX),
+            max_depth: Some(u32::MAX),
+            crc32: Some(u32::MAX),
+        };
+        let meta = self.encoder.new_meta(self.num_splats, self.chunk_size, vec![widest; num_chunks], u64::MAX);
+        let meta_size = serde_json::to_vec_pretty(&meta)?.len() + 1;
+        let header_size = roundup8(meta_size + 16 * num_chunks);
+
+        self.writer.write_all(&RAD_MAGIC.to_le_bytes())?;
+        self.writer.write_all(&(header_size as u32).to_le_bytes())?;
+        self.writer.write_all(&vec![b' '; header_size])?;
+        self.header_size = Some(header_size);
+        Ok(())
+    }
+
+    // Depths follow from the child ranges of earlier chunks, so only ranges
+    // that haven't been fully written yet are kept
+    fn chunk_depths(&mut self, chunk_base: usize, count: usize) -> (Option<u32>, Option<u32>) {
+        let getter = &mut self.encoder.getter;
+        self.buffer_u16.resize(count, 0);
+        self.buffer_usize.resize(count, 0);
+        getter.get_child_count(0, count, &mut self.buffer_u16[..count]);
+        getter.get_child_start(0, count, &mut self.buffer_usize[..count]);
+
+        let mut min_depth = None;
+        let mut max_depth = None;
+        let mut found: Option<(usize, usize, u32)> = None;
+        for i in 0..count {
+            let index = chunk_base + i;
+            let depth = if index == 0 {
+                Some(0)
+            } else {
+                if !found.is_some_and(|(start, end, _)| index >= start && index < end) {
+                    found = self.pending_depths.range(..=index).next_back()
+                        .filter(|(_, &(end, _))| index < end)
+                        .map(|(&start, &(end, depth))| (start, end, depth));
+                }
+                found.map(|(_, _, depth)| depth)
+            };
+            let Some(depth) = depth else { continue };
+            min_depth = Some(min_depth.map_or(depth, |d: u32| d.min(depth)));
+            max_depth = Some(max_depth.map_or(depth, |d: u32| d.max(depth)));
+
+            let child_count = self.buffer_u16[i] as usize;
+            if child_count > 0 {
+                let start = self.buffer_usize[i];
+                self.insert_pending(start, start + child_count, depth + 1);
+            }
+        }
+
+        let chunk_end = chunk_base + count;
+        self.pending_depths.retain(|_, &mut (end, _)| end > chunk_end);
+        (min_depth, max_depth)
+    }
+
+    fn insert_pending(&mut self, mut start: usize, mut end: usize, depth: u32) {
+        let before = self.pending_depths.range(..start).next_back().map(|(&start, &range)| (start, range));
+        if let Some((before_start, (before_end, before_depth))) = before {
+            if before_end == start && before_depth == depth {
+                self.pending_depths.remove(&before_start);
+                start = before_start;
+            }
+        }
+        if let Some(&(after_end, after_depth)) = self.pending_depths.get(&end) {
+            if after_depth == depth {
+                self.pending_depths.remove(&end);
+                end = after_end;
+            }
+        }
+        self.pending_depths.insert(start, (end, depth));
+    }
+}
+
+// Matches Tsplat::feature_size, with opacity > 1 extending LoD splats
+fn feature_size(scale: &[f32], opacity: f32) -> f32 {
+    let lod_opacity = if opacity > 1.0 { (1.0 + std::f32::consts::E * opacity.ln()).sqrt() } else { 1.0 };
+    2.0 * scale[0].max(scale[1]).max(scale[2]) * lod_opacity
+}
+
 fn roundup8(size: usize) -> usize {
     (size + 7) & !7
 }
@@ -1242,6 +1874,30 @@ fn decode_quat_oct88r8(data: &[u8], count: usize) -> Vec<f32> {
     result
 }
 
+fn encode_u8(data: &[f32], dims: usize, count: usize) -> Vec<u8> {
+    let mut result = Vec::with_capacity(dims * count);
+    for d in 0..dims {
+        let mut index = d;
+        for _ in 0..count {
+            result.push(data[index].round().clamp(0.0, 255.0) as u8);
+            index += dims;
+        }
+    }
+    result
+}
+
+fn decode_u8(data: &[u8], dims: usize, count: usize) -> Vec<f32> {
+    let mut result = Vec::with_capacity(dims * count);
+    for i in 0..count {
+        let mut index = i;
+        for _ in 0..dims {
+            result.push(data[index] as f32);
+            index += count;
+        }
+    }
+    result
+}
+
 fn encode_u16(data: &[u16], dims: usize, count: usize) -> Vec<u8> {
     let mut result = Vec::with_capacity(2 * dims * count);
     for d in 0..dims {
@@ -1352,6 +2008,387 @@ fn decode_u16_as_usize(data: &[u8], dims: usize, count: usize) -> Vec<usize> {
     result
 }
 
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+// Incremental CRC32 (IEEE 802.3, as used by zip and gzip)
+#[derive(Clone, Copy, Debug)]
+pub struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self(!0)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 = CRC32_TABLE[((self.0 ^ byte as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+// Validates the structure and any checksums of one encoded chunk,
+// returning the number of checksums verified
+fn verify_chunk_bytes(index: usize, expected: Option<u32>, bytes: &[u8]) -> anyhow::Result<usize> {
+    let mut verified = 0;
+    if let Some(expected) = expected {
+        let actual = crc32(bytes);
+        if actual != expected {
+            return Err(anyhow::anyhow!("RAD chunk {} checksum mismatch: expected {:08x}, got {:08x}", index, expected, actual));
+        }
+        verified += 1;
+    }
+
+    if bytes.len() < 8 || u32::from_le_bytes(bytes[0..4].try_into().unwrap()) != RAD_CHUNK_MAGIC {
+        return Err(anyhow::anyhow!("RAD chunk {} has invalid magic", index));
+    }
+    let length = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let payload_start = 8 + roundup8(length) + 8;
+    if bytes.len() < payload_start {
+        return Err(anyhow::anyhow!("RAD chunk {} header is truncated", index));
+    }
+    let meta: RadChunkMeta = serde_json::from_slice(&bytes[8..8 + length])
+        .map_err(|e| anyhow::anyhow!("RAD chunk {} has invalid header: {}", index, e))?;
+    let payload_bytes = u64::from_le_bytes(bytes[payload_start - 8..payload_start].try_into().unwrap()) as usize;
+    let payload = bytes.get(payload_start..payload_start + payload_bytes)
+        .ok_or_else(|| anyhow::anyhow!("RAD chunk {} is truncated: expected {} payload bytes, got {}", index, payload_bytes, bytes.len() - payload_start))?;
+
+    for prop in meta.properties.iter() {
+        let start = prop.offset as usize;
+        let stored = payload.get(start..start + prop.bytes as usize)
+            .ok_or_else(|| anyhow::anyhow!("RAD chunk {} property {:?} extends past the payload", index, prop.property))?;
+        if let Some(expected) = prop.crc32 {
+            let actual = crc32(stored);
+            if actual != expected {
+                return Err(anyhow::anyhow!("RAD chunk {} property {:?} checksum mismatch: expected {:08x}, got {:08x}", index, prop.property, expected, actual));
+            }
+            verified += 1;
+        }
+    }
+    Ok(verified)
+}
+
+fn decode_property_bytes<'a>(prop: &RadChunkProperty, data: &'a [u8], expected: usize) -> anyhow::Result<Cow<'a, [u8]>> {
+    let data = match prop.compression {
+        None => Cow::Borrowed(data),
+        Some(RadChunkPropertyCompression::Gz) => Cow::Owned(decompress_to_vec(data).map_err(|_e| anyhow::anyhow!("Failed to decompress gz data"))?),
+        Some(RadChunkPropertyCompression::Lz4) => Cow::Owned(lz4_decompress(data, expected)?),
+        Some(RadChunkPropertyCompression::Arith) => Cow::Owned(arith_decompress(data, expected)?),
+    };
+    if data.len() != expected {
+        return Err(anyhow::anyhow!("Property {:?} has {} bytes, expected {}", prop.property, data.len(), expected));
+    }
+    let stride = prop.filter_stride.unwrap_or(1).max(1) as usize;
+    Ok(match prop.filter {
+        None => data,
+        Some(RadChunkPropertyFilter::Shuffle) => Cow::Owned(unshuffle_bytes(&data, stride)),
+        Some(RadChunkPropertyFilter::BitShuffle) => Cow::Owned(unbitshuffle_bytes(&data, stride)),
+    })
+}
+
+// Transpose records of `stride` bytes so byte k of every record is contiguous.
+// Trailing bytes that don't fill a record are passed through unchanged.
+fn shuffle_bytes(data: &[u8], stride: usize) -> Vec<u8> {
+    let rows = data.len() / stride;
+    let mut output = Vec::with_capacity(data.len());
+    for b in 0..stride {
+        output.extend((0..rows).map(|r| data[r * stride + b]));
+    }
+    output.extend_from_slice(&data[rows * stride..]);
+    output
+}
+
+fn unshuffle_bytes(data: &[u8], stride: usize) -> Vec<u8> {
+    let rows = data.len() / stride;
+    let mut output = vec![0u8; data.len()];
+    for b in 0..stride {
+        for r in 0..rows {
+            output[r * stride + b] = data[b * rows + r];
+        }
+    }
+    output[rows * stride..].copy_from_slice(&data[rows * stride..]);
+    output
+}
+
+// Byte shuffle followed by splitting each byte lane into 8 bit planes.
+// Lane lengths are rounded down to a multiple of 8 for the planes, with any
+// remaining lane bytes stored as-is, so the output is the same size as the input.
+fn bitshuffle_bytes(data: &[u8], stride: usize) -> Vec<u8> {
+    let shuffled = shuffle_bytes(data, stride);
+    let rows = data.len() / stride;
+    let groups = rows / 8;
+    let mut output = Vec::with_capacity(data.len());
+    for lane in shuffled[..rows * stride].chunks(rows.max(1)) {
+        for bit in 0..8 {
+            for group in 0..groups {
+                let mut byte = 0u8;
+                for j in 0..8 {
+                    byte |= ((lane[group * 8 + j] >> bit) & 1) << j;
+                }
+                output.push(byte);
+            }
+        }
+        output.extend_from_slice(&lane[groups * 8..]);
+    }
+    output.extend_from_slice(&shuffled[rows * stride..]);
+    output
+}
+
+fn unbitshuffle_bytes(data: &[u8], stride: usize) -> Vec<u8> {
+    let rows = data.len() / stride;
+    let groups = rows / 8;
+    let mut shuffled = vec![0u8; data.len()];
+    for (lane_index, lane) in data[..rows * stride].chunks(rows.max(1)).enumerate() {
+        let output = &mut shuffled[lane_index * rows..(lane_index + 1) * rows];
+        for bit in 0..8 {
+            for group in 0..groups {
+                let byte = lane[bit * groups + group];
+                for j in 0..8 {
+                    output[group * 8 + j] |= ((byte >> j) & 1) << bit;
+                }
+            }
+        }
+        output[groups * 8..].copy_from_slice(&lane[groups * 8..]);
+    }
+    shuffled[rows * stride..].copy_from_slice(&data[rows * stride..]);
+    unshuffle_bytes(&shuffled, stride)
+}
+
+// Raw LZ4 block format (no frame header), greedy single-probe matcher.
+fn lz4_compress(data: &[u8]) -> Vec<u8> {
+    const MIN_MATCH: usize = 4;
+    const LAST_LITERALS: usize = 5;
+    const MATCH_FIND_LIMIT: usize = 12;
+    const HASH_BITS: u32 = 16;
+
+    fn push_length(output: &mut Vec<u8>, mut length: usize) {
+        while length >= 255 {
+            output.push(255);
+            length -= 255;
+        }
+        output.push(length as u8);
+    }
+
+    fn push_sequence(output: &mut Vec<u8>, literals: &[u8], match_info: Option<(usize, usize)>) {
+        let lit_len = literals.len();
+        let match_len = match_info.map_or(0, |(_, len)| len - MIN_MATCH);
+        output.push(((lit_len.min(15) as u8) << 4) | match_len.min(15) as u8);
+        if lit_len >= 15 {
+            push_length(output, lit_len - 15);
+        }
+        output.extend_from_slice(literals);
+        if let Some((offset, _)) = match_info {
+            output.extend_from_slice(&(offset as u16).to_le_bytes());
+            if match_len >= 15 {
+                push_length(output, match_len - 15);
+            }
+        }
+    }
+
+    let read_u32 = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+
+    let mut output = Vec::with_capacity(data.len() / 2 + 16);
+    let mut anchor = 0;
+    if data.len() > MATCH_FIND_LIMIT {
+        let mut table = vec![usize::MAX; 1 << HASH_BITS];
+        let match_limit = data.len() - MATCH_FIND_LIMIT;
+        let end_limit = data.len() - LAST_LITERALS;
+        let mut i = 0;
+        while i < match_limit {
+            let sequence = read_u32(i);
+            let hash = (sequence.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+            let candidate = table[hash];
+            table[hash] = i;
+            if candidate != usize::MAX && i - candidate <= 0xFFFF && read_u32(candidate) == sequence {
+                let mut length = MIN_MATCH;
+                while i + length < end_limit && data[candidate + length] == data[i + length] {
+                    length += 1;
+                }
+                push_sequence(&mut output, &data[anchor..i], Some((i - candidate, length)));
+                i += length;
+                anchor = i;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    push_sequence(&mut output, &data[anchor..], None);
+    output
+}
+
+// Output is bounded by the decoded size implied by the chunk
+fn lz4_decompress(data: &[u8], expected: usize) -> anyhow::Result<Vec<u8>> {
+    fn read_length(data: &[u8], i: &mut usize, mut length: usize) -> anyhow::Result<usize> {
+        loop {
+            let byte = *data.get(*i).ok_or_else(|| anyhow::anyhow!("Truncated lz4 data"))?;
+            *i += 1;
+            length = length.checked_add(byte as usize).ok_or_else(|| anyhow::anyhow!("Invalid lz4 length"))?;
+            if byte != 255 {
+                return Ok(length);
+            }
+        }
+    }
+
+    let overflow = || anyhow::anyhow!("lz4 data expands past expected {} bytes", expected);
+    let mut output = Vec::with_capacity(expected);
+    let mut i = 0;
+    while i < data.len() {
+        let token = data[i];
+        i += 1;
+        let mut lit_len = (token >> 4) as usize;
+        if lit_len == 15 {
+            lit_len = read_length(data, &mut i, lit_len)?;
+        }
+        if lit_len > expected - output.len() {
+            return Err(overflow());
+        }
+        let literals = data.get(i..i + lit_len).ok_or_else(|| anyhow::anyhow!("Truncated lz4 literals"))?;
+        output.extend_from_slice(literals);
+        i += lit_len;
+        if i >= data.len() {
+            break;
+        }
+
+        let offset = data.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).ok_or_else(|| anyhow::anyhow!("Truncated lz4 offset"))?;
+        i += 2;
+        let mut match_len = (token & 15) as usize;
+        if match_len == 15 {
+            match_len = read_length(data, &mut i, match_len)?;
+        }
+        match_len += 4;
+        if match_len > expected - output.len() {
+            return Err(overflow());
+        }
+        if offset == 0 || offset > output.len() {
+            return Err(anyhow::anyhow!("Invalid lz4 match offset {}", offset));
+        }
+        let start = output.len() - offset;
+        for j in 0..match_len {
+            output.push(output[start + j]);
+        }
+    }
+    Ok(output)
+}
+
+// Adaptive range coding of quantized bytes, modelled per byte lane of each
+// stride-byte record. Each lane is optionally predicted from the same lane of
+// the previous record (the previous splat in chunk order), and the model
+// context is selected by the magnitude of that lane's previous residual.
+// Stream layout: u32 length, u16 stride, one predict flag per lane, coded bytes.
+const ARITH_CONTEXTS: usize = 3;
+
+fn arith_context(previous: u8) -> usize {
+    match (previous as i8).unsigned_abs() {
+        0 => 0,
+        1..=3 => 1,
+        _ => 2,
+    }
+}
+
+fn arith_compress(data: &[u8], stride: usize) -> Vec<u8> {
+    let stride = stride.clamp(1, u16::MAX as usize);
+
+    // Choose per lane whether delta prediction lowers the order-0 entropy
+    let entropy = |histogram: &[u32; 256]| -> f64 {
+        let total: u32 = histogram.iter().sum();
+        histogram.iter().filter(|&&c| c > 0).map(|&c| {
+            let c = c as f64;
+            -c * (c / total as f64).log2()
+        }).sum()
+    };
+    let predict: Vec<bool> = (0..stride).map(|lane| {
+        let mut raw = [0u32; 256];
+        let mut delta = [0u32; 256];
+        let mut previous = 0u8;
+        for &byte in data.iter().skip(lane).step_by(stride) {
+            raw[byte as usize] += 1;
+            delta[byte.wrapping_sub(previous) as usize] += 1;
+            previous = byte;
+        }
+        entropy(&delta) < entropy(&raw)
+    }).collect();
+
+    let mut output = Vec::with_capacity(data.len() / 2 + stride + 16);
+    output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    output.extend_from_slice(&(stride as u16).to_le_bytes());
+    output.extend(predict.iter().map(|&p| p as u8));
+
+    let mut models = vec![AdaptiveModel::new(256); stride * ARITH_CONTEXTS];
+    let mut previous = vec![0u8; stride];
+    let mut residuals = vec![0u8; stride];
+    let mut encoder = RangeEncoder::new();
+    for (i, &byte) in data.iter().enumerate() {
+        let lane = i % stride;
+        let residual = if predict[lane] { byte.wrapping_sub(previous[lane]) } else { byte };
+        models[lane * ARITH_CONTEXTS + arith_context(residuals[lane])].encode(&mut encoder, residual as usize);
+        previous[lane] = byte;
+        residuals[lane] = residual;
+    }
+    output.extend(encoder.finish());
+    output
+}
+
+// Expected is the decoded size implied by the chunk, checked before the
+// length header is trusted for allocation
+fn arith_decompress(data: &[u8], expected: usize) -> anyhow::Result<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(anyhow::anyhow!("Truncated arith header"));
+    }
+    let length = u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize;
+    if length != expected {
+        return Err(anyhow::anyhow!("Invalid arith length {}, expected {}", length, expected));
+    }
+    let stride = u16::from_le_bytes(data[4..6].try_into().unwrap()) as usize;
+    if stride == 0 || data.len() < 6 + stride {
+        return Err(anyhow::anyhow!("Invalid arith stride {}", stride));
+    }
+    let predict: Vec<bool> = data[6..6 + stride].iter().map(|&p| p != 0).collect();
+
+    let mut models = vec![AdaptiveModel::new(256); stride * ARITH_CONTEXTS];
+    let mut previous = vec![0u8; stride];
+    let mut residuals = vec![0u8; stride];
+    let mut decoder = RangeDecoder::new(&data[6 + stride..]);
+    let mut output = Vec::with_capacity(length);
+    for i in 0..length {
+        let lane = i % stride;
+        let residual = models[lane * ARITH_CONTEXTS + arith_context(residuals[lane])].decode(&mut decoder) as u8;
+        let byte = if predict[lane] { residual.wrapping_add(previous[lane]) } else { residual };
+        output.push(byte);
+        previous[lane] = byte;
+        residuals[lane] = residual;
+    }
+    Ok(output)
+}
+
 fn _quantize_sh_byte(mut value: f32, bits: u8) -> u8 {
     let bucket = 1u32 << (8 - bits);
     value = ((value + (bucket as f32) / 2.0) / bucket as f32).floor() * bucket as f32;
@@ -1378,6 +2415,46 @@ pub fn decode_rad_header(bytes: &[u8]) -> anyhow::Result<Option<(RadMeta, u64)>>
     Ok(Some((meta, chunks_start)))
 }
 
+// Reads a RAD header, or the header of a standalone RADC chunk file
+pub fn probe_rad(bytes: &[u8]) -> Option<SplatFileInfo> {
+    if bytes.len() < 8 {
+        return Some(SplatFileInfo::needs(Some(SplatFileType::RAD), 8 - bytes.len()));
+    }
+    let magic = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+    let length = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    // 8 + length can overflow where usize is 32 bits
+    let end = length.checked_add(8)?;
+    if bytes.len() < end {
+        return Some(SplatFileInfo::needs(Some(SplatFileType::RAD), end - bytes.len()));
+    }
+
+    let info = SplatFileInfo::new(SplatFileType::RAD);
+    match magic {
+        RAD_MAGIC => {
+            let meta: RadMeta = serde_json::from_slice(&bytes[8..end]).ok()?;
+            let bounds = union_bounds(meta.chunks.iter().map(|chunk| chunk.bounds()));
+            Some(SplatFileInfo {
+                num_splats: Some(meta.count as usize),
+                max_sh_degree: Some(meta.max_sh.unwrap_or(0)),
+                lod_tree: Some(meta.lod_tree.unwrap_or(false)),
+                encoding: meta.splat_encoding,
+                ..info
+            }.with_bounds(bounds))
+        },
+        RAD_CHUNK_MAGIC => {
+            let meta: RadChunkMeta = serde_json::from_slice(&bytes[8..end]).ok()?;
+            Some(SplatFileInfo {
+                num_splats: Some(meta.count as usize),
+                max_sh_degree: Some(meta.max_sh.unwrap_or(0)),
+                lod_tree: Some(meta.lod_tree.unwrap_or(false)),
+                encoding: meta.splat_encoding,
+                ..info
+            })
+        },
+        _ => None,
+    }
+}
+
 pub struct RadDecoder<T: SplatReceiver> {
     splats: T,
     offset: u64,
@@ -1394,6 +2471,8 @@ pub struct RadDecoder<T: SplatReceiver> {
     prop_index: usize,
     base: usize,
     count: usize,
+    splat_type: SplatType,
+    chunk_crc: Crc32,
 }
 
 impl<T: SplatReceiver> RadDecoder<T> {
@@ -1414,6 +2493,8 @@ impl<T: SplatReceiver> RadDecoder<T> {
             prop_index: 0,
             base: 0,
             count: 0,
+            splat_type: SplatType::Gsplat,
+            chunk_crc: Crc32::new(),
         }
     }
 
@@ -1448,6 +2529,7 @@ impl<T: SplatReceiver> RadDecoder<T> {
                 if !self.skip_to_chunk_end()? {
                     return Ok(());
                 }
+                self.verify_chunk_crc()?;
                 self.chunk_meta = None;
                 self.chunk_index += 1;
             }
@@ -1507,10 +2589,6 @@ impl<T: SplatReceiver> RadDecoder<T> {
             return Err(anyhow::anyhow!("Unsupported RAD version: {}", meta.version));
         }
 
-        if meta.ty != RadType::Gsplat {
-            return Err(anyhow::anyhow!("Unsupported RAD type: {:?}", meta.ty));
-        }
-
         let num_splats = meta.count as usize;
         let max_sh_degree = meta.max_sh.unwrap_or(0);
         let lod_tree = meta.lod_tree.unwrap_or(false);
@@ -1526,6 +2604,15 @@ impl<T: SplatReceiver> RadDecoder<T> {
             lod_tree,
         })?;
 
+        self.splat_type = SplatType::from(meta.ty);
+        if self.splat_type != SplatType::Gsplat {
+            self.splats.set_splat_type(self.splat_type)?;
+        }
+
+        if let Some(extras) = meta.extras.as_ref() {
+            self.splats.init_extras(extras)?;
+        }
+
         if let Some(set_splat_encoding) = meta.splat_encoding.as_ref() {
             self.splats.set_encoding(set_splat_encoding)?;
         }
@@ -1561,6 +2648,13 @@ impl<T: SplatReceiver> RadDecoder<T> {
                 max_sh_degree: chunk_meta.max_sh.unwrap_or(0),
                 lod_tree: chunk_meta.lod_tree.unwrap_or(false),
             })?;
+            self.splat_type = chunk_meta.ty.map(SplatType::from).unwrap_or_default();
+            if self.splat_type != SplatType::Gsplat {
+                self.splats.set_splat_type(self.splat_type)?;
+            }
+            if let Some(extras) = chunk_meta.extras.as_ref() {
+                self.splats.init_extras(extras)?;
+            }
         }
 
         self.prop_index = 0;
@@ -1591,8 +2685,8 @@ impl<T: SplatReceiver> RadDecoder<T> {
         let meta: RadChunkMeta = serde_json::from_slice(&self.buffer[8..8 + length])?;
         let payload_bytes = u64::from_le_bytes(self.buffer[meta_end..meta_end + 8].try_into().unwrap());
 
-        self.buffer.drain(..meta_end + 8);
-        self.offset += (meta_end + 8) as u64;
+        self.chunk_crc = Crc32::new();
+        self.consume_chunk_bytes(meta_end + 8);
         let payload_start = self.offset;
         let chunk_end = self.offset + payload_bytes;
 
@@ -1600,6 +2694,31 @@ impl<T: SplatReceiver> RadDecoder<T> {
         Ok(true)
     }
 
+    // Decoded size of a property in the current chunk
+    fn property_bytes(&self, prop: &RadChunkProperty) -> usize {
+        let dims = match prop.property {
+            RadChunkPropertyName::Center | RadChunkPropertyName::Rgb | RadChunkPropertyName::Normal => 3,
+            RadChunkPropertyName::Alpha | RadChunkPropertyName::ShLabel | RadChunkPropertyName::Distortion |
+            RadChunkPropertyName::ChildCount | RadChunkPropertyName::ChildStart => 1,
+            RadChunkPropertyName::Scales => if self.splat_type == SplatType::Surfel { 2 } else { 3 },
+            RadChunkPropertyName::Orientation => if prop.encoding == RadChunkPropertyEncoding::Oct88R8 { 1 } else { 3 },
+            RadChunkPropertyName::Sh1 | RadChunkPropertyName::Sh1Code => 9,
+            RadChunkPropertyName::Sh2 | RadChunkPropertyName::Sh2Code => 15,
+            RadChunkPropertyName::Sh3 | RadChunkPropertyName::Sh3Code => 21,
+            RadChunkPropertyName::Extra => {
+                let extras = self.chunk_meta.as_ref().and_then(|meta| meta.extras.as_ref());
+                extras.and_then(|extras| extras.iter().find(|info| Some(info.name.as_str()) == prop.name.as_deref())).map_or(0, |info| info.dims)
+            },
+        };
+        let element_bytes = match prop.encoding {
+            RadChunkPropertyEncoding::F32 | RadChunkPropertyEncoding::F32LeBytes | RadChunkPropertyEncoding::U32 => 4,
+            RadChunkPropertyEncoding::F16 | RadChunkPropertyEncoding::F16LeBytes | RadChunkPropertyEncoding::LnF16 | RadChunkPropertyEncoding::U16 => 2,
+            RadChunkPropertyEncoding::Oct88R8 => 3,
+            _ => 1,
+        };
+        self.count * dims * element_bytes
+    }
+
     fn poll_chunk_props(&mut self) -> anyhow::Result<bool> {
         let props = &self.chunk_meta.as_ref().unwrap().properties;
         loop {
@@ -1616,15 +2735,15 @@ impl<T: SplatReceiver> RadDecoder<T> {
                 return Ok(false);
             }
 
-            let data = &self.buffer[0..prop.bytes as usize];
-            let data = if let Some(compression) = prop.compression.as_ref() {
-                match compression {
-                    RadChunkPropertyCompression::Gz => &decompress_to_vec(data).map_err(|_e| anyhow::anyhow!("Failed to decompress gz data"))?,
-                    // _ => return Err(anyhow::anyhow!("Unsupported compression: {:?}", compression)),
+            let stored = &self.buffer[0..prop.bytes as usize];
+            if let Some(expected) = prop.crc32 {
+                let actual = crc32(stored);
+                if actual != expected {
+                    return Err(anyhow::anyhow!("RAD chunk {} (base {}) property {:?} checksum mismatch: expected {:08x}, got {:08x}", self.chunk_index, self.base, prop.property, expected, actual));
                 }
-            } else {
-                data
-            };
+            }
+            let data = decode_property_bytes(prop, stored, self.property_bytes(prop))?;
+            let data = &data[..];
 
             match prop.property {
                 RadChunkPropertyName::Center => {
@@ -1676,9 +2795,10 @@ impl<T: SplatReceiver> RadDecoder<T> {
                     self.splats.set_rgb(self.base, self.count, &rgbs);
                 },
                 RadChunkPropertyName::Scales => {
+                    let dims = if self.splat_type == SplatType::Surfel { 2 } else { 3 };
                     let scales = match prop.encoding {
-                        RadChunkPropertyEncoding::F32 => decode_f32(data, 3, self.count),
-                        RadChunkPropertyEncoding::LnF16 => decode_ln_f16(data, 3, self.count),
+                        RadChunkPropertyEncoding::F32 => decode_f32(data, dims, self.count),
+                        RadChunkPropertyEncoding::LnF16 => decode_ln_f16(data, dims, self.count),
                         RadChunkPropertyEncoding::Ln0R8 => {
                             let Some(min) = prop.min else {
                                 return Err(anyhow::anyhow!("Property missing min"));
@@ -1686,11 +2806,16 @@ impl<T: SplatReceiver> RadDecoder<T> {
                             let Some(max) = prop.max else {
                                 return Err(anyhow::anyhow!("Property missing max"));
                             };
-                            decode_ln_0r8(data, 3, self.count, min, max)
+                            decode_ln_0r8(data, dims, self.count, min, max)
                         },
                         _ => return Err(anyhow::anyhow!("Unsupported scales encoding: {:?}", prop.encoding)),
                     };
-                    self.splats.set_scale(self.base, self.count, &scales);
+                    if dims == 2 {
+                        let scales: Vec<f32> = scales.chunks_exact(2).flat_map(|s| [s[0], s[1], 0.0]).collect();
+                        self.splats.set_scale(self.base, self.count, &scales);
+                    } else {
+                        self.splats.set_scale(self.base, self.count, &scales);
+                    }
                 },
                 RadChunkPropertyName::Orientation => {
                     let quaternions = if prop.encoding == RadChunkPropertyEncoding::Oct88R8 {
@@ -1779,11 +2904,46 @@ impl<T: SplatReceiver> RadDecoder<T> {
                     let child_starts = decode_u32_as_usize(data, 1, self.count);
                     self.splats.set_child_start(self.base, self.count, &child_starts);
                 },
+                RadChunkPropertyName::Extra => {
+                    let Some(name) = prop.name.as_deref() else {
+                        return Err(anyhow::anyhow!("Extra property missing name"));
+                    };
+                    let extras = self.chunk_meta.as_ref().and_then(|meta| meta.extras.as_ref());
+                    let Some(info) = extras.and_then(|extras| extras.iter().find(|info| info.name == name)) else {
+                        return Err(anyhow::anyhow!("Extra property {} not declared in chunk", name));
+                    };
+                    let dims = info.dims;
+                    let values: Vec<f64> = match prop.encoding {
+                        RadChunkPropertyEncoding::F32 => decode_f32(data, dims, self.count).into_iter().map(|v| v as f64).collect(),
+                        RadChunkPropertyEncoding::F16 => decode_f16(data, dims, self.count).into_iter().map(|v| v as f64).collect(),
+                        RadChunkPropertyEncoding::U8 => decode_u8(data, dims, self.count).into_iter().map(|v| v as f64).collect(),
+                        RadChunkPropertyEncoding::U16 => decode_u16(data, dims, self.count).into_iter().map(|v| v as f64).collect(),
+                        RadChunkPropertyEncoding::U32 => decode_u32(data, dims, self.count).into_iter().map(|v| v as f64).collect(),
+                        _ => return Err(anyhow::anyhow!("Unsupported extra encoding: {:?}", prop.encoding)),
+                    };
+                    self.splats.set_extra(self.base, self.count, name, &values);
+                },
+                RadChunkPropertyName::Normal | RadChunkPropertyName::Distortion => {
+                    let dims = if prop.property == RadChunkPropertyName::Normal { 3 } else { 1 };
+                    let values = match prop.encoding {
+                        RadChunkPropertyEncoding::F32 => decode_f32(data, dims, self.count),
+                        RadChunkPropertyEncoding::F16 => decode_f16(data, dims, self.count),
+                        _ => return Err(anyhow::anyhow!("Unsupported {:?} encoding: {:?}", prop.property, prop.encoding)),
+                    };
+                    if prop.property == RadChunkPropertyName::Normal {
+                        self.splats.set_normal(self.base, self.count, &values);
+                    } else {
+                        self.splats.set_distortion(self.base, self.count, &values);
+                    }
+                },
                 // _ => return Err(anyhow::anyhow!("Unknown property type: {:?}", prop.property)),
             }
 
-            self.buffer.drain(..roundup8(prop.bytes as usize));
-            self.offset += roundup8(prop.bytes as usize) as u64;
+            // Fields borrowed directly since props borrows chunk_meta
+            let consumed = roundup8(prop.bytes as usize);
+            self.chunk_crc.update(&self.buffer[..consumed]);
+            self.buffer.drain(..consumed);
+            self.offset += consumed as u64;
             self.prop_index += 1;
         }
     }
@@ -1795,12 +2955,28 @@ impl<T: SplatReceiver> RadDecoder<T> {
 
         let remaining = self.chunk_end - self.offset;
         let available = remaining.min(self.buffer.len() as u64);
-        self.buffer.drain(..available as usize);
-        self.offset += available;
+        self.consume_chunk_bytes(available as usize);
 
         return Ok(self.offset >= self.chunk_end);
     }
 
+    fn consume_chunk_bytes(&mut self, bytes: usize) {
+        self.chunk_crc.update(&self.buffer[..bytes]);
+        self.buffer.drain(..bytes);
+        self.offset += bytes as u64;
+    }
+
+    fn verify_chunk_crc(&self) -> anyhow::Result<()> {
+        let expected = self.meta.as_ref().and_then(|meta| meta.chunks.get(self.chunk_index)).and_then(|range| range.crc32);
+        if let Some(expected) = expected {
+            let actual = self.chunk_crc.finish();
+            if actual != expected {
+                return Err(anyhow::anyhow!("RAD chunk {} checksum mismatch: expected {:08x}, got {:08x}", self.chunk_index, expected, actual));
+            }
+        }
+        Ok(())
+    }
+
     fn skip_remaining(&mut self) -> anyhow::Result<()> {
         self.offset += self.buffer.len() as u64;
         self.buffer.clear();
@@ -1824,3 +3000,142 @@ impl<T: SplatReceiver> ChunkReceiver for RadDecoder<T> {
         Ok(())
     }
 }
+
+// Reads a chunk's bytes, checking the header's length against the data
+// before allocating
+fn read_rad_range<S: Read + Seek>(reader: &mut S, index: usize, start: u64, length: u64) -> anyhow::Result<Vec<u8>> {
+    let data_len = reader.seek(SeekFrom::End(0))?;
+    if start.checked_add(length).is_none_or(|end| end > data_len) {
+        return Err(anyhow::anyhow!("RAD chunk {} extends past end of data ({} + {} > {})", index, start, length, data_len));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Random access to the chunks of a RAD file, inline or in external .radc files
+pub struct RadReader<R: Read + Seek> {
+    reader: R,
+    meta: RadMeta,
+    chunks_start: u64,
+    chunk_dir: Option<PathBuf>,
+}
+
+impl RadReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        Ok(Self::new(BufReader::new(file))?.with_chunk_dir(dir))
+    }
+}
+
+impl<R: Read + Seek> RadReader<R> {
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut bytes = vec![0u8; 8];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut bytes)?;
+        let magic = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        if magic != RAD_MAGIC {
+            return Err(anyhow::anyhow!("Invalid RAD magic: 0x{:08x}", magic));
+        }
+        // Bound the header by the data before allocating it
+        let length = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as u64;
+        let data_len = reader.seek(SeekFrom::End(0))?;
+        if 8 + length > data_len {
+            return Err(anyhow::anyhow!("RAD header extends past end of data (8 + {} > {})", length, data_len));
+        }
+        bytes.resize(8 + length as usize, 0);
+        reader.seek(SeekFrom::Start(8))?;
+        reader.read_exact(&mut bytes[8..])?;
+
+        let Some((meta, chunks_start)) = decode_rad_header(&bytes)? else {
+            return Err(anyhow::anyhow!("Incomplete RAD header"));
+        };
+        if meta.version != 1 {
+            return Err(anyhow::anyhow!("Unsupported RAD version: {}", meta.version));
+        }
+        Ok(Self { reader, meta, chunks_start, chunk_dir: None })
+    }
+
+    // Directory containing external .radc chunk files
+    pub fn with_chunk_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.chunk_dir = Some(dir.into());
+        self
+    }
+
+    pub fn meta(&self) -> &RadMeta {
+        &self.meta
+    }
+
+    pub fn num_splats(&self) -> usize {
+        self.meta.count as usize
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.meta.chunks.len()
+    }
+
+    pub fn chunk_range(&self, index: usize) -> Option<&RadChunkRange> {
+        self.meta.chunks.get(index)
+    }
+
+    pub fn read_chunk_bytes(&mut self, index: usize) -> anyhow::Result<Vec<u8>> {
+        let range = self.chunk_range(index)
+            .ok_or_else(|| anyhow::anyhow!("RAD chunk index {} out of range", index))?
+            .clone();
+        match range.filename.as_deref() {
+            Some(filename) => {
+                let Some(dir) = self.chunk_dir.as_ref() else {
+                    return Err(anyhow::anyhow!("RAD chunk {} is stored in {} but no chunk directory is set", index, filename));
+                };
+                // Chunk files must sit directly in the chunk directory
+                let mut components = Path::new(filename).components();
+                let plain = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+                if !plain || filename.contains(['/', '\\']) {
+                    return Err(anyhow::anyhow!("RAD chunk {} has invalid filename {:?}", index, filename));
+                }
+                let mut file = File::open(dir.join(filename))?;
+                read_rad_range(&mut file, index, range.offset, range.bytes)
+            },
+            None => read_rad_range(&mut self.reader, index, self.chunks_start + range.offset, range.bytes),
+        }
+    }
+
+    // Checks every chunk and property checksum without decoding any splats,
+    // returning the number of checksums that were present and verified
+    pub fn verify(&mut self) -> anyhow::Result<usize> {
+        let mut verified = 0;
+        for index in 0..self.chunk_count() {
+            verified += self.verify_chunk(index)?;
+        }
+        Ok(verified)
+    }
+
+    pub fn verify_chunk(&mut self, index: usize) -> anyhow::Result<usize> {
+        let bytes = self.read_chunk_bytes(index)
+            .map_err(|e| anyhow::anyhow!("RAD chunk {} could not be read: {}", index, e))?;
+        verify_chunk_bytes(index, self.meta.chunks[index].crc32, &bytes)
+    }
+
+    // Decodes a single chunk into receiver, rebased so its first splat is at index 0
+    pub fn read_chunk<T: SplatReceiver>(&mut self, index: usize, mut receiver: T) -> anyhow::Result<T> {
+        let bytes = self.read_chunk_bytes(index)?;
+        verify_chunk_bytes(index, self.meta.chunks[index].crc32, &bytes)?;
+        if let Some(set_splat_encoding) = self.meta.splat_encoding.as_ref() {
+            receiver.set_encoding(set_splat_encoding)?;
+        }
+        if self.meta.lod_tree.unwrap_or(false) {
+            receiver.set_encoding(&SetSplatEncoding {
+                lod_opacity: Some(true),
+                ..Default::default()
+            })?;
+        }
+
+        let mut decoder = RadDecoder::new(receiver);
+        decoder.push(&bytes)?;
+        decoder.finish()?;
+        Ok(decoder.into_splats())
+    }
+}
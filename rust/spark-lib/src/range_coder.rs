@@ -0,0 +1,142 @@
+// Carryless range coder (Subbotin) with adaptive frequency models, used for
+// entropy coding quantized RAD properties.
+
+const TOP: u32 = 1 << 24;
+const BOT: u32 = 1 << 16;
+
+// Model totals must stay <= BOT for the coder to remain exact
+const MAX_TOTAL: u32 = BOT;
+const INCREMENT: u32 = 24;
+
+pub struct RangeEncoder {
+    low: u32,
+    range: u32,
+    output: Vec<u8>,
+}
+
+impl Default for RangeEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RangeEncoder {
+    pub fn new() -> Self {
+        Self { low: 0, range: u32::MAX, output: Vec::new() }
+    }
+
+    pub fn encode(&mut self, cum: u32, freq: u32, total: u32) {
+        self.range /= total;
+        self.low = self.low.wrapping_add(cum * self.range);
+        self.range *= freq;
+        loop {
+            if (self.low ^ self.low.wrapping_add(self.range)) >= TOP {
+                if self.range >= BOT {
+                    break;
+                }
+                self.range = self.low.wrapping_neg() & (BOT - 1);
+            }
+            self.output.push((self.low >> 24) as u8);
+            self.low <<= 8;
+            self.range <<= 8;
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        for _ in 0..4 {
+            self.output.push((self.low >> 24) as u8);
+            self.low <<= 8;
+        }
+        self.output
+    }
+}
+
+pub struct RangeDecoder<'a> {
+    low: u32,
+    range: u32,
+    code: u32,
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut decoder = Self { low: 0, range: u32::MAX, code: 0, data, pos: 0 };
+        for _ in 0..4 {
+            decoder.code = (decoder.code << 8) | decoder.next_byte() as u32;
+        }
+        decoder
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        // Reading past the end only happens on corrupt input, which the
+        // caller detects through the decoded length or symbol values
+        let byte = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        byte
+    }
+
+    pub fn get_freq(&mut self, total: u32) -> u32 {
+        self.range /= total;
+        (self.code.wrapping_sub(self.low) / self.range).min(total - 1)
+    }
+
+    pub fn decode(&mut self, cum: u32, freq: u32) {
+        self.low = self.low.wrapping_add(cum * self.range);
+        self.range *= freq;
+        loop {
+            if (self.low ^ self.low.wrapping_add(self.range)) >= TOP {
+                if self.range >= BOT {
+                    break;
+                }
+                self.range = self.low.wrapping_neg() & (BOT - 1);
+            }
+            self.code = (self.code << 8) | self.next_byte() as u32;
+            self.low <<= 8;
+            self.range <<= 8;
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AdaptiveModel {
+    freqs: Vec<u32>,
+    total: u32,
+}
+
+impl AdaptiveModel {
+    pub fn new(num_symbols: usize) -> Self {
+        Self { freqs: vec![1; num_symbols], total: num_symbols as u32 }
+    }
+
+    pub fn encode(&mut self, encoder: &mut RangeEncoder, symbol: usize) {
+        let cum: u32 = self.freqs[..symbol].iter().sum();
+        encoder.encode(cum, self.freqs[symbol], self.total);
+        self.update(symbol);
+    }
+
+    pub fn decode(&mut self, decoder: &mut RangeDecoder) -> usize {
+        let target = decoder.get_freq(self.total);
+        let mut cum = 0;
+        let mut symbol = 0;
+        while cum + self.freqs[symbol] <= target {
+            cum += self.freqs[symbol];
+            symbol += 1;
+        }
+        decoder.decode(cum, self.freqs[symbol]);
+        self.update(symbol);
+        symbol
+    }
+
+    fn update(&mut self, symbol: usize) {
+        self.freqs[symbol] += INCREMENT;
+        self.total += INCREMENT;
+        if self.total > MAX_TOTAL {
+            self.total = 0;
+            for freq in self.freqs.iter_mut() {
+                *freq = freq.div_ceil(2);
+                self.total += *freq;
+            }
+        }
+    }
+}
@@ -1,16 +1,20 @@
-use std::{collections::HashMap, io::Cursor};
+use std::{collections::HashMap, io::{Cursor, Seek, Write}, path::{Path, PathBuf}};
 
 use anyhow::{anyhow, Context};
-use image::{DynamicImage, GenericImageView, ImageReader};
+use image::{codecs::webp::WebPEncoder, DynamicImage, ExtendedColorType, GenericImageView, ImageReader};
 use serde_json;
-use serde::Deserialize;
-use zip::ZipArchive;
+use serde::{Deserialize, Serialize};
+use miniz_oxide::inflate::decompress_to_vec;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
 
-use crate::decoder::{ChunkReceiver, SplatInit, SplatProps, SplatReceiver};
+use crate::{decoder::{is_sogs_meta, ChunkReceiver, SplatFileType, SplatGetter, SplatInit, SplatReceiver}, probe::SplatFileInfo, sh_clustering::{CpuFindNearestClusters, FindNearestClusters}};
 
 const PK_MAGIC: u32 = 0x04034b50;
 const SH_C0: f32 = 0.28209479177387814;
 const MAX_SPLAT_CHUNK: usize = 65536;
+const CODEBOOK_SIZE: usize = 256;
+const SHN_PALETTE_COLUMNS: usize = 64;
+const MAX_SHN_PALETTE: usize = 65536;
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
@@ -19,7 +23,7 @@ enum PcSogsRoot {
     V1(PcSogsV1),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct PcSogsV2 {
     version: u32,
     count: usize,
@@ -27,7 +31,7 @@ struct PcSogsV2 {
     scales: ScalesV2,
     quats: Quats,
     sh0: Sh0V2,
-    #[serde(rename = "shN")]
+    #[serde(rename = "shN", skip_serializing_if = "Option::is_none")]
     shn: Option<ShNV2>,
 }
 
@@ -41,11 +45,12 @@ struct PcSogsV1 {
     shn: Option<ShNV1>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Means {
     files: [String; 2],
     mins: [f32; 3],
     maxs: [f32; 3],
+    #[serde(skip_serializing_if = "Option::is_none")]
     shape: Option<[usize; 2]>,
 }
 
@@ -64,15 +69,16 @@ struct ScalesV1 {
     maxs: [f32; 3],
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ScalesV2 {
     files: [String; 1],
     codebook: Vec<f32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Quats {
     files: [String; 1],
+    #[serde(skip_serializing_if = "Option::is_none")]
     encoding: Option<String>,
 }
 
@@ -83,7 +89,7 @@ struct Sh0V1 {
     maxs: [f32; 4],
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct Sh0V2 {
     files: [String; 1],
     codebook: Vec<f32>,
@@ -97,229 +103,499 @@ struct ShNV1 {
     shape: [usize; 2],
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct ShNV2 {
     files: [String; 2],
     codebook: Vec<f32>,
     bands: u8,
 }
 
+// Fetches a file named in a loose meta.json, relative to that meta.json
+pub trait SogsFileResolver {
+    fn resolve(&mut self, name: &str) -> anyhow::Result<Vec<u8>>;
+}
+
+impl<F: FnMut(&str) -> anyhow::Result<Vec<u8>>> SogsFileResolver for F {
+    fn resolve(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        self(name)
+    }
+}
+
+pub struct SogsDirectoryResolver {
+    dir: PathBuf,
+}
+
+impl SogsDirectoryResolver {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // Resolve siblings of the file at pathname, typically a meta.json
+    pub fn from_pathname(pathname: &str) -> Self {
+        let dir = Path::new(pathname).parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        Self { dir }
+    }
+}
+
+impl SogsFileResolver for SogsDirectoryResolver {
+    fn resolve(&mut self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let path = self.dir.join(name);
+        std::fs::read(&path).with_context(|| format!("Failed to read SOGS file {}", path.display()))
+    }
+}
+
 pub struct SogsDecoder<T: SplatReceiver> {
     splats: T,
-    buffer: Vec<u8>,
+    zip: ZipStream,
+    resolver: Option<Box<dyn SogsFileResolver>>,
+    // Bytes of a bare meta.json, when not decoding a zip
+    meta: Option<Vec<u8>>,
+    prefix: String,
+    num_splats: usize,
+    initialized: bool,
+    // Completed zip entries received before meta.json
+    files: HashMap<String, Vec<u8>>,
+    images: HashMap<String, ImageData>,
+    properties: Vec<SogsProperty>,
 }
 
 impl<T: SplatReceiver> SogsDecoder<T> {
-    pub fn new(splats: T, _pathname: Option<String>) -> Self {
+    pub fn new(splats: T) -> Self {
         Self {
             splats,
-            buffer: Vec::new(),
+            zip: ZipStream::default(),
+            resolver: None,
+            meta: None,
+            prefix: String::new(),
+            num_splats: 0,
+            initialized: false,
+            files: HashMap::new(),
+            images: HashMap::new(),
+            properties: Vec::new(),
         }
     }
 
+    pub fn with_resolver(mut self, resolver: Box<dyn SogsFileResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
     pub fn into_splats(self) -> T {
         self.splats
     }
+
+    fn finish_meta(&mut self, meta: Vec<u8>) -> anyhow::Result<()> {
+        self.add_file("meta.json".to_string(), meta)?;
+        let names: Vec<String> = self.properties.iter()
+            .flat_map(|p| p.files())
+            .map(|f| f.to_string())
+            .collect();
+        for name in names {
+            let resolver = self.resolver.as_mut()
+                .ok_or_else(|| anyhow!("SOGS meta.json requires a file resolver"))?;
+            let bytes = resolver.resolve(&name)?;
+            self.add_file(name, bytes)?;
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, name: String, bytes: Vec<u8>) -> anyhow::Result<()> {
+        if !self.initialized {
+            if is_sogs_meta(&name) {
+                self.prefix = name.rsplit_once('/').map(|(p, _)| format!("{}/", p)).unwrap_or_default();
+                return self.init_meta(&bytes);
+            }
+            self.files.insert(name, bytes);
+            return Ok(());
+        }
+
+        let relative = name.strip_prefix(self.prefix.as_str()).unwrap_or(&name);
+        let needed = self.properties.iter().any(|p| p.files().contains(&relative));
+        if needed && !self.images.contains_key(relative) {
+            let image = decode_image(&bytes).with_context(|| format!("decode {}", relative))?;
+            self.images.insert(relative.to_string(), image);
+            self.emit_ready()?;
+        }
+        Ok(())
+    }
+
+    fn init_meta(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        let meta: PcSogsRoot = serde_json::from_slice(bytes)
+            .context("Failed to parse meta.json for SOGS")?;
+        let (num_splats, max_sh_degree, properties) = sogs_properties(meta)?;
+        self.splats.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
+        self.num_splats = num_splats;
+        self.properties = properties;
+        self.initialized = true;
+
+        for (name, bytes) in std::mem::take(&mut self.files) {
+            self.add_file(name, bytes)?;
+        }
+        Ok(())
+    }
+
+    fn emit_ready(&mut self) -> anyhow::Result<()> {
+        let mut index = 0;
+        while index < self.properties.len() {
+            if !self.properties[index].files().iter().all(|f| self.images.contains_key(*f)) {
+                index += 1;
+                continue;
+            }
+            let property = self.properties.swap_remove(index);
+            let images = property.files().iter()
+                .map(|f| self.images.remove(*f).unwrap())
+                .collect::<Vec<_>>();
+            for image in images.iter() {
+                if image.rgba.len() < self.num_splats * 4 {
+                    return Err(anyhow!("SOGS image too small for {} splats", self.num_splats));
+                }
+            }
+            property.emit(&mut self.splats, self.num_splats, &images)?;
+        }
+        Ok(())
+    }
 }
 
 impl<T: SplatReceiver> ChunkReceiver for SogsDecoder<T> {
     fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
-        self.buffer.extend_from_slice(bytes);
+        if self.meta.is_none() && !self.zip.started && self.zip.buffer.is_empty()
+            && bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{')
+        {
+            self.meta = Some(Vec::new());
+        }
+        if let Some(meta) = self.meta.as_mut() {
+            meta.extend_from_slice(bytes);
+            return Ok(());
+        }
+
+        self.zip.push(bytes)?;
+        while let Some((name, data)) = self.zip.next_entry()? {
+            self.add_file(name, data)?;
+        }
         Ok(())
     }
 
     fn finish(&mut self) -> anyhow::Result<()> {
-        if self.buffer.len() < 4 {
-            return Err(anyhow!("SOGS file too small"));
+        if let Some(meta) = self.meta.take() {
+            self.finish_meta(meta)?;
         }
-        let magic = u32::from_le_bytes([self.buffer[0], self.buffer[1], self.buffer[2], self.buffer[3]]);
-        if magic != PK_MAGIC {
-            return Err(anyhow!("Not a ZIP/SOGS file"));
+        if !self.zip.done && !self.zip.buffer.is_empty() {
+            return Err(anyhow!("Truncated SOGS zip"));
         }
-        decode_sogs(&self.buffer, &mut self.splats, None)?;
-        Ok(())
+        if !self.initialized {
+            return Err(anyhow!("meta.json not found in SOGS zip"));
+        }
+        if let Some(property) = self.properties.first() {
+            let missing = property.files().into_iter().find(|f| !self.images.contains_key(*f)).unwrap_or_default();
+            return Err(anyhow!("Missing file {missing} in SOGS zip"));
+        }
+        self.splats.finish()
     }
 }
 
-fn decode_sogs<T: SplatReceiver>(bytes: &[u8], splats: &mut T, _pathname: Option<&str>) -> anyhow::Result<()> {
-    let cursor = Cursor::new(bytes);
-    let mut zip = ZipArchive::new(cursor)?;
+// Incremental parser for zip local file entries
+#[derive(Default)]
+struct ZipStream {
+    buffer: Vec<u8>,
+    descriptor_scan: usize,
+    started: bool,
+    done: bool,
+}
 
-    // Find meta.json entry and its prefix
-    let mut meta_name: Option<String> = None;
-    for i in 0..zip.len() {
-        let name = zip.by_index(i)?.name().to_string();
-        if name.ends_with("meta.json") {
-            meta_name = Some(name);
-            break;
+const ZIP_CENTRAL_MAGIC: u32 = 0x02014b50;
+const ZIP_END_MAGIC: u32 = 0x06054b50;
+const ZIP64_END_MAGIC: u32 = 0x06064b50;
+const ZIP_DESCRIPTOR_MAGIC: u32 = 0x08074b50;
+
+impl ZipStream {
+    fn push(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        if !self.done {
+            self.buffer.extend_from_slice(bytes);
         }
+        Ok(())
     }
-    let meta_name = meta_name.ok_or_else(|| anyhow!("meta.json not found in SOGS zip"))?;
-    let prefix = meta_name.rsplit_once('/').map(|(p, _)| format!("{}/", p)).unwrap_or_default();
 
-    let meta_bytes = {
-        let mut meta_file = zip.by_name(&meta_name)?;
-        let mut meta_bytes = Vec::new();
-        std::io::copy(&mut meta_file, &mut meta_bytes)?;
-        meta_bytes
-    };
-    let meta: PcSogsRoot = serde_json::from_slice(&meta_bytes)
-        .context("Failed to parse meta.json for SOGS")?;
+    fn next_entry(&mut self) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+        if self.done || self.buffer.len() < 4 {
+            return Ok(None);
+        }
+        let b = &self.buffer;
+        match read_u32(b, 0) {
+            PK_MAGIC => {},
+            ZIP_CENTRAL_MAGIC | ZIP_END_MAGIC | ZIP64_END_MAGIC if self.started => {
+                // Everything after the local entries is the central directory
+                self.done = true;
+                self.buffer = Vec::new();
+                return Ok(None);
+            },
+            _ => return Err(anyhow!("Not a ZIP/SOGS file")),
+        }
+        if b.len() < 30 {
+            return Ok(None);
+        }
 
-    let mut file_cache: HashMap<String, Vec<u8>> = HashMap::new();
-    preload_all(&meta, &prefix, &mut zip, &mut file_cache)?;
+        let flags = read_u16(b, 6);
+        let method = read_u16(b, 8);
+        let mut compressed_size = read_u32(b, 18) as u64;
+        let uncompressed_size = read_u32(b, 22);
+        let name_len = read_u16(b, 26) as usize;
+        let extra_len = read_u16(b, 28) as usize;
+        let data_start = 30 + name_len + extra_len;
+        if b.len() < data_start {
+            return Ok(None);
+        }
+        if (flags & 0x1) != 0 {
+            return Err(anyhow!("Encrypted SOGS zip entries are not supported"));
+        }
+        let name = String::from_utf8_lossy(&b[30..30 + name_len]).into_owned();
+
+        // ZIP64 extended sizes
+        let mut extra = &b[30 + name_len..data_start];
+        while extra.len() >= 4 {
+            let id = read_u16(extra, 0);
+            let size = (read_u16(extra, 2) as usize).min(extra.len() - 4);
+            if id == 0x0001 {
+                let mut field = &extra[4..4 + size];
+                if uncompressed_size == 0xffffffff && field.len() >= 8 {
+                    field = &field[8..];
+                }
+                if compressed_size == 0xffffffff && field.len() >= 8 {
+                    compressed_size = read_u64(field, 0);
+                }
+            }
+            extra = &extra[4 + size..];
+        }
+
+        let (data_end, entry_end) = if (flags & 0x8) != 0 && compressed_size == 0 {
+            // Sizes follow the data in a data descriptor
+            match self.find_descriptor(data_start) {
+                Some(ends) => ends,
+                None => return Ok(None),
+            }
+        } else {
+            let data_end = data_start + compressed_size as usize;
+            let descriptor = if (flags & 0x8) == 0 {
+                0
+            } else if b.len() >= data_end + 4 && read_u32(b, data_end) == ZIP_DESCRIPTOR_MAGIC {
+                16
+            } else {
+                12
+            };
+            (data_end, data_end + descriptor)
+        };
+        if self.buffer.len() < entry_end {
+            return Ok(None);
+        }
+
+        let raw = &self.buffer[data_start..data_end];
+        let data = match method {
+            0 => raw.to_vec(),
+            8 => decompress_to_vec(raw).map_err(|_| anyhow!("Failed to inflate {} in SOGS zip", name))?,
+            _ => return Err(anyhow!("Unsupported compression method {} in SOGS zip", method)),
+        };
+        self.buffer.drain(..entry_end);
+        self.descriptor_scan = 0;
+        self.started = true;
+        Ok(Some((name, data)))
+    }
 
-    let mut get_file = |name: &str| -> anyhow::Result<Vec<u8>> {
-        file_cache.get(name).cloned().ok_or_else(|| anyhow!("Missing file {name} in cache"))
+    fn find_descriptor(&mut self, data_start: usize) -> Option<(usize, usize)> {
+        let b = &self.buffer;
+        let mut i = data_start + self.descriptor_scan;
+        while i + 24 <= b.len() {
+            if read_u32(b, i) == ZIP_DESCRIPTOR_MAGIC {
+                let size = (i - data_start) as u64;
+                if read_u32(b, i + 8) as u64 == size {
+                    return Some((i, i + 16));
+                }
+                if read_u64(b, i + 8) == size {
+                    return Some((i, i + 24));
+                }
+            }
+            i += 1;
+        }
+        self.descriptor_scan = i - data_start;
+        None
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+// Reads splat count, SH degree and bounds from a SOGS meta.json
+pub fn probe_sogs_meta(bytes: &[u8]) -> Option<SplatFileInfo> {
+    let meta: PcSogsRoot = serde_json::from_slice(bytes).ok()?;
+    let (mins, maxs) = match &meta {
+        PcSogsRoot::V2(meta) => (meta.means.mins, meta.means.maxs),
+        PcSogsRoot::V1(meta) => (meta.means.mins, meta.means.maxs),
     };
+    let (num_splats, max_sh_degree, _) = sogs_properties(meta).ok()?;
+    // Means are stored log-transformed, which preserves ordering
+    let unlog = |v: [f32; 3]| v.map(|x| x.signum() * (x.abs().exp() - 1.0));
+    Some(SplatFileInfo {
+        num_splats: Some(num_splats),
+        max_sh_degree: Some(max_sh_degree),
+        lod_tree: Some(false),
+        bounds_min: Some(unlog(mins)),
+        bounds_max: Some(unlog(maxs)),
+        ..SplatFileInfo::new(SplatFileType::SOGS)
+    })
+}
+
+// A splat property decoded from one or more SOGS images
+enum SogsProperty {
+    Means { files: [String; 2], mins: [f32; 3], maxs: [f32; 3] },
+    Scales { file: String, lookup: [Vec<f32>; 3] },
+    Quats { file: String },
+    Sh0 { file: String, lookup_rgb: [Vec<f32>; 3], lookup_a: Vec<f32> },
+    ShN { files: [String; 2], lookup: Vec<f32>, degree: usize },
+}
 
+fn sogs_properties(meta: PcSogsRoot) -> anyhow::Result<(usize, usize, Vec<SogsProperty>)> {
+    let range_lookup = |min: f32, max: f32, post: fn(f32) -> f32| -> Vec<f32> {
+        (0..256)
+            .map(|i| min + (max - min) * (i as f32 / 255.0))
+            .map(post)
+            .collect()
+    };
     match meta {
-        PcSogsRoot::V2(v2) => decode_v2(v2, splats, &mut get_file),
-        PcSogsRoot::V1(v1) => decode_v1(v1, splats, &mut get_file),
+        PcSogsRoot::V2(meta) => {
+            let _ = meta.version;
+            let num_splats = meta.count;
+            let max_sh_degree = meta.shn.as_ref().map(|shn| shn.bands.min(3) as usize).unwrap_or(0);
+            let scales = meta.scales.codebook.iter().map(|v| v.exp()).collect::<Vec<_>>();
+            let rgb = meta.sh0.codebook.iter().map(|v| SH_C0 * v + 0.5).collect::<Vec<_>>();
+            let [scales_file] = meta.scales.files;
+            let [quats_file] = meta.quats.files;
+            let [sh0_file] = meta.sh0.files;
+            let mut properties = vec![
+                SogsProperty::Means { files: meta.means.files, mins: meta.means.mins, maxs: meta.means.maxs },
+                SogsProperty::Scales { file: scales_file, lookup: [scales.clone(), scales.clone(), scales] },
+                SogsProperty::Quats { file: quats_file },
+                SogsProperty::Sh0 {
+                    file: sh0_file,
+                    lookup_rgb: [rgb.clone(), rgb.clone(), rgb],
+                    lookup_a: (0..256).map(|i| i as f32 / 255.0).collect(),
+                },
+            ];
+            if let Some(shn) = meta.shn {
+                if max_sh_degree > 0 {
+                    properties.push(SogsProperty::ShN { files: shn.files, lookup: shn.codebook, degree: max_sh_degree });
+                }
+            }
+            Ok((num_splats, max_sh_degree, properties))
+        },
+        PcSogsRoot::V1(meta) => {
+            let num_splats = meta.means.shape[0];
+            if meta.quats.encoding.as_deref() != Some("quaternion_packed") {
+                return Err(anyhow!("Unsupported quaternion encoding in SOGS v1"));
+            }
+            let max_sh_degree = match &meta.shn {
+                Some(shn) if shn.shape[1] >= 48 - 3 => 3,
+                Some(shn) if shn.shape[1] >= 27 - 3 => 2,
+                Some(shn) if shn.shape[1] >= 12 - 3 => 1,
+                _ => 0,
+            };
+            let (smin, smax) = (meta.scales.mins, meta.scales.maxs);
+            let (cmin, cmax) = (meta.sh0.mins, meta.sh0.maxs);
+            let rgb_post: fn(f32) -> f32 = |v| SH_C0 * v + 0.5;
+            let [scales_file] = meta.scales.files;
+            let [quats_file] = meta.quats.files;
+            let [sh0_file] = meta.sh0.files;
+            let mut properties = vec![
+                SogsProperty::Means { files: meta.means.files, mins: meta.means.mins, maxs: meta.means.maxs },
+                SogsProperty::Scales {
+                    file: scales_file,
+                    lookup: std::array::from_fn(|d| range_lookup(smin[d], smax[d], |ln| ln.exp())),
+                },
+                SogsProperty::Quats { file: quats_file },
+                SogsProperty::Sh0 {
+                    file: sh0_file,
+                    lookup_rgb: std::array::from_fn(|d| range_lookup(cmin[d], cmax[d], rgb_post)),
+                    lookup_a: range_lookup(cmin[3], cmax[3], |v| 1.0 / (1.0 + (-v).exp())),
+                },
+            ];
+            if let Some(shn) = meta.shn {
+                if max_sh_degree > 0 {
+                    properties.push(SogsProperty::ShN {
+                        files: shn.files,
+                        lookup: range_lookup(shn.mins, shn.maxs, |v| v),
+                        degree: max_sh_degree,
+                    });
+                }
+            }
+            Ok((num_splats, max_sh_degree, properties))
+        },
     }
 }
 
-fn decode_v2<T: SplatReceiver>(
-    meta: PcSogsV2,
-    splats: &mut T,
-    get_file: &mut dyn FnMut(&str) -> anyhow::Result<Vec<u8>>,
-) -> anyhow::Result<()> {
-    let _ = meta.version;
-    let num_splats = meta.count;
-    let max_sh_degree = meta.shn.as_ref().map(|shn| {
-        if shn.bands >= 3 { 3 } else if shn.bands >= 2 { 2 } else if shn.bands >= 1 { 1 } else { 0 }
-    }).unwrap_or(0);
-    splats.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
-
-    let means0 = decode_rgba(&get_file(&meta.means.files[0])?)
-        .context("decode means[0]")?;
-    let means1 = decode_rgba(&get_file(&meta.means.files[1])?)
-        .context("decode means[1]")?;
-    let scales_img = decode_rgba(&get_file(&meta.scales.files[0])?)
-        .context("decode scales")?;
-    let quats_img = decode_rgba(&get_file(&meta.quats.files[0])?)
-        .context("decode quats")?;
-    let sh0_img = decode_rgba(&get_file(&meta.sh0.files[0])?)
-        .context("decode sh0")?;
-
-    let mut center = vec![0.0f32; num_splats * 3];
-    let mut scale = vec![0.0f32; num_splats * 3];
-    let mut quat = vec![0.0f32; num_splats * 4];
-    let mut rgb = vec![0.0f32; num_splats * 3];
-    let mut opacity = vec![0.0f32; num_splats];
-    let mut sh1: Vec<f32> = if max_sh_degree >= 1 { vec![0.0; num_splats * 9] } else { Vec::new() };
-    let mut sh2: Vec<f32> = if max_sh_degree >= 2 { vec![0.0; num_splats * 15] } else { Vec::new() };
-    let mut sh3: Vec<f32> = if max_sh_degree >= 3 { vec![0.0; num_splats * 21] } else { Vec::new() };
-
-    if let Some(shape) = meta.means.shape {
-        let _ = shape;
-    }
-    decode_means(&meta.means.mins, &meta.means.maxs, &means0, &means1, &mut center)?;
-    decode_scales_v2(&meta.scales.codebook, &scales_img, &mut scale)?;
-    decode_quats(&quats_img, &mut quat)?;
-    decode_sh0_v2(&meta.sh0.codebook, &sh0_img, &mut rgb, &mut opacity)?;
-
-    if let Some(shn) = meta.shn {
-        decode_shn_v2(
-            shn,
-            get_file,
-            num_splats,
-            &mut sh1,
-            &mut sh2,
-            &mut sh3,
-        )?;
-    }
-
-    emit_to_receiver(
-        splats,
-        num_splats,
-        max_sh_degree,
-        &center,
-        &scale,
-        &quat,
-        &rgb,
-        &opacity,
-        &sh1,
-        &sh2,
-        &sh3,
-    )
-}
-
-fn decode_v1<T: SplatReceiver>(
-    meta: PcSogsV1,
-    splats: &mut T,
-    get_file: &mut dyn FnMut(&str) -> anyhow::Result<Vec<u8>>,
-) -> anyhow::Result<()> {
-    let num_splats = meta.means.shape[0];
-    if meta.quats.encoding.as_deref() != Some("quaternion_packed") {
-        return Err(anyhow!("Unsupported quaternion encoding in SOGS v1"));
-    }
-
-    let mut max_sh_degree = 0usize;
-    if let Some(shn) = &meta.shn {
-        max_sh_degree = if shn.shape[1] >= 48 - 3 {
-            3
-        } else if shn.shape[1] >= 27 - 3 {
-            2
-        } else if shn.shape[1] >= 12 - 3 {
-            1
-        } else {
-            0
-        };
+impl SogsProperty {
+    fn files(&self) -> Vec<&str> {
+        match self {
+            Self::Means { files, .. } | Self::ShN { files, .. } => files.iter().map(|f| f.as_str()).collect(),
+            Self::Scales { file, .. } | Self::Quats { file } | Self::Sh0 { file, .. } => vec![file.as_str()],
+        }
     }
 
-    splats.init_splats(&SplatInit { num_splats, max_sh_degree, lod_tree: false })?;
-
-    let means0 = decode_rgba(&get_file(&meta.means.files[0])?)
-        .context("decode means[0]")?;
-    let means1 = decode_rgba(&get_file(&meta.means.files[1])?)
-        .context("decode means[1]")?;
-    let scales_img = decode_rgba(&get_file(&meta.scales.files[0])?)
-        .context("decode scales")?;
-    let quats_img = decode_rgba(&get_file(&meta.quats.files[0])?)
-        .context("decode quats")?;
-    let sh0_img = decode_rgba(&get_file(&meta.sh0.files[0])?)
-        .context("decode sh0")?;
-
-    let mut center = vec![0.0f32; num_splats * 3];
-    let mut scale = vec![0.0f32; num_splats * 3];
-    let mut quat = vec![0.0f32; num_splats * 4];
-    let mut rgb = vec![0.0f32; num_splats * 3];
-    let mut opacity = vec![0.0f32; num_splats];
-    let mut sh1: Vec<f32> = if max_sh_degree >= 1 { vec![0.0; num_splats * 9] } else { Vec::new() };
-    let mut sh2: Vec<f32> = if max_sh_degree >= 2 { vec![0.0; num_splats * 15] } else { Vec::new() };
-    let mut sh3: Vec<f32> = if max_sh_degree >= 3 { vec![0.0; num_splats * 21] } else { Vec::new() };
-
-    decode_means(&meta.means.mins, &meta.means.maxs, &means0, &means1, &mut center)?;
-    decode_scales_v1(&meta.scales.mins, &meta.scales.maxs, &scales_img, &mut scale)?;
-    decode_quats(&quats_img, &mut quat)?;
-    decode_sh0_v1(&meta.sh0.mins, &meta.sh0.maxs, &sh0_img, &mut rgb, &mut opacity)?;
-
-    if let Some(shn) = meta.shn {
-        decode_shn_v1(
-            shn,
-            get_file,
-            num_splats,
-            max_sh_degree,
-            &mut sh1,
-            &mut sh2,
-            &mut sh3,
-        )?;
-    }
-
-    emit_to_receiver(
-        splats,
-        num_splats,
-        max_sh_degree,
-        &center,
-        &scale,
-        &quat,
-        &rgb,
-        &opacity,
-        &sh1,
-        &sh2,
-        &sh3,
-    )
+    fn emit<T: SplatReceiver>(&self, splats: &mut T, num_splats: usize, images: &[ImageData]) -> anyhow::Result<()> {
+        let chunk = num_splats.min(MAX_SPLAT_CHUNK);
+        let mut out = vec![0.0f32; chunk * 4];
+        let mut out2 = vec![0.0f32; chunk];
+        let mut sh = match self {
+            Self::ShN { degree, .. } => [
+                vec![0.0f32; chunk * 9],
+                vec![0.0f32; if *degree >= 2 { chunk * 15 } else { 0 }],
+                vec![0.0f32; if *degree >= 3 { chunk * 21 } else { 0 }],
+            ],
+            _ => Default::default(),
+        };
+
+        let mut base = 0usize;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            match self {
+                Self::Means { mins, maxs, .. } => {
+                    decode_means(mins, maxs, &images[0], &images[1], base, &mut out[..count * 3]);
+                    splats.set_center(base, count, &out[..count * 3]);
+                },
+                Self::Scales { lookup, .. } => {
+                    decode_lookup3(lookup, &images[0], base, &mut out[..count * 3]);
+                    splats.set_scale(base, count, &out[..count * 3]);
+                },
+                Self::Quats { .. } => {
+                    decode_quats(&images[0], base, &mut out[..count * 4]);
+                    splats.set_quat(base, count, &out[..count * 4]);
+                },
+                Self::Sh0 { lookup_rgb, lookup_a, .. } => {
+                    decode_lookup3(lookup_rgb, &images[0], base, &mut out[..count * 3]);
+                    for i in 0..count {
+                        out2[i] = lookup_a[images[0].rgba[(base + i) * 4 + 3] as usize];
+                    }
+                    splats.set_rgb(base, count, &out[..count * 3]);
+                    splats.set_opacity(base, count, &out2[..count]);
+                },
+                Self::ShN { lookup, degree, .. } => {
+                    let [sh1, sh2, sh3] = &mut sh;
+                    let sh1 = &mut sh1[..count * 9];
+                    let sh2 = &mut sh2[..if *degree >= 2 { count * 15 } else { 0 }];
+                    let sh3 = &mut sh3[..if *degree >= 3 { count * 21 } else { 0 }];
+                    decode_shn(lookup, *degree, &images[0], &images[1], base, sh1, sh2, sh3)?;
+                    splats.set_sh(base, count, sh1, sh2, sh3);
+                },
+            }
+            base += count;
+        }
+        Ok(())
+    }
 }
 
 fn decode_means(
@@ -327,11 +603,12 @@ fn decode_means(
     maxs: &[f32; 3],
     img0: &ImageData,
     img1: &ImageData,
+    base: usize,
     out_center: &mut [f32],
-) -> anyhow::Result<()> {
-    let num_splats = out_center.len() / 3;
-    for i in 0..num_splats {
-        let i4 = i * 4;
+) {
+    let count = out_center.len() / 3;
+    for i in 0..count {
+        let i4 = (base + i) * 4;
         let fx = (img0.rgba[i4] as u32 + ((img1.rgba[i4] as u32) << 8)) as f32 / 65535.0;
         let fy = (img0.rgba[i4 + 1] as u32 + ((img1.rgba[i4 + 1] as u32) << 8)) as f32 / 65535.0;
         let fz = (img0.rgba[i4 + 2] as u32 + ((img1.rgba[i4 + 2] as u32) << 8)) as f32 / 65535.0;
@@ -346,49 +623,25 @@ fn decode_means(
         out_center[i3 + 1] = y;
         out_center[i3 + 2] = z;
     }
-    Ok(())
 }
 
-fn decode_scales_v2(codebook: &[f32], img: &ImageData, out_scale: &mut [f32]) -> anyhow::Result<()> {
-    let num_splats = out_scale.len() / 3;
-    let lookup: Vec<f32> = codebook.iter().map(|v| v.exp()).collect();
-    for i in 0..num_splats {
-        let i4 = i * 4;
+fn decode_lookup3(lookup: &[Vec<f32>; 3], img: &ImageData, base: usize, out: &mut [f32]) {
+    let count = out.len() / 3;
+    for i in 0..count {
+        let i4 = (base + i) * 4;
         let i3 = i * 3;
-        out_scale[i3] = lookup[img.rgba[i4] as usize];
-        out_scale[i3 + 1] = lookup[img.rgba[i4 + 1] as usize];
-        out_scale[i3 + 2] = lookup[img.rgba[i4 + 2] as usize];
+        out[i3] = lookup[0][img.rgba[i4] as usize];
+        out[i3 + 1] = lookup[1][img.rgba[i4 + 1] as usize];
+        out[i3 + 2] = lookup[2][img.rgba[i4 + 2] as usize];
     }
-    Ok(())
 }
 
-fn decode_scales_v1(mins: &[f32; 3], maxs: &[f32; 3], img: &ImageData, out_scale: &mut [f32]) -> anyhow::Result<()> {
-    let num_splats = out_scale.len() / 3;
-    let build_lookup = |min: f32, max: f32| -> Vec<f32> {
-        (0..256)
-            .map(|i| min + (max - min) * (i as f32 / 255.0))
-            .map(|ln| ln.exp())
-            .collect()
-    };
-    let lx = build_lookup(mins[0], maxs[0]);
-    let ly = build_lookup(mins[1], maxs[1]);
-    let lz = build_lookup(mins[2], maxs[2]);
-    for i in 0..num_splats {
-        let i4 = i * 4;
-        let i3 = i * 3;
-        out_scale[i3] = lx[img.rgba[i4] as usize];
-        out_scale[i3 + 1] = ly[img.rgba[i4 + 1] as usize];
-        out_scale[i3 + 2] = lz[img.rgba[i4 + 2] as usize];
-    }
-    Ok(())
-}
-
-fn decode_quats(img: &ImageData, out_quat: &mut [f32]) -> anyhow::Result<()> {
-    let num_splats = out_quat.len() / 4;
+fn decode_quats(img: &ImageData, base: usize, out_quat: &mut [f32]) {
+    let count = out_quat.len() / 4;
     const SQRT2: f32 = std::f32::consts::SQRT_2;
     let lookup: Vec<f32> = (0..256).map(|i| (i as f32 / 255.0 - 0.5) * SQRT2).collect();
-    for i in 0..num_splats {
-        let i4 = i * 4;
+    for i in 0..count {
+        let i4 = (base + i) * 4;
         let r0 = lookup[img.rgba[i4] as usize];
         let r1 = lookup[img.rgba[i4 + 1] as usize];
         let r2 = lookup[img.rgba[i4 + 2] as usize];
@@ -404,83 +657,44 @@ fn decode_quats(img: &ImageData, out_quat: &mut [f32]) -> anyhow::Result<()> {
         out_quat[o + 2] = quat_z;
         out_quat[o + 3] = quat_w;
     }
-    Ok(())
 }
 
-fn decode_sh0_v2(codebook: &[f32], img: &ImageData, out_rgb: &mut [f32], out_opacity: &mut [f32]) -> anyhow::Result<()> {
-    let num_splats = out_opacity.len();
-    let lookup_rgb: Vec<f32> = codebook.iter().map(|v| SH_C0 * v + 0.5).collect();
-    let lookup_a: Vec<f32> = (0..256).map(|i| i as f32 / 255.0).collect();
-    for i in 0..num_splats {
-        let i4 = i * 4;
-        let i3 = i * 3;
-        out_rgb[i3] = lookup_rgb[img.rgba[i4] as usize];
-        out_rgb[i3 + 1] = lookup_rgb[img.rgba[i4 + 1] as usize];
-        out_rgb[i3 + 2] = lookup_rgb[img.rgba[i4 + 2] as usize];
-        out_opacity[i] = lookup_a[img.rgba[i4 + 3] as usize];
-    }
-    Ok(())
-}
-
-fn decode_sh0_v1(mins: &[f32; 4], maxs: &[f32; 4], img: &ImageData, out_rgb: &mut [f32], out_opacity: &mut [f32]) -> anyhow::Result<()> {
-    let num_splats = out_opacity.len();
-    let build_lookup = |min: f32, max: f32, post: fn(f32) -> f32| -> Vec<f32> {
-        (0..256)
-            .map(|i| min + (max - min) * (i as f32 / 255.0))
-            .map(post)
-            .collect()
-    };
-    let lr = build_lookup(mins[0], maxs[0], |v| SH_C0 * v + 0.5);
-    let lg = build_lookup(mins[1], maxs[1], |v| SH_C0 * v + 0.5);
-    let lb = build_lookup(mins[2], maxs[2], |v| SH_C0 * v + 0.5);
-    let la = build_lookup(mins[3], maxs[3], |v| 1.0 / (1.0 + (-v).exp()));
-    for i in 0..num_splats {
-        let i4 = i * 4;
-        let i3 = i * 3;
-        out_rgb[i3] = lr[img.rgba[i4] as usize];
-        out_rgb[i3 + 1] = lg[img.rgba[i4 + 1] as usize];
-        out_rgb[i3 + 2] = lb[img.rgba[i4 + 2] as usize];
-        out_opacity[i] = la[img.rgba[i4 + 3] as usize];
-    }
-    Ok(())
-}
-
-fn decode_shn_v2(
-    shn: ShNV2,
-    get_file: &mut dyn FnMut(&str) -> anyhow::Result<Vec<u8>>,
-    num_splats: usize,
+#[allow(clippy::too_many_arguments)]
+fn decode_shn(
+    lookup: &[f32],
+    degree: usize,
+    centroids: &ImageData,
+    labels: &ImageData,
+    base: usize,
     sh1: &mut [f32],
     sh2: &mut [f32],
     sh3: &mut [f32],
 ) -> anyhow::Result<()> {
-    let centroids = decode_image(&get_file(&shn.files[0])?)?;
-    let labels = decode_image(&get_file(&shn.files[1])?)?;
-    let lookup = shn.codebook;
-    let use_sh1 = shn.bands >= 1;
-    let use_sh2 = shn.bands >= 2;
-    let use_sh3 = shn.bands >= 3;
+    let count = sh1.len() / 9;
+    let stride = if degree >= 3 { 15 } else if degree >= 2 { 8 } else { 3 };
+    let value = |index: usize| -> anyhow::Result<f32> {
+        let code = *centroids.rgba.get(index).ok_or_else(|| anyhow!("SOGS shN label out of range"))?;
+        lookup.get(code as usize).copied().ok_or_else(|| anyhow!("SOGS shN codebook too small"))
+    };
 
-    for i in 0..num_splats {
-        let i4 = i * 4;
+    for i in 0..count {
+        let i4 = (base + i) * 4;
         let label = labels.rgba[i4] as u16 | ((labels.rgba[i4 + 1] as u16) << 8);
-        let stride = if use_sh3 { 15 } else if use_sh2 { 8 } else { 3 };
         let col = (label & 63) as usize * stride;
         let row = (label >> 6) as usize;
         let offset = row * centroids.width + col;
         for d in 0..3 {
-            if use_sh1 {
-                for k in 0..3 {
-                    sh1[i * 9 + k * 3 + d] = lookup[centroids.rgba[(offset + k) * 4 + d] as usize];
-                }
+            for k in 0..3 {
+                sh1[i * 9 + k * 3 + d] = value((offset + k) * 4 + d)?;
             }
-            if use_sh2 {
+            if degree >= 2 {
                 for k in 0..5 {
-                    sh2[i * 15 + k * 3 + d] = lookup[centroids.rgba[(offset + 3 + k) * 4 + d] as usize];
+                    sh2[i * 15 + k * 3 + d] = value((offset + 3 + k) * 4 + d)?;
                 }
             }
-            if use_sh3 {
+            if degree >= 3 {
                 for k in 0..7 {
-                    sh3[i * 21 + k * 3 + d] = lookup[centroids.rgba[(offset + 8 + k) * 4 + d] as usize];
+                    sh3[i * 21 + k * 3 + d] = value((offset + 8 + k) * 4 + d)?;
                 }
             }
         }
@@ -488,162 +702,408 @@ fn decode_shn_v2(
     Ok(())
 }
 
-fn decode_shn_v1(
-    shn: ShNV1,
-    get_file: &mut dyn FnMut(&str) -> anyhow::Result<Vec<u8>>,
-    num_splats: usize,
-    max_sh_degree: usize,
-    sh1: &mut [f32],
-    sh2: &mut [f32],
-    sh3: &mut [f32],
-) -> anyhow::Result<()> {
-    let centroids = decode_image(&get_file(&shn.files[0])?)?;
-    let labels = decode_image(&get_file(&shn.files[1])?)?;
-    let lookup: Vec<f32> = (0..256)
-        .map(|i| shn.mins + (shn.maxs - shn.mins) * (i as f32 / 255.0))
-        .collect();
+struct ImageData {
+    rgba: Vec<u8>,
+    width: usize,
+    #[allow(dead_code)]
+    height: usize,
+}
 
-    for i in 0..num_splats {
-        let i4 = i * 4;
-        let label = labels.rgba[i4] as u16 | ((labels.rgba[i4 + 1] as u16) << 8);
-        let stride = if max_sh_degree >= 3 { 15 } else if max_sh_degree >= 2 { 8 } else { 3 };
-        let col = (label & 63) as usize * stride;
-        let row = (label >> 6) as usize;
-        let offset = row * centroids.width + col;
-        for d in 0..3 {
-            if max_sh_degree >= 1 {
-                for k in 0..3 {
-                    sh1[i * 9 + k * 3 + d] = lookup[centroids.rgba[(offset + k) * 4 + d] as usize];
-                }
+fn decode_image(bytes: &[u8]) -> anyhow::Result<ImageData> {
+    let img = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .decode()?;
+    let (width, height) = img.dimensions();
+    let rgba: Vec<u8> = match img {
+        DynamicImage::ImageRgba8(i) => i.into_raw(),
+        other => other.to_rgba8().into_raw(),
+    };
+    Ok(ImageData { rgba, width: width as usize, height: height as usize })
+}
+
+pub struct SogsEncoder<T: SplatGetter> {
+    getter: T,
+    max_sh_out: Option<u8>,
+    sh_palette_size: Option<usize>,
+    iterations: usize,
+}
+
+impl<T: SplatGetter> SogsEncoder<T> {
+    pub fn new(getter: T) -> Self {
+        Self {
+            getter,
+            max_sh_out: None,
+            sh_palette_size: None,
+            iterations: 10,
+        }
+    }
+
+    pub fn with_max_sh(mut self, max_sh: u8) -> Self {
+        self.max_sh_out = Some(max_sh.min(3));
+        self
+    }
+
+    pub fn with_sh_palette_size(mut self, sh_palette_size: usize) -> Self {
+        self.sh_palette_size = Some(sh_palette_size.clamp(1, MAX_SHN_PALETTE));
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn encode_to_writer<W: Write + Seek>(mut self, writer: &mut W) -> anyhow::Result<()> {
+        let num_splats = self.getter.num_splats();
+        let sh_src = self.getter.max_sh_degree() as u8;
+        let sh_degree = self.max_sh_out.map(|m| m.min(sh_src)).unwrap_or(sh_src) as usize;
+
+        let mut center = vec![0.0f32; num_splats * 3];
+        let mut opacity = vec![0.0f32; num_splats];
+        let mut rgb = vec![0.0f32; num_splats * 3];
+        let mut scale = vec![0.0f32; num_splats * 3];
+        let mut quat = vec![0.0f32; num_splats * 4];
+        let mut sh1: Vec<f32> = if sh_degree >= 1 { vec![0.0; num_splats * 9] } else { Vec::new() };
+        let mut sh2: Vec<f32> = if sh_degree >= 2 { vec![0.0; num_splats * 15] } else { Vec::new() };
+        let mut sh3: Vec<f32> = if sh_degree >= 3 { vec![0.0; num_splats * 21] } else { Vec::new() };
+
+        let mut base = 0usize;
+        while base < num_splats {
+            let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
+            let [i3, i4] = [base * 3, base * 4];
+            self.getter.get_center(base, count, &mut center[i3..i3 + count * 3]);
+            self.getter.get_opacity(base, count, &mut opacity[base..base + count]);
+            self.getter.get_rgb(base, count, &mut rgb[i3..i3 + count * 3]);
+            self.getter.get_scale(base, count, &mut scale[i3..i3 + count * 3]);
+            self.getter.get_quat(base, count, &mut quat[i4..i4 + count * 4]);
+            if sh_degree >= 1 {
+                self.getter.get_sh1(base, count, &mut sh1[base * 9..(base + count) * 9]);
             }
-            if max_sh_degree >= 2 {
-                for k in 0..5 {
-                    sh2[i * 15 + k * 3 + d] = lookup[centroids.rgba[(offset + 3 + k) * 4 + d] as usize];
-                }
+            if sh_degree >= 2 {
+                self.getter.get_sh2(base, count, &mut sh2[base * 15..(base + count) * 15]);
             }
-            if max_sh_degree >= 3 {
-                for k in 0..7 {
-                    sh3[i * 21 + k * 3 + d] = lookup[centroids.rgba[(offset + 8 + k) * 4 + d] as usize];
-                }
+            if sh_degree >= 3 {
+                self.getter.get_sh3(base, count, &mut sh3[base * 21..(base + count) * 21]);
             }
+            base += count;
         }
+
+        // Images are roughly square with dimensions rounded up to multiples of 4
+        let width = ((num_splats as f32).sqrt().ceil() as usize).div_ceil(4).max(1) * 4;
+        let height = num_splats.div_ceil(width).div_ceil(4).max(1) * 4;
+        let pixels = width * height;
+
+        let mut zip = ZipWriter::new(writer);
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut images: Vec<(String, Vec<u8>)> = Vec::new();
+
+        let (means_l, means_u, mins, maxs) = encode_means(&center, pixels);
+        images.push(("means_l.webp".to_string(), encode_webp(&means_l, width, height)?));
+        images.push(("means_u.webp".to_string(), encode_webp(&means_u, width, height)?));
+
+        let ln_scale: Vec<f32> = scale.iter().map(|s| s.max(1.0e-30).ln()).collect();
+        let scales_codebook = kmeans_1d(&ln_scale, CODEBOOK_SIZE, self.iterations);
+        let mut scales_img = vec![255u8; pixels * 4];
+        for i in 0..num_splats {
+            for d in 0..3 {
+                scales_img[i * 4 + d] = nearest_code(&scales_codebook, ln_scale[i * 3 + d]);
+            }
+        }
+        images.push(("scales.webp".to_string(), encode_webp(&scales_img, width, height)?));
+
+        let quats_img = encode_quats(&quat, pixels);
+        images.push(("quats.webp".to_string(), encode_webp(&quats_img, width, height)?));
+
+        let f_dc: Vec<f32> = rgb.iter().map(|c| (c - 0.5) / SH_C0).collect();
+        let sh0_codebook = kmeans_1d(&f_dc, CODEBOOK_SIZE, self.iterations);
+        let mut sh0_img = vec![0u8; pixels * 4];
+        for i in 0..num_splats {
+            for d in 0..3 {
+                sh0_img[i * 4 + d] = nearest_code(&sh0_codebook, f_dc[i * 3 + d]);
+            }
+            sh0_img[i * 4 + 3] = (opacity[i].clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        images.push(("sh0.webp".to_string(), encode_webp(&sh0_img, width, height)?));
+
+        let shn = if sh_degree >= 1 {
+            let palette_size = self.sh_palette_size.unwrap_or_else(|| default_palette_size(num_splats));
+            let palette = encode_shn(sh_degree, &sh1, &sh2, &sh3, num_splats, pixels, palette_size, self.iterations)?;
+            images.push(("shN_centroids.webp".to_string(), encode_webp(&palette.centroids, palette.width, palette.height)?));
+            images.push(("shN_labels.webp".to_string(), encode_webp(&palette.labels, width, height)?));
+            Some(ShNV2 {
+                files: ["shN_centroids.webp".to_string(), "shN_labels.webp".to_string()],
+                codebook: palette.codebook,
+                bands: sh_degree as u8,
+            })
+        } else {
+            None
+        };
+
+        let meta = PcSogsV2 {
+            version: 2,
+            count: num_splats,
+            means: Means {
+                files: ["means_l.webp".to_string(), "means_u.webp".to_string()],
+                mins,
+                maxs,
+                shape: None,
+            },
+            scales: ScalesV2 { files: ["scales.webp".to_string()], codebook: scales_codebook },
+            quats: Quats { files: ["quats.webp".to_string()], encoding: None },
+            sh0: Sh0V2 { files: ["sh0.webp".to_string()], codebook: sh0_codebook },
+            shn,
+        };
+
+        zip.start_file("meta.json", SimpleFileOptions::default())?;
+        zip.write_all(&serde_json::to_vec(&meta)?)?;
+        for (name, bytes) in images {
+            zip.start_file(name, stored)?;
+            zip.write_all(&bytes)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    pub fn encode(self) -> anyhow::Result<Vec<u8>> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.encode_to_writer(&mut cursor)?;
+        Ok(cursor.into_inner())
     }
-    Ok(())
 }
 
-fn emit_to_receiver<T: SplatReceiver>(
-    splats: &mut T,
-    num_splats: usize,
-    max_sh_degree: usize,
-    center: &[f32],
-    scale: &[f32],
-    quat: &[f32],
-    rgb: &[f32],
-    opacity: &[f32],
+fn default_palette_size(num_splats: usize) -> usize {
+    // Power-of-two multiple of 1024 centroids, scaling with the splat count
+    let blocks = (num_splats / 1024).max(1);
+    let blocks = (1usize << blocks.ilog2()).min(MAX_SHN_PALETTE / 1024);
+    (blocks * 1024).min(num_splats.max(1))
+}
+
+fn encode_means(center: &[f32], pixels: usize) -> (Vec<u8>, Vec<u8>, [f32; 3], [f32; 3]) {
+    let num_splats = center.len() / 3;
+    let log: Vec<f32> = center.iter().map(|x| x.signum() * (x.abs() + 1.0).ln()).collect();
+    let mut mins = [f32::INFINITY; 3];
+    let mut maxs = [f32::NEG_INFINITY; 3];
+    for i in 0..num_splats {
+        for d in 0..3 {
+            mins[d] = mins[d].min(log[i * 3 + d]);
+            maxs[d] = maxs[d].max(log[i * 3 + d]);
+        }
+    }
+    if num_splats == 0 {
+        mins = [0.0; 3];
+        maxs = [0.0; 3];
+    }
+
+    let mut means_l = vec![255u8; pixels * 4];
+    let mut means_u = vec![255u8; pixels * 4];
+    for i in 0..num_splats {
+        for d in 0..3 {
+            let range = maxs[d] - mins[d];
+            let t = if range > 0.0 { (log[i * 3 + d] - mins[d]) / range } else { 0.0 };
+            let value = (t * 65535.0).round().clamp(0.0, 65535.0) as u16;
+            means_l[i * 4 + d] = (value & 0xff) as u8;
+            means_u[i * 4 + d] = (value >> 8) as u8;
+        }
+    }
+    (means_l, means_u, mins, maxs)
+}
+
+fn encode_quats(quat: &[f32], pixels: usize) -> Vec<u8> {
+    const SQRT2: f32 = std::f32::consts::SQRT_2;
+    let num_splats = quat.len() / 4;
+    let mut img = vec![0u8; pixels * 4];
+    let to_byte = |v: f32| ((v / SQRT2 + 0.5) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    for i in 0..num_splats {
+        let [x, y, z, w] = std::array::from_fn(|k| quat[i * 4 + k]);
+        let norm = (x * x + y * y + z * z + w * w).sqrt();
+        let [x, y, z, w] = if norm > 0.0 { [x / norm, y / norm, z / norm, w / norm] } else { [0.0, 0.0, 0.0, 1.0] };
+
+        // Drop the largest component, matching decode_quats() ordering
+        let (order, largest, rest) = [
+            (0, w, [x, y, z]),
+            (1, x, [w, y, z]),
+            (2, y, [w, x, z]),
+            (3, z, [w, x, y]),
+        ].into_iter().max_by(|a, b| a.1.abs().total_cmp(&b.1.abs())).unwrap();
+        let sign = if largest < 0.0 { -1.0 } else { 1.0 };
+
+        let i4 = i * 4;
+        for k in 0..3 {
+            img[i4 + k] = to_byte(sign * rest[k]);
+        }
+        img[i4 + 3] = 252 + order;
+    }
+    img
+}
+
+struct ShNPalette {
+    codebook: Vec<f32>,
+    centroids: Vec<u8>,
+    width: usize,
+    height: usize,
+    labels: Vec<u8>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_shn(
+    sh_degree: usize,
     sh1: &[f32],
     sh2: &[f32],
     sh3: &[f32],
-) -> anyhow::Result<()> {
-    let mut base = 0usize;
-    while base < num_splats {
-        let count = (num_splats - base).min(MAX_SPLAT_CHUNK);
-        let i3 = base * 3;
-        let i4 = base * 4;
-        splats.set_batch(
-            base,
-            count,
-            &SplatProps {
-                center: &center[i3..i3 + count * 3],
-                opacity: &opacity[base..base + count],
-                rgb: &rgb[i3..i3 + count * 3],
-                scale: &scale[i3..i3 + count * 3],
-                quat: &quat[i4..i4 + count * 4],
-                sh1: if max_sh_degree >= 1 { &sh1[base * 9..base * 9 + count * 9] } else { &[] },
-                sh2: if max_sh_degree >= 2 { &sh2[base * 15..base * 15 + count * 15] } else { &[] },
-                sh3: if max_sh_degree >= 3 { &sh3[base * 21..base * 21 + count * 21] } else { &[] },
-                ..Default::default()
-            },
-        );
-        base += count;
+    num_splats: usize,
+    pixels: usize,
+    palette_size: usize,
+    iterations: usize,
+) -> anyhow::Result<ShNPalette> {
+    let stride = match sh_degree { 1 => 3, 2 => 8, _ => 15 };
+    let dims = stride * 3;
+
+    let mut vectors = Vec::with_capacity(num_splats * dims);
+    for i in 0..num_splats {
+        vectors.extend_from_slice(&sh1[i * 9..(i + 1) * 9]);
+        if sh_degree >= 2 {
+            vectors.extend_from_slice(&sh2[i * 15..(i + 1) * 15]);
+        }
+        if sh_degree >= 3 {
+            vectors.extend_from_slice(&sh3[i * 21..(i + 1) * 21]);
+        }
     }
-    splats.finish()
+
+    let num_clusters = palette_size.min(num_splats).max(1);
+    let (centroids, labels) = kmeans_vectors(&vectors, dims, num_clusters, iterations)?;
+    let codebook = kmeans_1d(&centroids, CODEBOOK_SIZE, iterations);
+
+    let centroids_width = SHN_PALETTE_COLUMNS * stride;
+    let centroids_height = num_clusters.div_ceil(SHN_PALETTE_COLUMNS);
+    let mut centroids_img = vec![255u8; centroids_width * centroids_height * 4];
+    for c in 0..num_clusters {
+        let offset = (c >> 6) * centroids_width + (c & 63) * stride;
+        for k in 0..stride {
+            for d in 0..3 {
+                centroids_img[(offset + k) * 4 + d] = nearest_code(&codebook, centroids[c * dims + k * 3 + d]);
+            }
+        }
+    }
+
+    let mut labels_img = vec![0u8; pixels * 4];
+    for (i, &label) in labels.iter().enumerate() {
+        labels_img[i * 4] = (label & 0xff) as u8;
+        labels_img[i * 4 + 1] = (label >> 8) as u8;
+        labels_img[i * 4 + 3] = 255;
+    }
+
+    Ok(ShNPalette {
+        codebook,
+        centroids: centroids_img,
+        width: centroids_width,
+        height: centroids_height,
+        labels: labels_img,
+    })
 }
 
-fn preload_all(
-    meta: &PcSogsRoot,
-    prefix: &str,
-    zip: &mut ZipArchive<Cursor<&[u8]>>,
-    cache: &mut HashMap<String, Vec<u8>>,
-) -> anyhow::Result<()> {
-    match meta {
-        PcSogsRoot::V2(v2) => {
-            for f in v2.means.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v2.scales.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v2.quats.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v2.sh0.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            if let Some(shn) = &v2.shn {
-                for f in shn.files.iter() { preload_file(zip, prefix, cache, f)?; }
+fn kmeans_vectors(vectors: &[f32], dims: usize, num_clusters: usize, iterations: usize) -> anyhow::Result<(Vec<f32>, Vec<u32>)> {
+    let num_vectors = vectors.len() / dims;
+    if num_vectors == 0 {
+        // Nothing to sample from, so an empty array gets zero centroids
+        return Ok((vec![0.0; num_clusters * dims], Vec::new()));
+    }
+    let mut fnc = CpuFindNearestClusters::new();
+    fnc.init_fnc(dims, num_clusters, MAX_SPLAT_CHUNK)?;
+
+    let mut clusters = Vec::with_capacity(num_clusters * dims);
+    for c in 0..num_clusters {
+        let sample = c * num_vectors / num_clusters;
+        clusters.extend_from_slice(&vectors[sample * dims..(sample + 1) * dims]);
+    }
+
+    let mut labels = Vec::with_capacity(num_vectors);
+    let mut sums = vec![0.0f64; num_clusters * dims];
+    let mut counts = vec![0usize; num_clusters];
+
+    for iteration in 0..=iterations {
+        fnc.set_clusters(dims, &clusters)?;
+        labels.clear();
+        for chunk in vectors.chunks(MAX_SPLAT_CHUNK * dims) {
+            labels.extend(fnc.find_nearest_clusters(dims, chunk)?.into_iter().map(|(c, _)| c));
+        }
+        if iteration == iterations {
+            break;
+        }
+
+        sums.fill(0.0);
+        counts.fill(0);
+        for (i, &c) in labels.iter().enumerate() {
+            let c = c as usize;
+            counts[c] += 1;
+            for d in 0..dims {
+                sums[c * dims + d] += vectors[i * dims + d] as f64;
             }
         }
-        PcSogsRoot::V1(v1) => {
-            for f in v1.means.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v1.scales.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v1.quats.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            for f in v1.sh0.files.iter() { preload_file(zip, prefix, cache, f)?; }
-            if let Some(shn) = &v1.shn {
-                for f in shn.files.iter() { preload_file(zip, prefix, cache, f)?; }
+        for c in 0..num_clusters {
+            if counts[c] > 0 {
+                for d in 0..dims {
+                    clusters[c * dims + d] = (sums[c * dims + d] / counts[c] as f64) as f32;
+                }
             }
         }
     }
-    Ok(())
+
+    Ok((clusters, labels))
 }
 
-fn preload_file(
-    zip: &mut ZipArchive<Cursor<&[u8]>>,
-    prefix: &str,
-    cache: &mut HashMap<String, Vec<u8>>,
-    name: &str,
-) -> anyhow::Result<()> {
-    if cache.contains_key(name) {
-        return Ok(());
+fn kmeans_1d(values: &[f32], k: usize, iterations: usize) -> Vec<f32> {
+    let mut sorted: Vec<f32> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    if sorted.is_empty() {
+        return vec![0.0; k];
     }
-    let full = format!("{}{}", prefix, name);
-    let mut out = Vec::new();
-    {
-        if let Ok(mut entry) = zip.by_name(&full) {
-            std::io::copy(&mut entry, &mut out)?;
-            cache.insert(name.to_string(), out);
-            return Ok(());
+    sorted.sort_by(f32::total_cmp);
+
+    // Seed from quantiles, then run Lloyd iterations exploiting sorted order
+    let mut centroids: Vec<f32> = (0..k)
+        .map(|c| sorted[((2 * c + 1) * sorted.len() / (2 * k)).min(sorted.len() - 1)])
+        .collect();
+    let mut sums = vec![0.0f64; k];
+    let mut counts = vec![0usize; k];
+
+    for _ in 0..iterations {
+        sums.fill(0.0);
+        counts.fill(0);
+        let mut c = 0;
+        for &v in sorted.iter() {
+            while c + 1 < k && (centroids[c + 1] - v).abs() <= (v - centroids[c]).abs() {
+                c += 1;
+            }
+            sums[c] += v as f64;
+            counts[c] += 1;
+        }
+        for c in 0..k {
+            if counts[c] > 0 {
+                centroids[c] = (sums[c] / counts[c] as f64) as f32;
+            }
         }
+        centroids.sort_by(f32::total_cmp);
     }
-    let mut entry = zip.by_name(name).context(format!("Missing file {name} in SOGS zip"))?;
-    std::io::copy(&mut entry, &mut out)?;
-    cache.insert(name.to_string(), out);
-    Ok(())
+    centroids
 }
 
-struct ImageData {
-    rgba: Vec<u8>,
-    width: usize,
-    #[allow(dead_code)]
-    height: usize,
-}
-
-fn decode_rgba(bytes: &[u8]) -> anyhow::Result<ImageData> {
-    let img = decode_image(bytes)?;
-    Ok(img)
+fn nearest_code(codebook: &[f32], value: f32) -> u8 {
+    let index = codebook.partition_point(|&c| c < value);
+    if index == 0 {
+        return 0;
+    }
+    if index >= codebook.len() {
+        return (codebook.len() - 1) as u8;
+    }
+    if (value - codebook[index - 1]) <= (codebook[index] - value) {
+        (index - 1) as u8
+    } else {
+        index as u8
+    }
 }
 
-fn decode_image(bytes: &[u8]) -> anyhow::Result<ImageData> {
-    let img = ImageReader::new(Cursor::new(bytes))
-        .with_guessed_format()?
-        .decode()?;
-    let (width, height) = img.dimensions();
-    let rgba: Vec<u8> = match img {
-        DynamicImage::ImageRgba8(i) => i.into_raw(),
-        other => other.to_rgba8().into_raw(),
-    };
-    Ok(ImageData { rgba, width: width as usize, height: height as usize })
+fn encode_webp(rgba: &[u8], width: usize, height: usize) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    WebPEncoder::new_lossless(&mut out).encode(rgba, width as u32, height as u32, ExtendedColorType::Rgba8)?;
+    Ok(out)
 }
-
-
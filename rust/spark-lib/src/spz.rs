@@ -5,7 +5,8 @@ use miniz_oxide::inflate::core::inflate_flags::{
 };
 use miniz_oxide::inflate::TINFLStatus;
 
-use crate::decoder::{ChunkReceiver, SetSplatEncoding, SplatGetter, SplatInit, SplatReceiver};
+use crate::decoder::{ChunkReceiver, SetSplatEncoding, SplatFileType, SplatGetter, SplatInit, SplatReceiver};
+use crate::probe::SplatFileInfo;
 use miniz_oxide::deflate::compress_to_vec;
 
 pub const SPZ_MAGIC: u32 = 0x5053474e; // "NGSP"
@@ -15,6 +16,27 @@ const MAX_SPLAT_CHUNK: usize = 65536;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SpzDecoderStage { Centers, Alphas, Rgb, Scales, Quats, Sh, Extension, ChildCounts, ChildStarts, Done }
 
+// Reads the 16-byte header at the start of the inflated SPZ stream
+pub fn probe_spz(inflated: &[u8]) -> Option<SplatFileInfo> {
+    if inflated.len() < 16 {
+        return Some(SplatFileInfo::needs(Some(SplatFileType::SPZ), 16 - inflated.len()));
+    }
+    let version = read_u32_le(&inflated[4..8]);
+    if read_u32_le(&inflated[0..4]) != SPZ_MAGIC || !(1..=3).contains(&version) {
+        return None;
+    }
+    let flags = inflated[14];
+    let lod_tree = flags & 0x80 != 0;
+    Some(SplatFileInfo {
+        num_splats: Some(read_u32_le(&inflated[8..12]) as usize),
+        max_sh_degree: Some(inflated[12] as usize),
+        lod_tree: Some(lod_tree),
+        antialias: Some(flags & 0x01 != 0),
+        encoding: lod_tree.then(|| SetSplatEncoding { lod_opacity: Some(true), ..Default::default() }),
+        ..SplatFileInfo::new(SplatFileType::SPZ)
+    })
+}
+
 pub struct SpzDecoder<T: SplatReceiver> {
     splats: T,
     decompressor: DecompressorOxide,
@@ -630,10 +652,11 @@ pub struct SpzEncoder<T: SplatGetter> {
     getter: T,
     max_sh_out: Option<usize>,
     fractional_bits: u8,
+    version: u32,
 }
 
 impl<T: SplatGetter> SpzEncoder<T> {
-    pub fn new(getter: T) -> Self { Self { getter, max_sh_out: None, fractional_bits: 12 } }
+    pub fn new(getter: T) -> Self { Self { getter, max_sh_out: None, fractional_bits: 12, version: 2 } }
 
     pub fn with_max_sh(mut self, max_sh: usize) -> Self {
         self.max_sh_out = Some(max_sh.min(3));
@@ -645,6 +668,12 @@ impl<T: SplatGetter> SpzEncoder<T> {
         self
     }
 
+    // Version 2 writes 3-byte quaternions, version 3 smallest-three 32-bit quaternions
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version.clamp(2, 3);
+        self
+    }
+
     pub fn encode(mut self) -> anyhow::Result<Vec<u8>> {
         let num_splats = self.getter.num_splats();
         let sh_src = self.getter.max_sh_degree();
@@ -652,7 +681,7 @@ impl<T: SplatGetter> SpzEncoder<T> {
         let fractional_bits = self.fractional_bits;
         let flag_antialias = self.getter.flag_antialias();
         let lod_tree = self.getter.has_lod_tree();
-        let version = 2u32; // fixed for now; encoder writes v2 layout by default
+        let version = self.version;
 
         // Header (16 bytes)
         let mut raw = Vec::with_capacity(16 + num_splats * 64); // rough guess
@@ -762,23 +791,27 @@ impl<T: SplatGetter> SpzEncoder<T> {
                 ensure_len(&mut f32_buf, count * 4);
                 self.getter.get_quat(base, count, &mut f32_buf[..count * 4]);
                 for i in 0..count {
-                    let q = &mut f32_buf[i * 4..i * 4 + 4];
-                    // ensure unit and handle sign: choose largest index
+                    let q = &f32_buf[i * 4..i * 4 + 4];
+                    let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+                    let q: [f32; 4] = if norm > 0.0 { std::array::from_fn(|k| q[k] / norm) } else { [0.0, 0.0, 0.0, 1.0] };
+                    // Drop the largest component, flipping sign so it is implicitly positive
                     let (idx, _) = (0..4)
                         .map(|k| (k, q[k].abs()))
-                        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                        .max_by(|a, b| a.1.total_cmp(&b.1))
                         .unwrap();
-                    let mut comp: u32 = (idx as u32) << 30;
+                    let flip = q[idx] < 0.0;
                     let max_value: f32 = std::f32::consts::FRAC_1_SQRT_2;
                     let value_mask: u32 = (1u32 << 9) - 1;
-                    for k in (0..4).rev() {
+                    // Decoder consumes components from index 3 down, lowest bits first
+                    let mut comp: u32 = 0;
+                    for (k, &v) in q.iter().enumerate() {
                         if k == idx { continue; }
-                        let mut v = q[k].clamp(-max_value, max_value);
-                        let sign = v.is_sign_negative();
-                        if sign { v = -v; }
-                        let mag = (v / max_value * value_mask as f32).round().clamp(0.0, value_mask as f32) as u32;
+                        let v = if flip { -v } else { v };
+                        let sign = v < 0.0;
+                        let mag = (v.abs() / max_value * value_mask as f32).round().clamp(0.0, value_mask as f32) as u32;
                         comp = (comp << 10) | ((sign as u32) << 9) | mag;
                     }
+                    comp |= (idx as u32) << 30;
                     raw.extend_from_slice(&comp.to_le_bytes());
                 }
                 base += count;
@@ -102,6 +102,14 @@ impl SymMat3 {
         self.1 = other.1.mul_add(Vec2::splat(weight), self.1);
     }
 
+    pub fn mul_vec3(&self, v: Vec3A) -> Vec3A {
+        Vec3A::new(
+            self.xx() * v.x + self.xy() * v.y + self.xz() * v.z,
+            self.xy() * v.x + self.yy() * v.y + self.yz() * v.z,
+            self.xz() * v.x + self.yz() * v.y + self.zz() * v.z,
+        )
+    }
+
     pub fn new_average(a: &Self, b: &Self) -> Self {
         Self(
             a.0.mul_add(Vec4::splat(0.5), b.0 * 0.5),
@@ -2,7 +2,7 @@ use ahash::AHashMap;
 use glam::I64Vec3;
 use smallvec::{smallvec, SmallVec};
 
-use crate::{ordering, tsplat::{Tsplat, TsplatArray}};
+use crate::{bhatt_lod::flatten_merged, ordering, tsplat::{Tsplat, TsplatArray}};
 
 const CHUNK_SIZE: usize = 65536;
 // const CHUNK_LEVELS: i16 = 2;
@@ -96,6 +96,9 @@ pub fn compute_lod_tree<SA: TsplatArray>(splats: &mut SA, lod_base: f32, merge_f
                 let merge_step = if merge_filter { step } else { 0.0 };
                 let indices: SmallVec<[usize; 4]> = (start..end).map(|i| active[i].0).collect();
                 let merged = splats.new_merged(&indices, merge_step);
+                if splats.is_planar() {
+                    flatten_merged(splats, merged, &indices, merge_step);
+                }
                 next_active.push(merged);
                 output.push((merged, indices));
                 merged_count += 1;
@@ -0,0 +1,225 @@
+use glam::{Affine3A, Mat3A, Quat, Vec3A};
+
+use crate::symmat3::SymMat3;
+use crate::tsplat::{Tsplat, TsplatArray, TsplatMut};
+
+// Tolerance for treating the linear part as rotation * uniform scale
+const SIMILARITY_EPSILON: f32 = 1.0e-4;
+const SH_SAMPLE_DIRECTIONS: usize = 32;
+// Linear parts with a smaller absolute determinant are treated as singular
+pub const MIN_TRANSFORM_DETERMINANT: f32 = 1.0e-12;
+
+// Affine transform decomposed for applying to splats: centers take the full
+// transform, covariances the linear part, and SH coefficients the rotation
+// from its polar decomposition. Linear parts with negative determinant
+// mirror the scene, which quaternions can't express, so orientations are
+// rotated by the negated (proper) rotation instead. A Gaussian is symmetric
+// under point reflection about its center, so its shape comes out the same,
+// and the SH take the reflection as a sign flip on odd bands.
+#[derive(Clone, Debug)]
+pub struct SplatTransform {
+    pub affine: Affine3A,
+    // Proper rotation applied to orientations and SH
+    rotation: Quat,
+    // Uniform scale when the linear part is a similarity, otherwise
+    // covariances are transformed and re-diagonalized
+    similarity_scale: Option<f32>,
+    mirror: bool,
+    // Inverse transpose of the linear part, for surfel normals
+    normal_matrix: Mat3A,
+    // Row-major (2l+1)^2 coefficient maps for SH bands 1..3
+    sh_bands: [Vec<f32>; 3],
+}
+
+// True if the transform can be applied to splats, see SplatTransform::new
+pub fn is_invertible(affine: &Affine3A) -> bool {
+    affine.matrix3.determinant().abs() >= MIN_TRANSFORM_DETERMINANT
+}
+
+impl SplatTransform {
+    // The linear part must be invertible: singular ones flatten the scene
+    // and leave the polar rotation and normal matrix NaN
+    pub fn new(affine: Affine3A) -> Self {
+        assert!(is_invertible(&affine), "Singular splat transform: {:?}", affine);
+        let linear = affine.matrix3;
+        let determinant = linear.determinant();
+        let mirror = determinant < 0.0;
+
+        let orthogonal = polar_rotation(linear);
+        let rotation_matrix = if mirror { -orthogonal } else { orthogonal };
+        let rotation = Quat::from_mat3a(&rotation_matrix).normalize();
+
+        let scale = determinant.abs().cbrt();
+        let gram = linear.transpose() * linear;
+        let is_similarity = scale > 0.0 && (0..3).all(|i| (0..3).all(|j| {
+            let expected = if i == j { scale * scale } else { 0.0 };
+            (gram.col(i)[j] - expected).abs() <= SIMILARITY_EPSILON * scale * scale
+        }));
+
+        Self {
+            affine,
+            rotation,
+            similarity_scale: is_similarity.then_some(scale),
+            mirror,
+            normal_matrix: linear.inverse().transpose(),
+            sh_bands: std::array::from_fn(|band| sh_band_matrix(band + 1, orthogonal)),
+        }
+    }
+
+    pub fn is_mirror(&self) -> bool {
+        self.mirror
+    }
+
+    pub fn transform_center(&self, center: Vec3A) -> Vec3A {
+        self.affine.transform_point3a(center)
+    }
+
+    pub fn transform_scales_quaternion(&self, scales: Vec3A, quaternion: Quat) -> (Vec3A, Quat) {
+        if let Some(scale) = self.similarity_scale {
+            return (scales * scale, (self.rotation * quaternion).normalize());
+        }
+
+        // Sigma' = M R S^2 R^T M^T, from the transformed scaled axes
+        let rotation = Mat3A::from_quat(quaternion);
+        let axes = [rotation.x_axis * scales.x, rotation.y_axis * scales.y, rotation.z_axis * scales.z]
+            .map(|axis| self.affine.matrix3 * axis);
+        let entry = |i: usize, j: usize| axes.iter().map(|axis| axis[i] * axis[j]).sum::<f32>();
+        let covariance = SymMat3::new([entry(0, 0), entry(1, 1), entry(2, 2), entry(0, 1), entry(0, 2), entry(1, 2)]);
+
+        let (values, vectors) = covariance.positive_eigens();
+        let scales = Vec3A::from_array(values.map(|v| v.max(0.0).sqrt()));
+        let basis = Mat3A::from_cols(vectors[0], vectors[1], vectors[2]);
+        (scales, Quat::from_mat3a(&basis).normalize())
+    }
+
+    pub fn transform_normal(&self, normal: Vec3A) -> Vec3A {
+        (self.normal_matrix * normal).normalize_or_zero()
+    }
+
+    // Rotates SH coefficients for bands 1..=degree in place, laid out as
+    // sh[k * 3 + channel] like the SH1/SH2/SH3 arrays
+    pub fn transform_sh(&self, degree: usize, sh: &mut [f32]) {
+        let band = &self.sh_bands[degree - 1];
+        let n = 2 * degree + 1;
+        for channel in 0..3 {
+            let input: [f32; 7] = std::array::from_fn(|k| if k < n { sh[k * 3 + channel] } else { 0.0 });
+            for k in 0..n {
+                sh[k * 3 + channel] = (0..n).map(|j| band[k * n + j] * input[j]).sum();
+            }
+        }
+    }
+}
+
+// Applies centers, scales and orientations to every splat, leaving SH and
+// other per-array data to the caller
+pub fn transform_splats<T: TsplatArray>(splats: &mut T, transform: &SplatTransform) {
+    let planar = splats.is_planar();
+    for index in 0..splats.len() {
+        let mut splat = splats.get_mut(index);
+        let (mut scales, quaternion) = transform.transform_scales_quaternion(splat.scales(), splat.quaternion());
+        if planar {
+            // Eigenvalues sort descending, so the flat axis stays last but
+            // picks up rounding noise from a non-similarity transform
+            scales.z = 0.0;
+        }
+        splat.set_center(transform.transform_center(splat.center()));
+        splat.set_scales(scales);
+        splat.set_quaternion(quaternion);
+    }
+}
+
+// Orthogonal factor Q of the polar decomposition M = Q P, which is the
+// closest rotation (or rotation with mirror) to M
+fn polar_rotation(linear: Mat3A) -> Mat3A {
+    let mut q = linear;
+    for _ in 0..32 {
+        let next = (q + q.inverse().transpose()) * 0.5;
+        let delta = (next - q).to_cols_array().iter().map(|v| v.abs()).fold(0.0, f32::max);
+        q = next;
+        if delta < 1.0e-7 {
+            break;
+        }
+    }
+    q
+}
+
+// Real SH basis functions of one band, matching the renderer's evaluation
+fn sh_basis(degree: usize, d: [f64; 3]) -> [f64; 7] {
+    let [x, y, z] = d;
+    let (xx, yy, zz) = (x * x, y * y, z * z);
+    match degree {
+        1 => [-0.4886025119029199 * y, 0.4886025119029199 * z, -0.4886025119029199 * x, 0.0, 0.0, 0.0, 0.0],
+        2 => [
+            1.0925484305920792 * x * y,
+            -1.0925484305920792 * y * z,
+            0.31539156525252005 * (2.0 * zz - xx - yy),
+            -1.0925484305920792 * x * z,
+            0.5462742152960396 * (xx - yy),
+            0.0,
+            0.0,
+        ],
+        3 => [
+            -0.5900435899266435 * y * (3.0 * xx - yy),
+            2.890611442640554 * x * y * z,
+            -0.4570457994644658 * y * (4.0 * zz - xx - yy),
+            0.3731763325901154 * z * (2.0 * zz - 3.0 * xx - 3.0 * yy),
+            -0.4570457994644658 * x * (4.0 * zz - xx - yy),
+            1.445305721320277 * z * (xx - yy),
+            -0.5900435899266435 * x * (xx - 3.0 * yy),
+        ],
+        _ => unreachable!(),
+    }
+}
+
+// Wigner-D matrix of one band in the renderer's SH basis: maps coefficients
+// c to c' with f'(d) = f(Q^T d). Each band is closed under orthogonal maps,
+// so solving at sample directions by least squares gives it exactly, and
+// works the same for rotations and mirrors.
+fn sh_band_matrix(degree: usize, orthogonal: Mat3A) -> Vec<f32> {
+    let n = 2 * degree + 1;
+    let inverse = orthogonal.transpose();
+
+    // Normal equations A^T A X = A^T B with A[i][m] = Y_m(d_i), B[i][m] = Y_m(Q^T d_i)
+    let mut ata = vec![0.0f64; n * n];
+    let mut atb = vec![0.0f64; n * n];
+    for i in 0..SH_SAMPLE_DIRECTIONS {
+        // Fibonacci sphere
+        let z = 1.0 - (2.0 * i as f64 + 1.0) / SH_SAMPLE_DIRECTIONS as f64;
+        let r = (1.0 - z * z).sqrt();
+        let phi = i as f64 * std::f64::consts::PI * (3.0 - 5.0f64.sqrt());
+        let d = [r * phi.cos(), r * phi.sin(), z];
+        let rotated = inverse * Vec3A::new(d[0] as f32, d[1] as f32, d[2] as f32);
+        let a = sh_basis(degree, d);
+        let b = sh_basis(degree, rotated.to_array().map(|v| v as f64));
+        for row in 0..n {
+            for col in 0..n {
+                ata[row * n + col] += a[row] * a[col];
+                atb[row * n + col] += a[row] * b[col];
+            }
+        }
+    }
+
+    // Gauss-Jordan elimination with partial pivoting
+    for pivot in 0..n {
+        let best = (pivot..n).max_by(|&r, &s| ata[r * n + pivot].abs().total_cmp(&ata[s * n + pivot].abs())).unwrap();
+        for col in 0..n {
+            ata.swap(pivot * n + col, best * n + col);
+            atb.swap(pivot * n + col, best * n + col);
+        }
+        let inv = 1.0 / ata[pivot * n + pivot];
+        for col in 0..n {
+            ata[pivot * n + col] *= inv;
+            atb[pivot * n + col] *= inv;
+        }
+        for row in 0..n {
+            let factor = ata[row * n + pivot];
+            if row != pivot && factor != 0.0 {
+                for col in 0..n {
+                    ata[row * n + col] -= factor * ata[pivot * n + col];
+                    atb[row * n + col] -= factor * atb[pivot * n + col];
+                }
+            }
+        }
+    }
+    atb.into_iter().map(|v| v as f32).collect()
+}
@@ -1,7 +1,8 @@
-use glam::{I64Vec3, Quat, Vec3A};
+use glam::{Affine3A, I64Vec3, Quat, Vec3A};
 use ordered_float::OrderedFloat;
 use smallvec::SmallVec;
 
+use crate::decoder::{SplatExtraInfo, SplatExtraMerge};
 use crate::symmat3::SymMat3;
 
 pub trait Tsplat: std::fmt::Debug {
@@ -97,6 +98,9 @@ pub trait TsplatArray {
 
     fn similarity(&self, a: usize, b: usize) -> f32;
 
+    // Planar splats (surfels) must stay planar when merged
+    fn is_planar(&self) -> bool { false }
+
     fn retain<F: (FnMut(Self::SplatMut<'_>) -> bool)>(&mut self, f: F);
     fn retain_children<F: (FnMut(Self::SplatMut<'_>, &[usize]) -> bool)>(&mut self, f: F);
     fn permute(&mut self, index_map: &[usize]);
@@ -104,6 +108,9 @@ pub trait TsplatArray {
     fn new_from_index_map(&mut self, index_map: &[usize]) -> Self;
     fn clone_subset(&self, start: usize, count: usize) -> Self;
 
+    // Transforms centers, orientations, scales and SH, see SplatTransform
+    fn apply_transform(&mut self, transform: Affine3A);
+
     fn sort_by<F: (Fn(Self::Splat<'_>) -> f32)>(&mut self, f: F) {
         let mut index_map = Vec::with_capacity(self.len());
         index_map.extend(0..self.len());
@@ -161,15 +168,98 @@ pub fn apply_swaps<T>(data: &mut [T], swaps: &[(usize, usize)]) {
     }
 }
 
+// Flat storage for one user-defined per-splat channel, values[index * dims + d],
+// in f64 so integer IDs and timestamps stay exact
+#[derive(Clone, Debug)]
+pub struct SplatExtra {
+    pub info: SplatExtraInfo,
+    pub values: Vec<f64>,
+}
+
+impl SplatExtra {
+    pub fn new(info: SplatExtraInfo, num_splats: usize) -> Self {
+        let values = vec![0.0; num_splats * info.dims];
+        Self { info, values }
+    }
+
+    pub fn get(&self, index: usize) -> &[f64] {
+        let dims = self.info.dims;
+        &self.values[index * dims..(index + 1) * dims]
+    }
+
+    pub fn push_default(&mut self) {
+        self.values.resize(self.values.len() + self.info.dims, 0.0);
+    }
+
+    // Appends the merge of indices according to info.merge, with weights summing to 1
+    pub fn push_merged(&mut self, indices: &[usize], weights: &[f32]) {
+        let dims = self.info.dims;
+        let merged: SmallVec<[f64; 8]> = (0..dims).map(|d| {
+            let value = |i: usize| self.values[indices[i] * dims + d];
+            match self.info.merge {
+                SplatExtraMerge::Mean => (0..indices.len()).map(|i| weights[i] as f64 * value(i)).sum(),
+                SplatExtraMerge::Min => (0..indices.len()).map(value).fold(f64::INFINITY, f64::min),
+                SplatExtraMerge::Max => (0..indices.len()).map(value).fold(f64::NEG_INFINITY, f64::max),
+                SplatExtraMerge::Vote => {
+                    let votes = |v: f64| (0..indices.len()).filter(|&j| value(j) == v).map(|j| weights[j]).sum::<f32>();
+                    (0..indices.len()).map(value)
+                        .max_by_key(|&v| OrderedFloat(votes(v)))
+                        .unwrap_or(0.0)
+                },
+            }
+        }).collect();
+        self.values.extend(merged);
+    }
+
+    pub fn retain(&mut self, keep: &[bool]) {
+        let dims = self.info.dims;
+        let mut write = 0;
+        for (index, &kept) in keep.iter().enumerate() {
+            if kept {
+                self.values.copy_within(index * dims..(index + 1) * dims, write * dims);
+                write += 1;
+            }
+        }
+        self.values.truncate(write * dims);
+    }
+
+    pub fn apply_swaps(&mut self, swaps: &[(usize, usize)]) {
+        let dims = self.info.dims;
+        for &(a, b) in swaps {
+            for d in 0..dims {
+                self.values.swap(a * dims + d, b * dims + d);
+            }
+        }
+    }
+
+    pub fn truncate(&mut self, count: usize) {
+        self.values.truncate(count * self.info.dims);
+    }
+
+    pub fn new_from_index_map(&self, index_map: &[usize]) -> Self {
+        let values = index_map.iter().flat_map(|&i| self.get(i).iter().copied()).collect();
+        Self { info: self.info.clone(), values }
+    }
+
+    pub fn clone_subset(&self, start: usize, count: usize) -> Self {
+        let dims = self.info.dims;
+        Self { info: self.info.clone(), values: self.values[start * dims..(start + count) * dims].to_vec() }
+    }
+}
+
 pub fn bhattacharyya_distance(a: &impl Tsplat, b: &impl Tsplat) -> f32 {
     let cov_a = SymMat3::new_scale_quaternion(a.scales(), a.quaternion());
     let cov_b = SymMat3::new_scale_quaternion(b.scales(), b.quaternion());
-    let sigma = SymMat3::new_average(&cov_a, &cov_b);
+    bhattacharyya_distance_cov(a.center(), &cov_a, b.center(), &cov_b)
+}
+
+fn bhattacharyya_distance_cov(center_a: Vec3A, cov_a: &SymMat3, center_b: Vec3A, cov_b: &SymMat3) -> f32 {
+    let sigma = SymMat3::new_average(cov_a, cov_b);
     let Some(inv) = sigma.inverse() else {
         return 0.0;
     };
 
-    let delta = b.center() - a.center();
+    let delta = center_b - center_a;
     let quad = inv.xx() * delta.x * delta.x
         + inv.yy() * delta.y * delta.y
         + inv.zz() * delta.z * delta.z
@@ -203,3 +293,33 @@ pub fn similarity_metric(a: &impl Tsplat, b: &impl Tsplat) -> f32 {
     }
     metric
 }
+
+// Surfel covariances are singular, so give them a thickness relative to
+// their smaller in-plane scale when comparing
+const PLANAR_THICKNESS: f32 = 0.1;
+
+pub fn planar_normal(splat: &impl Tsplat) -> Vec3A {
+    splat.quaternion() * Vec3A::Z
+}
+
+fn thickened_covariance(splat: &impl Tsplat) -> SymMat3 {
+    let scales = splat.scales();
+    let thickness = PLANAR_THICKNESS * scales.x.min(scales.y);
+    SymMat3::new_scale_quaternion(scales.with_z(scales.z.max(thickness)), splat.quaternion())
+}
+
+pub fn planar_similarity_metric(a: &impl Tsplat, b: &impl Tsplat) -> f32 {
+    let cov_a = thickened_covariance(a);
+    let cov_b = thickened_covariance(b);
+    let spatial = (-bhattacharyya_distance_cov(a.center(), &cov_a, b.center(), &cov_b)).exp();
+
+    let color_delta2 = (a.rgb() - b.rgb()).length_squared();
+    // Surfels are double-sided, so only the alignment of the planes matters
+    let alignment = planar_normal(a).dot(planar_normal(b)).abs();
+
+    let metric = spatial * (-color_delta2).exp() * alignment;
+    if metric.is_nan() {
+        return 0.0;
+    }
+    metric
+}
@@ -600,3 +600,12 @@ pub fn decode_rad_header(bytes: Uint8Array) -> Result<JsValue, JsValue> {
         Ok(JsValue::null())
     }
 }
+
+#[wasm_bindgen]
+pub fn probe_splat_file(bytes: Uint8Array) -> Result<JsValue, JsValue> {
+    let bytes = bytes.to_vec();
+    match spark_lib::probe::probe(&bytes) {
+        Some(info) => Ok(serde_wasm_bindgen::to_value(&info)?),
+        None => Ok(JsValue::null()),
+    }
+}
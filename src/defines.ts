@@ -81,12 +81,25 @@ export type RadMeta = {
   maxSh?: number;
   lodTree?: boolean;
   chunkSize?: number;
+  extras?: {
+    name: string;
+    dims: number;
+    encoding?: "f32" | "f16" | "u8" | "u16" | "u32";
+    merge?: "mean" | "vote" | "min" | "max";
+  }[];
   chunks: {
     offset: number;
     bytes: number;
     base?: number;
     count?: number;
     filename?: string;
+    boundsMin?: [number, number, number];
+    boundsMax?: [number, number, number];
+    minFeatureSize?: number;
+    maxFeatureSize?: number;
+    minDepth?: number;
+    maxDepth?: number;
+    crc32?: number;
   }[];
   splatEncoding?: SplatEncoding;
 };